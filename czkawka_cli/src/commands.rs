@@ -3,8 +3,16 @@ use std::path::PathBuf;
 use image_hasher::{FilterType, HashAlg};
 use structopt::StructOpt;
 
+use czkawka_core::common_apply::ApplyAction;
+use czkawka_core::common_cache_manager::CacheTool;
 use czkawka_core::common_dir_traversal::CheckingMethod;
-use czkawka_core::duplicate::{DeleteMethod, HashType};
+use czkawka_core::common_export::ExportFormat;
+use czkawka_core::common_io_scheduler::IoScheduler;
+use czkawka_core::common_journal::{default_quarantine_folder, Journal};
+use czkawka_core::common_keep_policy::KeepPolicy;
+use czkawka_core::common_remove::RemovalMethod;
+use czkawka_core::common_watch::WatchTool;
+use czkawka_core::duplicate::{DeleteMethod, HashType, PartialHashStage, SymlinkType, VerificationMethod};
 use czkawka_core::same_music::MusicSimilarity;
 use czkawka_core::similar_images::SimilarityPreset;
 
@@ -29,13 +37,39 @@ pub enum Commands {
         allowed_extensions: AllowedExtensions,
         #[structopt(short, long, default_value = "HASH", parse(try_from_str = parse_checking_method), help = "Search method (NAME, SIZE, HASH)", long_help = "Methods to search files.\nNAME - Fast but but rarely usable,\nSIZE - Fast but not accurate, checking by the file's size,\nHASH - The slowest method, checking by the hash of the entire file")]
         search_method: CheckingMethod,
-        #[structopt(short = "D", long, default_value = "NONE", parse(try_from_str = parse_delete_method), help = "Delete method (AEN, AEO, ON, OO, HARD)", long_help = "Methods to delete the files.\nAEN - All files except the newest,\nAEO - All files except the oldest,\nON - Only 1 file, the newest,\nOO - Only 1 file, the oldest\nHARD - create hard link\nNONE - not delete files")]
+        #[structopt(short = "D", long, default_value = "NONE", parse(try_from_str = parse_delete_method), help = "Delete method (AEN, AEO, ON, OO, HARD, SYMLINK, SYMLINK-RELATIVE, REFLINK)", long_help = "Methods to delete the files.\nAEN - All files except the newest,\nAEO - All files except the oldest,\nON - Only 1 file, the newest,\nOO - Only 1 file, the oldest\nHARD - create hard link\nSYMLINK - create symbolic link with absolute path\nSYMLINK-RELATIVE - create symbolic link with path relative to link location\nREFLINK - share content of files using copy-on-write(Btrfs, XFS), files stay independent\nNONE - not delete files")]
         delete_method: DeleteMethod,
-        #[structopt(short = "ht", long, default_value = "BLAKE3", parse(try_from_str = parse_hash_type), help = "Hash type (BLAKE3, CRC32, XXH3)")]
+        #[structopt(long, parse(try_from_str = parse_keep_policy), help = "Comma separated rules choosing kept and reference files, e.g. prefer-path:/srv/master,shortest-path,oldest", long_help = KEEP_RULES_HELP)]
+        keep: Option<KeepPolicy>,
+        #[structopt(long = "verify", default_value = "NONE", parse(try_from_str = parse_verification_method), help = "Verification before deleting or linking (NONE, BYTES, BLAKE3)", long_help = "Checks that every file is identical to the kept one before deleting or linking it, differing files are skipped and reported as possible hash collisions.\nNONE - trust the search results,\nBYTES - compare files byte by byte,\nBLAKE3 - compare BLAKE3 hashes of whole files")]
+        verification_method: VerificationMethod,
+        #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(short = "ht", long, default_value = "BLAKE3", parse(try_from_str = parse_hash_type), help = "Hash type (BLAKE3, CRC32, XXH3, SHA256, SHA1, MD5)", long_help = "Hash type used to compare files.\nBLAKE3, XXH3 - fast and reliable,\nCRC32 - fastest, but collisions are possible,\nSHA256, SHA1, MD5 - slower, but hashes are same as printed by sha256sum, sha1sum and md5sum")]
         hash_type: HashType,
         #[structopt(flatten)]
+        partial_hash_stages: PartialHashStagesOption,
+        #[structopt(flatten)]
+        io_scheduling: IoSchedulingOptions,
+        #[structopt(
+            long,
+            help = "Find identical folders",
+            long_help = "Finds folders with identical content(names of files are ignored), only with HASH search method. Groups of files inside identical folders are not printed separately"
+        )]
+        find_directories: bool,
+        #[structopt(long, value_name = "percent", default_value = "100", parse(try_from_str = parse_directory_similarity), help = "Minimal similarity of folders in percents (1-100)", long_help = "Folders which share at least this percentage of files directly inside them are reported as similar, used only with --find-directories. 100 reports only identical folders")]
+        directory_similarity: u8,
+        #[structopt(
+            long,
+            help = "Look inside zip and tar archives",
+            long_help = "Files inside zip, tar, tar.gz, tar.bz2 and tar.xz archives are compared with other files. They are only reported, never removed or linked"
+        )]
+        archives: bool,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -56,7 +90,11 @@ pub enum Commands {
         #[structopt(short = "D", long, help = "Delete found folders")]
         delete_folders: bool,
         #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
+        #[structopt(flatten)]
+        load_results: LoadResults,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
         exclude_other_filesystems: ExcludeOtherFilesystems,
@@ -76,8 +114,12 @@ pub enum Commands {
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -96,8 +138,12 @@ pub enum Commands {
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -114,8 +160,12 @@ pub enum Commands {
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -138,6 +188,8 @@ pub enum Commands {
         #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -164,6 +216,8 @@ pub enum Commands {
         #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -186,8 +240,12 @@ pub enum Commands {
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -206,8 +264,12 @@ pub enum Commands {
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        removal_method: RemovalMethodOption,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[cfg(target_family = "unix")]
         #[structopt(flatten)]
@@ -226,6 +288,8 @@ pub enum Commands {
         #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
         #[structopt(flatten)]
         not_recursive: NotRecursive,
@@ -239,6 +303,131 @@ pub enum Commands {
         #[structopt(short = "t", long, parse(try_from_str = parse_tolerance), default_value = "10", help = "Video maximium difference (allowed values <0,20>)", long_help = "Maximum difference between video frames, bigger value means that videos can looks more and more different (allowed values <0,20>)")]
         tolerance: i32,
     },
+    #[structopt(name = "missing-in-backup", about = "Finds files which don't have copy in backup", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka missing-in-backup -d /home/rafal/Photos -b /mnt/backup/Photos -f results.txt")]
+    MissingInBackup {
+        #[structopt(flatten)]
+        directories: Directories,
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            required_unless = "load-results",
+            help = "Backup directorie(s)",
+            long_help = "List of directorie(s) with backup(absolute path), file is missing when its content doesn't exist anywhere inside them"
+        )]
+        backup_directories: Vec<PathBuf>,
+        #[structopt(flatten)]
+        excluded_directories: ExcludedDirectories,
+        #[structopt(flatten)]
+        excluded_items: ExcludedItems,
+        #[structopt(flatten)]
+        allowed_extensions: AllowedExtensions,
+        #[structopt(short, long, parse(try_from_str = parse_minimal_file_size), default_value = "1", help = "Minimum size in bytes", long_help = "Minimum size of checked files in bytes, smaller files are never reported")]
+        minimal_file_size: u64,
+        #[structopt(short = "i", long, parse(try_from_str = parse_maximal_file_size), default_value = "18446744073709551615", help = "Maximum size in bytes", long_help = "Maximum size of checked files in bytes, bigger files are never reported")]
+        maximal_file_size: u64,
+        #[structopt(short = "c", long, parse(try_from_str = parse_minimal_file_size), default_value = "257144", help = "Minimum cached file size in bytes", long_help = "Minimum size of cached files in bytes, assigning bigger value may speed up will cause that lower amount of files will be cached, but loading of cache will be faster")]
+        minimal_cached_file_size: u64,
+        #[structopt(short, long, default_value = "HASH", parse(try_from_str = parse_backup_checking_method), help = "Search method (SIZE, HASH)", long_help = "Methods to compare files with backup.\nSIZE - Fast but not accurate, file with same size in backup is treated as copy,\nHASH - The slowest method, checking by the hash of the entire file")]
+        search_method: CheckingMethod,
+        #[structopt(long, default_value = "BLAKE3", parse(try_from_str = parse_hash_type), help = "Hash type (BLAKE3, CRC32, XXH3, SHA256, SHA1, MD5)")]
+        hash_type: HashType,
+        #[structopt(flatten)]
+        file_to_save: FileToSave,
+        #[structopt(flatten)]
+        load_results: LoadResults,
+        #[structopt(flatten)]
+        not_recursive: NotRecursive,
+        #[cfg(target_family = "unix")]
+        #[structopt(flatten)]
+        exclude_other_filesystems: ExcludeOtherFilesystems,
+    },
+    #[structopt(name = "apply", about = "Performs action on results saved earlier", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka apply -r results.json -k in-path:/home/rafal/Photos -a trash --dry-run")]
+    Apply {
+        #[structopt(short = "r", long, parse(from_os_str), help = "File with results saved in JSON format")]
+        results_file: PathBuf,
+        #[structopt(short, long, default_value = "newest", parse(try_from_str = parse_keep_policy), help = "Comma separated rules choosing file kept in every group, e.g. prefer-path:/srv/master,shortest-path,oldest", long_help = KEEP_RULES_HELP)]
+        keep: KeepPolicy,
+        #[structopt(short, long, parse(try_from_str = parse_apply_action), help = "Action (DELETE, TRASH, QUARANTINE, QUARANTINE:<folder>, HARD, SYMLINK, SYMLINK-RELATIVE, REFLINK, MOVE:<folder>)", long_help = "Action performed on every not kept file.\nDELETE - permanently delete file,\nTRASH - move file to trash,\nQUARANTINE - move file into new folder inside app data folder,\nQUARANTINE:<folder> - move file into folder, original path is recreated inside it,\nHARD - replace file with hard link to kept file,\nSYMLINK - replace file with symbolic link to kept file,\nSYMLINK-RELATIVE - replace file with symbolic link to kept file, using relative path,\nREFLINK - share content of file with kept file using copy-on-write,\nMOVE:<folder> - move file into folder, original path is recreated inside it")]
+        action: ApplyAction,
+        #[structopt(flatten)]
+        journal: JournalOption,
+        #[structopt(flatten)]
+        dryrun: DryRun,
+    },
+    #[structopt(name = "undo", about = "Restores files saved in journal", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka undo --list\n    czkawka undo --last 5 --dry-run")]
+    Undo {
+        #[structopt(short, long, help = "Lists operations saved in journal")]
+        list: bool,
+        #[structopt(short, long, help = "Restores all files which can be restored")]
+        all: bool,
+        #[structopt(short = "n", long, help = "Restores given number of most recent operations which can be restored")]
+        last: Option<usize>,
+        #[structopt(short, long, parse(from_os_str), help = "Restores files with given original paths")]
+        paths: Vec<PathBuf>,
+        #[structopt(long, parse(from_os_str), help = "Uses journal from given file instead of default one")]
+        journal_file: Option<PathBuf>,
+        #[structopt(flatten)]
+        dryrun: DryRun,
+    },
+    #[structopt(name = "watch", about = "Watches folders and reports duplicates, empty and broken files when files change", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka watch -d /srv/media -e /srv/media/tmp -t dup empty --delay 5")]
+    Watch {
+        #[structopt(flatten)]
+        directories: Directories,
+        #[structopt(flatten)]
+        excluded_directories: ExcludedDirectories,
+        #[structopt(flatten)]
+        excluded_items: ExcludedItems,
+        #[structopt(flatten)]
+        allowed_extensions: AllowedExtensions,
+        #[structopt(short, long, parse(try_from_str = parse_minimal_file_size), default_value = "8192", help = "Minimum size in bytes of files checked for duplicates")]
+        minimal_file_size: u64,
+        #[structopt(short = "i", long, parse(try_from_str = parse_maximal_file_size), default_value = "18446744073709551615", help = "Maximum size in bytes of files checked for duplicates")]
+        maximal_file_size: u64,
+        #[structopt(short = "c", long, parse(try_from_str = parse_minimal_file_size), default_value = "257144", help = "Minimum cached file size in bytes")]
+        minimal_cached_file_size: u64,
+        #[structopt(long, default_value = "BLAKE3", parse(try_from_str = parse_hash_type), help = "Hash type (BLAKE3, CRC32, XXH3, SHA256, SHA1, MD5)")]
+        hash_type: HashType,
+        #[structopt(short, long, parse(try_from_str = parse_watch_tool), help = "Reports only results of given tool(s) (dup, empty, broken), by default all are used")]
+        tools: Vec<WatchTool>,
+        #[structopt(long, default_value = "2", help = "Seconds without changes after which changed files are checked")]
+        delay: u64,
+        #[structopt(flatten)]
+        not_recursive: NotRecursive,
+        #[cfg(target_family = "unix")]
+        #[structopt(flatten)]
+        exclude_other_filesystems: ExcludeOtherFilesystems,
+    },
+    #[structopt(name = "cache", about = "Shows and cleans cache files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka cache --list\n    czkawka cache --prune --remove-prefix /media/pendrive -t dup image\n    czkawka cache --clear -t video")]
+    Cache {
+        #[structopt(short, long, help = "Lists cache files with number of entries and size, used also when no other action is chosen")]
+        list: bool,
+        #[structopt(short, long, help = "Removes entries of files which don't exist anymore")]
+        prune: bool,
+        #[structopt(short, long, parse(from_os_str), help = "Removes entries of files inside given folder(s)")]
+        remove_prefix: Vec<PathBuf>,
+        #[structopt(short, long, help = "Maximum size of every cache file in bytes, entries of smallest files are removed first")]
+        max_size: Option<u64>,
+        #[structopt(short, long, help = "Removes whole cache files")]
+        clear: bool,
+        #[structopt(short, long, parse(try_from_str = parse_cache_tool), help = "Uses only caches of given tool(s) (dup, image, video, music, broken), by default all are used")]
+        tools: Vec<CacheTool>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Uses caches saved on removable drive which contains given path, instead of default cache folder"
+        )]
+        volume: Option<PathBuf>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Creates marker file on removable drive which contains given path",
+            long_help = "Creates marker file in root folder of removable drive which contains given path.\nMarker gives identifier to drive without UUID, so cached paths stay valid after mounting it in other place"
+        )]
+        mark_volume: Option<PathBuf>,
+        #[structopt(long, requires = "mark-volume", help = "Caches of files from marked drive are saved on this drive")]
+        store_on_volume: bool,
+    },
     #[structopt(name = "tester", about = "Contains various test", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka tests -i")]
     Tester {
         #[structopt(short = "i", long = "test_image", help = "Test speed of hashing provided test.jpg image with different filters and methods.")]
@@ -252,7 +441,7 @@ pub struct Directories {
         short,
         long,
         parse(from_os_str),
-        required = true,
+        required_unless = "load-results",
         help = "Directorie(s) to search",
         long_help = "List of directorie(s) which will be searched(absolute path)"
     )]
@@ -310,6 +499,93 @@ pub struct ExcludeOtherFilesystems {
 pub struct FileToSave {
     #[structopt(short, long, value_name = "file-name", help = "Saves the results into the file")]
     pub file_to_save: Option<PathBuf>,
+    #[structopt(
+        short = "F",
+        long,
+        value_name = "format",
+        default_value = "text",
+        parse(try_from_str = parse_export_format),
+        help = "Format of saved results (TEXT, JSON, CSV, CHECKSUMS)",
+        long_help = "Format of file with results.\nTEXT - human readable summary,\nJSON - groups of entries with paths, sizes, modification dates, hashes, similarity and reference flags,\nCSV - same data as JSON, one row per entry with group number,\nCHECKSUMS - hashes of files in sha256sum format, which can be checked with e.g. sha256sum -c when same hash type was used"
+    )]
+    pub export_format: ExportFormat,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct LoadResults {
+    #[structopt(
+        long,
+        value_name = "file-name",
+        parse(from_os_str),
+        help = "Loads results saved in JSON format instead of searching",
+        long_help = "Loads results saved earlier with \"-F json\" instead of searching. Files which were removed or changed since saving are skipped, delete options are applied to the rest"
+    )]
+    pub load_results: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RemovalMethodOption {
+    #[structopt(
+        long,
+        value_name = "method",
+        default_value = "permanent",
+        parse(try_from_str = parse_removal_method),
+        help = "How deleted files are removed (PERMANENT, TRASH, QUARANTINE, QUARANTINE:<folder>)",
+        long_help = "How files deleted by delete options are removed.\nPERMANENT - remove files permanently,\nTRASH - move files to trash,\nQUARANTINE - move files into new folder inside app data folder,\nQUARANTINE:<folder> - move files into folder, original path is recreated inside it"
+    )]
+    pub removal_method: RemovalMethod,
+    #[structopt(flatten)]
+    pub journal: JournalOption,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct JournalOption {
+    #[structopt(long, help = "Do not save removed files to journal, which makes undo impossible")]
+    pub no_journal: bool,
+}
+
+// Alias prevents structopt from treating option as list of values
+type PartialHashStages = Vec<PartialHashStage>;
+
+#[derive(Debug, StructOpt)]
+pub struct PartialHashStagesOption {
+    #[structopt(
+        long,
+        parse(try_from_str = parse_partial_hash_stages),
+        help = "Partial hash stages checked before full hash (e.g. head:2048,tail:65536,sampled:4x1048576 or none)",
+        long_help = "Comma separated list of parts of files hashed before full hash, files with different partial hash are not checked further.\nhead:<bytes> - beginning of file,\ntail:<bytes> - end of file,\nsampled:<chunks>x<bytes> - chunks spread evenly over whole file,\nnone - only full hash is used.\nDefault is head:2048,tail:65536,sampled:4x1048576"
+    )]
+    pub partial_hash_stages: Option<PartialHashStages>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct IoSchedulingOptions {
+    #[structopt(long, default_value = "0", help = "Files hashed at once from one device, 0 uses all threads")]
+    pub io_threads: usize,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Files hashed at once from one HDD, 0 uses --io-threads",
+        long_help = "Files hashed at once from one rotational disk(HDD), reading many files at once from HDD is slow because of seeking, so value 1 is recommended for single HDD. Disks are detected only on Linux, 0(default) uses --io-threads"
+    )]
+    pub hdd_threads: usize,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_device_threads),
+        help = "Files hashed at once from device containing folder (e.g. /mnt/backup=1)",
+        long_help = "Number of files hashed at once from device on which given folder is stored, overrides other limits for this device. Can be used multiple times"
+    )]
+    pub device_threads: Vec<(PathBuf, usize)>,
+}
+
+impl IoSchedulingOptions {
+    pub fn io_scheduler(self) -> IoScheduler {
+        IoScheduler {
+            default_threads: self.io_threads,
+            rotational_threads: self.hdd_threads,
+            device_threads: self.device_threads,
+        }
+    }
 }
 
 #[derive(Debug, StructOpt)]
@@ -320,10 +596,30 @@ pub struct AllowHardLinks {
 
 #[derive(Debug, StructOpt)]
 pub struct DryRun {
-    #[structopt(long, help = "Do nothing and print the operation that would happen.")]
+    #[structopt(long, alias = "dry-run", help = "Do nothing and print the operation that would happen.")]
     pub dryrun: bool,
 }
 
+impl JournalOption {
+    pub fn journal(&self) -> Option<Journal> {
+        if self.no_journal {
+            return None;
+        }
+
+        Journal::default_journal()
+    }
+}
+
+impl LoadResults {
+    pub fn file_name(&self) -> Option<&str> {
+        if let Some(file_name) = &self.load_results {
+            return file_name.to_str();
+        }
+
+        None
+    }
+}
+
 impl FileToSave {
     pub fn file_name(&self) -> Option<&str> {
         if let Some(file_name) = &self.file_to_save {
@@ -334,12 +630,19 @@ impl FileToSave {
     }
 }
 
+fn parse_export_format(src: &str) -> Result<ExportFormat, &'static str> {
+    ExportFormat::from_name(src).ok_or("Couldn't parse the export format (allowed: TEXT, JSON, CSV, CHECKSUMS)")
+}
+
 fn parse_hash_type(src: &str) -> Result<HashType, &'static str> {
     match src.to_ascii_lowercase().as_str() {
         "blake3" => Ok(HashType::Blake3),
         "crc32" => Ok(HashType::Crc32),
         "xxh3" => Ok(HashType::Xxh3),
-        _ => Err("Couldn't parse the hash type (allowed: BLAKE3, CRC32, XXH3)"),
+        "sha256" => Ok(HashType::Sha256),
+        "sha1" => Ok(HashType::Sha1),
+        "md5" => Ok(HashType::Md5),
+        _ => Err("Couldn't parse the hash type (allowed: BLAKE3, CRC32, XXH3, SHA256, SHA1, MD5)"),
     }
 }
 
@@ -356,6 +659,19 @@ fn parse_tolerance(src: &str) -> Result<i32, &'static str> {
     }
 }
 
+fn parse_directory_similarity(src: &str) -> Result<u8, &'static str> {
+    match src.parse::<u8>() {
+        Ok(t) => {
+            if (1..=100).contains(&t) {
+                Ok(t)
+            } else {
+                Err("Directory similarity should be in range <1,100>")
+            }
+        }
+        _ => Err("Failed to parse directory similarity as u8 value."),
+    }
+}
+
 fn parse_checking_method(src: &str) -> Result<CheckingMethod, &'static str> {
     match src.to_ascii_lowercase().as_str() {
         "name" => Ok(CheckingMethod::Name),
@@ -365,15 +681,80 @@ fn parse_checking_method(src: &str) -> Result<CheckingMethod, &'static str> {
     }
 }
 
+fn parse_backup_checking_method(src: &str) -> Result<CheckingMethod, &'static str> {
+    match src.to_ascii_lowercase().as_str() {
+        "size" => Ok(CheckingMethod::Size),
+        "hash" => Ok(CheckingMethod::Hash),
+        _ => Err("Couldn't parse the search method (allowed: SIZE, HASH)"),
+    }
+}
+
 fn parse_delete_method(src: &str) -> Result<DeleteMethod, &'static str> {
     match src.to_ascii_lowercase().as_str() {
         "none" => Ok(DeleteMethod::None),
         "aen" => Ok(DeleteMethod::AllExceptNewest),
         "aeo" => Ok(DeleteMethod::AllExceptOldest),
         "hard" => Ok(DeleteMethod::HardLink),
+        "symlink" => Ok(DeleteMethod::Symlink(SymlinkType::Absolute)),
+        "symlink-relative" => Ok(DeleteMethod::Symlink(SymlinkType::Relative)),
+        "reflink" => Ok(DeleteMethod::Reflink),
         "on" => Ok(DeleteMethod::OneNewest),
         "oo" => Ok(DeleteMethod::OneOldest),
-        _ => Err("Couldn't parse the delete method (allowed: AEN, AEO, ON, OO, HARD)"),
+        _ => Err("Couldn't parse the delete method (allowed: AEN, AEO, ON, OO, HARD, SYMLINK, SYMLINK-RELATIVE, REFLINK)"),
+    }
+}
+
+fn parse_verification_method(src: &str) -> Result<VerificationMethod, &'static str> {
+    match src.to_ascii_lowercase().as_str() {
+        "none" => Ok(VerificationMethod::None),
+        "bytes" => Ok(VerificationMethod::ByteByByte),
+        "blake3" => Ok(VerificationMethod::Blake3),
+        _ => Err("Couldn't parse the verification method (allowed: NONE, BYTES, BLAKE3)"),
+    }
+}
+
+fn parse_keep_policy(src: &str) -> Result<KeepPolicy, String> {
+    KeepPolicy::from_text(src)
+}
+
+fn parse_removal_method(src: &str) -> Result<RemovalMethod, &'static str> {
+    if let Some(folder) = strip_prefix_ignore_case(src, "quarantine:") {
+        return Ok(RemovalMethod::Quarantine(PathBuf::from(folder)));
+    }
+    match src.to_ascii_lowercase().as_str() {
+        "permanent" => Ok(RemovalMethod::Permanent),
+        "trash" => Ok(RemovalMethod::Trash),
+        "quarantine" => default_quarantine_folder()
+            .map(RemovalMethod::Quarantine)
+            .ok_or("Couldn't find app data folder for quarantine"),
+        _ => Err("Couldn't parse the removal method (allowed: PERMANENT, TRASH, QUARANTINE, QUARANTINE:<folder>)"),
+    }
+}
+
+fn parse_apply_action(src: &str) -> Result<ApplyAction, &'static str> {
+    if let Some(folder) = strip_prefix_ignore_case(src, "move:") {
+        return Ok(ApplyAction::Move(PathBuf::from(folder)));
+    }
+    if let Some(folder) = strip_prefix_ignore_case(src, "quarantine:") {
+        return Ok(ApplyAction::Remove(RemovalMethod::Quarantine(PathBuf::from(folder))));
+    }
+    match src.to_ascii_lowercase().as_str() {
+        "delete" => Ok(ApplyAction::Remove(RemovalMethod::Permanent)),
+        "trash" => Ok(ApplyAction::Remove(RemovalMethod::Trash)),
+        "quarantine" => parse_removal_method(src).map(ApplyAction::Remove),
+        "hard" => Ok(ApplyAction::HardLink),
+        "symlink" => Ok(ApplyAction::Symlink(SymlinkType::Absolute)),
+        "symlink-relative" => Ok(ApplyAction::Symlink(SymlinkType::Relative)),
+        "reflink" => Ok(ApplyAction::Reflink),
+        _ => Err("Couldn't parse the action (allowed: DELETE, TRASH, QUARANTINE, QUARANTINE:<folder>, HARD, SYMLINK, SYMLINK-RELATIVE, REFLINK, MOVE:<folder>)"),
+    }
+}
+
+/// Returns not empty rest of text after prefix
+fn strip_prefix_ignore_case<'a>(src: &'a str, prefix: &str) -> Option<&'a str> {
+    match src.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) && src.len() > prefix.len() => Some(&src[prefix.len()..]),
+        _ => None,
     }
 }
 
@@ -389,6 +770,66 @@ fn parse_similar_images_similarity(src: &str) -> Result<SimilarityPreset, &'stat
     }
 }
 
+fn parse_device_threads(src: &str) -> Result<(PathBuf, usize), String> {
+    let (folder, threads) = src.rsplit_once('=').ok_or_else(|| format!("Couldn't parse \"{}\", expected <folder>=<threads>", src))?;
+    match threads.trim().parse::<usize>() {
+        Ok(threads) => Ok((PathBuf::from(folder), threads)),
+        Err(_) => Err(format!("Couldn't parse number of threads in \"{}\"", src)),
+    }
+}
+
+fn parse_partial_hash_stages(src: &str) -> Result<PartialHashStages, String> {
+    if src.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    src.split(',').map(|stage| parse_partial_hash_stage(stage.trim())).collect()
+}
+
+fn parse_partial_hash_stage(src: &str) -> Result<PartialHashStage, String> {
+    let parse_bytes = |value: &str| match value.parse::<u64>() {
+        Ok(0) | Err(_) => Err(format!("Couldn't parse number of bytes in partial hash stage \"{}\"", src)),
+        Ok(t) => Ok(t),
+    };
+    if let Some(bytes) = strip_prefix_ignore_case(src, "head:") {
+        Ok(PartialHashStage::Head(parse_bytes(bytes)?))
+    } else if let Some(bytes) = strip_prefix_ignore_case(src, "tail:") {
+        Ok(PartialHashStage::Tail(parse_bytes(bytes)?))
+    } else if let Some(chunks) = strip_prefix_ignore_case(src, "sampled:") {
+        match chunks.split_once(['x', 'X']) {
+            Some((chunks, chunk_size)) => Ok(PartialHashStage::Sampled {
+                chunks: parse_bytes(chunks)?,
+                chunk_size: parse_bytes(chunk_size)?,
+            }),
+            None => Err(format!("Sampled partial hash stage must be in format sampled:<chunks>x<bytes>, found \"{}\"", src)),
+        }
+    } else {
+        Err(format!(
+            "Couldn't parse partial hash stage \"{}\" (allowed: head:<bytes>, tail:<bytes>, sampled:<chunks>x<bytes>)",
+            src
+        ))
+    }
+}
+
+fn parse_cache_tool(src: &str) -> Result<CacheTool, &'static str> {
+    match src.to_ascii_lowercase().as_str() {
+        "dup" => Ok(CacheTool::Duplicates),
+        "image" => Ok(CacheTool::SimilarImages),
+        "video" => Ok(CacheTool::SimilarVideos),
+        "music" => Ok(CacheTool::SameMusic),
+        "broken" => Ok(CacheTool::BrokenFiles),
+        _ => Err("Couldn't parse the cache tool (allowed: dup, image, video, music, broken)"),
+    }
+}
+
+fn parse_watch_tool(src: &str) -> Result<WatchTool, &'static str> {
+    match src.to_ascii_lowercase().as_str() {
+        "dup" => Ok(WatchTool::Duplicates),
+        "empty" => Ok(WatchTool::EmptyFiles),
+        "broken" => Ok(WatchTool::BrokenFiles),
+        _ => Err("Couldn't parse the watch tool (allowed: dup, empty, broken)"),
+    }
+}
+
 fn parse_minimal_file_size(src: &str) -> Result<u64, String> {
     match src.parse::<u64>() {
         Ok(minimal_file_size) => {
@@ -481,6 +922,15 @@ fn parse_music_duplicate_type(src: &str) -> Result<MusicSimilarity, String> {
 
 static HELP_MESSAGE: &str = "Prints help information (--help will give more information)";
 
+const KEEP_RULES_HELP: &str = "Comma separated list of rules choosing file kept in every group, every next rule only decides between files equally good for previous ones, when rules don't decide first file is kept.
+NEWEST, OLDEST - the most or the least recently modified file,
+BIGGEST, SMALLEST - the biggest or the smallest file,
+IN-PATH:<folder> - only file inside folder can be kept, groups without such file are skipped,
+PREFER-PATH:<folder> - prefers files inside folder,
+SHORTEST-PATH, SHORTEST-NAME - file with the shortest full path or name,
+WITHOUT-COPY-SUFFIX - prefers names without endings like \" (1)\" or \" - Copy\",
+HIGHEST-RESOLUTION, LOWEST-RESOLUTION - image with the most or the least pixels, only for similar images";
+
 const HELP_TEMPLATE: &str = r#"
 {bin} {version}
 
@@ -497,11 +947,17 @@ SUBCOMMANDS:
 
 EXAMPLES:
     {bin} dup -d /home/rafal -e /home/rafal/Obrazy  -m 25 -x 7z rar IMAGE -s hash -f results.txt -D aeo
+    {bin} dup -d /home/rafal -s hash -f results.json -F json
+    {bin} dup --load-results results.json -D aeo
+    {bin} apply -r results.json -k oldest -a hard --dry-run
     {bin} empty-folders -d /home/rafal/rr /home/gateway -f results.txt
     {bin} big -d /home/rafal/ /home/piszczal -e /home/rafal/Roman -n 25 -x VIDEO -f results.txt
     {bin} empty-files -d /home/rafal /home/szczekacz -e /home/rafal/Pulpit -R -f results.txt
     {bin} temp -d /home/rafal/ -E */.git */tmp* *Pulpit -f results.txt -D
+    {bin} empty-files -d /home/rafal -D --removal-method trash
+    {bin} undo --last 5
     {bin} image -d /home/rafal -e /home/rafal/Pulpit -f results.txt
     {bin} music -d /home/rafal -e /home/rafal/Pulpit -z "artist,year, ARTISTALBUM, ALBUM___tiTlE"  -f results.txt
     {bin} symlinks -d /home/kicikici/ /home/szczek -e /home/kicikici/jestempsem -x jpg -f results.txt
-    {bin} broken -d /home/mikrut/ -e /home/mikrut/trakt -f results.txt"#;
+    {bin} broken -d /home/mikrut/ -e /home/mikrut/trakt -f results.txt
+    {bin} missing-in-backup -d /home/rafal/Photos -b /mnt/backup/Photos -f results.txt"#;
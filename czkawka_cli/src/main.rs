@@ -1,12 +1,21 @@
 #![allow(clippy::needless_late_init)]
 
 use std::process;
+use std::thread;
+use std::time::Duration;
 
+use humansize::{file_size_opts as options, FileSize};
 use structopt::StructOpt;
 
-use commands::Commands;
-#[allow(unused_imports)] // It is used in release for print_results().
-use czkawka_core::common_traits::*;
+use commands::{Commands, FileToSave, LoadResults};
+use czkawka_core::common_apply::apply_to_results;
+use czkawka_core::common_cache_manager::{CacheCleaning, CacheManager};
+use czkawka_core::common_export::{ExportFormat, ExportedResults};
+use czkawka_core::common_journal::Journal;
+use czkawka_core::common_messages::Messages;
+use czkawka_core::common_tool::{SearchSettings, Tool};
+use czkawka_core::common_volume;
+use czkawka_core::common_watch::{DirectoryWatcher, WatchEvent};
 use czkawka_core::similar_images::test_image_conversion_speed;
 use czkawka_core::{
     big_file::{self, BigFile},
@@ -16,6 +25,7 @@ use czkawka_core::{
     empty_folder::EmptyFolder,
     invalid_symlinks,
     invalid_symlinks::InvalidSymlinks,
+    missing_in_backup::MissingInBackup,
     same_music::SameMusic,
     similar_images::{return_similarity_from_similarity_preset, SimilarImages},
     similar_videos::SimilarVideos,
@@ -41,8 +51,17 @@ fn main() {
             allowed_extensions,
             search_method,
             delete_method,
+            keep,
+            verification_method,
+            removal_method,
             hash_type,
+            partial_hash_stages,
+            io_scheduling,
+            find_directories,
+            directory_similarity,
+            archives,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
@@ -51,39 +70,44 @@ fn main() {
         } => {
             let mut df = DuplicateFinder::new();
 
-            df.set_included_directory(directories.directories);
-            df.set_excluded_directory(excluded_directories.excluded_directories);
-            df.set_excluded_items(excluded_items.excluded_items);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             df.set_minimal_file_size(minimal_file_size);
             df.set_maximal_file_size(maximal_file_size);
             df.set_minimal_cache_file_size(minimal_cached_file_size);
-            df.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
             df.set_check_method(search_method);
             df.set_delete_method(delete_method);
+            df.set_keep_policy(keep);
+            df.set_verification_method(verification_method);
             df.set_hash_type(hash_type);
-            df.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            df.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
+            if let Some(partial_hash_stages) = partial_hash_stages.partial_hash_stages {
+                df.set_partial_hash_stages(partial_hash_stages);
+            }
+            df.set_io_scheduler(io_scheduling.io_scheduler());
+            df.set_find_duplicated_directories(find_directories);
+            df.set_minimal_directory_similarity(directory_similarity);
+            df.set_look_inside_archives(archives);
             df.set_ignore_hard_links(!allow_hard_links.allow_hard_links);
             df.set_dryrun(dryrun.dryrun);
+            df.set_removal_method(removal_method.removal_method);
+            df.set_journal(removal_method.journal.journal());
 
-            df.find_duplicates(None, None);
-
-            if let Some(file_name) = file_to_save.file_name() {
-                if !df.save_results_to_file(file_name) {
-                    df.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
-
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            df.print_results();
-            df.get_text_messages().print_messages();
+            run_tool(&mut df, search_settings, &file_to_save, &load_results);
         }
         Commands::EmptyFolders {
             directories,
             delete_folders,
+            removal_method,
             file_to_save,
+            load_results,
             excluded_directories,
             excluded_items,
             #[cfg(target_family = "unix")]
@@ -91,25 +115,19 @@ fn main() {
         } => {
             let mut ef = EmptyFolder::new();
 
-            ef.set_included_directory(directories.directories);
-            ef.set_excluded_directory(excluded_directories.excluded_directories);
-            ef.set_excluded_items(excluded_items.excluded_items);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             ef.set_delete_folder(delete_folders);
-            #[cfg(target_family = "unix")]
-            ef.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
-
-            ef.find_empty_folders(None, None);
+            ef.set_removal_method(removal_method.removal_method);
+            ef.set_journal(removal_method.journal.journal());
 
-            if let Some(file_name) = file_to_save.file_name() {
-                if !ef.save_results_to_file(file_name) {
-                    ef.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
-
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            ef.print_results();
-            ef.get_text_messages().print_messages();
+            run_tool(&mut ef, search_settings, &file_to_save, &load_results);
         }
         Commands::BiggestFiles {
             directories,
@@ -118,37 +136,34 @@ fn main() {
             allowed_extensions,
             number_of_files,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
             delete_files,
+            removal_method,
         } => {
             let mut bf = BigFile::new();
 
-            bf.set_included_directory(directories.directories);
-            bf.set_excluded_directory(excluded_directories.excluded_directories);
-            bf.set_excluded_items(excluded_items.excluded_items);
-            bf.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             bf.set_number_of_files_to_check(number_of_files);
-            bf.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            bf.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
             if delete_files {
                 bf.set_delete_method(big_file::DeleteMethod::Delete);
             }
 
-            bf.find_big_files(None, None);
+            bf.set_removal_method(removal_method.removal_method);
+            bf.set_journal(removal_method.journal.journal());
 
-            if let Some(file_name) = file_to_save.file_name() {
-                if !bf.save_results_to_file(file_name) {
-                    bf.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
-
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            bf.print_results();
-            bf.get_text_messages().print_messages();
+            run_tool(&mut bf, search_settings, &file_to_save, &load_results);
         }
         Commands::EmptyFiles {
             directories,
@@ -156,37 +171,34 @@ fn main() {
             excluded_items,
             allowed_extensions,
             delete_files,
+            removal_method,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
         } => {
             let mut ef = EmptyFiles::new();
 
-            ef.set_included_directory(directories.directories);
-            ef.set_excluded_directory(excluded_directories.excluded_directories);
-            ef.set_excluded_items(excluded_items.excluded_items);
-            ef.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            ef.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            ef.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
 
             if delete_files {
                 ef.set_delete_method(empty_files::DeleteMethod::Delete);
             }
 
-            ef.find_empty_files(None, None);
-
-            if let Some(file_name) = file_to_save.file_name() {
-                if !ef.save_results_to_file(file_name) {
-                    ef.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
+            ef.set_removal_method(removal_method.removal_method);
+            ef.set_journal(removal_method.journal.journal());
 
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            ef.print_results();
-            ef.get_text_messages().print_messages();
+            run_tool(&mut ef, search_settings, &file_to_save, &load_results);
         }
         Commands::Temporary {
             directories,
@@ -195,40 +207,38 @@ fn main() {
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
             delete_files,
+            removal_method,
             file_to_save,
+            load_results,
             not_recursive,
         } => {
             let mut tf = Temporary::new();
 
-            tf.set_included_directory(directories.directories);
-            tf.set_excluded_directory(excluded_directories.excluded_directories);
-            tf.set_excluded_items(excluded_items.excluded_items);
-            tf.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            tf.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
 
             if delete_files {
                 tf.set_delete_method(temporary::DeleteMethod::Delete);
             }
 
-            tf.find_temporary_files(None, None);
-
-            if let Some(file_name) = file_to_save.file_name() {
-                if !tf.save_results_to_file(file_name) {
-                    tf.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
+            tf.set_removal_method(removal_method.removal_method);
+            tf.set_journal(removal_method.journal.journal());
 
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            tf.print_results();
-            tf.get_text_messages().print_messages();
+            run_tool(&mut tf, search_settings, &file_to_save, &load_results);
         }
         Commands::SimilarImages {
             directories,
             excluded_directories,
             excluded_items,
             file_to_save,
+            load_results,
             minimal_file_size,
             maximal_file_size,
             similarity_preset,
@@ -241,32 +251,24 @@ fn main() {
         } => {
             let mut sf = SimilarImages::new();
 
-            sf.set_included_directory(directories.directories);
-            sf.set_excluded_directory(excluded_directories.excluded_directories);
-            sf.set_excluded_items(excluded_items.excluded_items);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             sf.set_minimal_file_size(minimal_file_size);
             sf.set_maximal_file_size(maximal_file_size);
-            sf.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            sf.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
             sf.set_image_filter(image_filter);
             sf.set_hash_alg(hash_alg);
             sf.set_hash_size(hash_size);
 
             sf.set_similarity(return_similarity_from_similarity_preset(&similarity_preset, hash_size));
 
-            sf.find_similar_images(None, None);
-
-            if let Some(file_name) = file_to_save.file_name() {
-                if !sf.save_results_to_file(file_name) {
-                    sf.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
-
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            sf.print_results();
-            sf.get_text_messages().print_messages();
+            run_tool(&mut sf, search_settings, &file_to_save, &load_results);
         }
         Commands::SameMusic {
             directories,
@@ -274,6 +276,7 @@ fn main() {
             excluded_items,
             // delete_files,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
@@ -283,32 +286,24 @@ fn main() {
         } => {
             let mut mf = SameMusic::new();
 
-            mf.set_included_directory(directories.directories);
-            mf.set_excluded_directory(excluded_directories.excluded_directories);
-            mf.set_excluded_items(excluded_items.excluded_items);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             mf.set_minimal_file_size(minimal_file_size);
             mf.set_maximal_file_size(maximal_file_size);
-            mf.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            mf.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
             mf.set_music_similarity(music_similarity);
 
             // if delete_files {
             //     // TODO mf.set_delete_method(same_music::DeleteMethod::Delete);
             // }
 
-            mf.find_same_music(None, None);
-
-            if let Some(file_name) = file_to_save.file_name() {
-                if !mf.save_results_to_file(file_name) {
-                    mf.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
-
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            mf.print_results();
-            mf.get_text_messages().print_messages();
+            run_tool(&mut mf, search_settings, &file_to_save, &load_results);
         }
         Commands::InvalidSymlinks {
             directories,
@@ -316,36 +311,33 @@ fn main() {
             excluded_items,
             allowed_extensions,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
             delete_files,
+            removal_method,
         } => {
             let mut ifs = InvalidSymlinks::new();
 
-            ifs.set_included_directory(directories.directories);
-            ifs.set_excluded_directory(excluded_directories.excluded_directories);
-            ifs.set_excluded_items(excluded_items.excluded_items);
-            ifs.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            ifs.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            ifs.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             if delete_files {
                 ifs.set_delete_method(invalid_symlinks::DeleteMethod::Delete);
             }
 
-            ifs.find_invalid_links(None, None);
+            ifs.set_removal_method(removal_method.removal_method);
+            ifs.set_journal(removal_method.journal.journal());
 
-            if let Some(file_name) = file_to_save.file_name() {
-                if !ifs.save_results_to_file(file_name) {
-                    ifs.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
-
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            ifs.print_results();
-            ifs.get_text_messages().print_messages();
+            run_tool(&mut ifs, search_settings, &file_to_save, &load_results);
         }
         Commands::BrokenFiles {
             directories,
@@ -353,43 +345,41 @@ fn main() {
             excluded_items,
             allowed_extensions,
             delete_files,
+            removal_method,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
         } => {
             let mut br = BrokenFiles::new();
 
-            br.set_included_directory(directories.directories);
-            br.set_excluded_directory(excluded_directories.excluded_directories);
-            br.set_excluded_items(excluded_items.excluded_items);
-            br.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            br.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            br.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
 
             if delete_files {
                 br.set_delete_method(broken_files::DeleteMethod::Delete);
             }
 
-            br.find_broken_files(None, None);
-
-            if let Some(file_name) = file_to_save.file_name() {
-                if !br.save_results_to_file(file_name) {
-                    br.get_text_messages().print_messages();
-                    process::exit(1);
-                }
-            }
+            br.set_removal_method(removal_method.removal_method);
+            br.set_journal(removal_method.journal.journal());
 
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            br.print_results();
-            br.get_text_messages().print_messages();
+            run_tool(&mut br, search_settings, &file_to_save, &load_results);
         }
         Commands::SimilarVideos {
             directories,
             excluded_directories,
             excluded_items,
             file_to_save,
+            load_results,
             not_recursive,
             #[cfg(target_family = "unix")]
             exclude_other_filesystems,
@@ -400,29 +390,279 @@ fn main() {
         } => {
             let mut vr = SimilarVideos::new();
 
-            vr.set_included_directory(directories.directories);
-            vr.set_excluded_directory(excluded_directories.excluded_directories);
-            vr.set_excluded_items(excluded_items.excluded_items);
-            vr.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            vr.set_recursive_search(!not_recursive.not_recursive);
-            #[cfg(target_family = "unix")]
-            vr.set_exclude_other_filesystems(exclude_other_filesystems.exclude_other_filesystems);
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
             vr.set_minimal_file_size(minimal_file_size);
             vr.set_maximal_file_size(maximal_file_size);
             vr.set_tolerance(tolerance);
 
-            vr.find_similar_videos(None, None);
+            run_tool(&mut vr, search_settings, &file_to_save, &load_results);
+        }
+        Commands::MissingInBackup {
+            directories,
+            backup_directories,
+            excluded_directories,
+            excluded_items,
+            allowed_extensions,
+            minimal_file_size,
+            maximal_file_size,
+            minimal_cached_file_size,
+            search_method,
+            hash_type,
+            file_to_save,
+            load_results,
+            not_recursive,
+            #[cfg(target_family = "unix")]
+            exclude_other_filesystems,
+        } => {
+            let mut mb = MissingInBackup::new();
+
+            let search_settings = SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            };
+            mb.set_backup_directory(backup_directories);
+            mb.set_minimal_file_size(minimal_file_size);
+            mb.set_maximal_file_size(maximal_file_size);
+            mb.set_minimal_cache_file_size(minimal_cached_file_size);
+            mb.set_check_method(search_method);
+            mb.set_hash_type(hash_type);
+
+            run_tool(&mut mb, search_settings, &file_to_save, &load_results);
+        }
+        Commands::Apply {
+            results_file,
+            keep,
+            action,
+            journal,
+            dryrun,
+        } => {
+            let results = match ExportedResults::load_from_file(&results_file.to_string_lossy()) {
+                Ok(t) => t,
+                Err(diagnostic) => {
+                    eprintln!("{}", diagnostic);
+                    process::exit(1);
+                }
+            };
+
+            let mut text_messages = Messages::new();
+            let info = apply_to_results(results, &keep, &action, dryrun.dryrun, journal.journal().as_ref(), &mut text_messages);
+
+            println!(
+                "Processed {} files ({}), failed to process {} files, skipped {} groups",
+                info.processed_files,
+                info.processed_size.file_size(options::BINARY).unwrap(),
+                info.failed_files,
+                info.skipped_groups
+            );
+            text_messages.print_messages();
+            if !text_messages.errors.is_empty() || info.failed_files > 0 {
+                process::exit(1);
+            }
+        }
+        Commands::Undo {
+            list,
+            all,
+            last,
+            paths,
+            journal_file,
+            dryrun,
+        } => {
+            let journal = match journal_file.map(Journal::new).or_else(Journal::default_journal) {
+                Some(t) => t,
+                None => {
+                    eprintln!("Couldn't find app data folder with journal");
+                    process::exit(1);
+                }
+            };
+            let entries = match journal.load() {
+                Ok(t) => t,
+                Err(diagnostic) => {
+                    eprintln!("{}", diagnostic);
+                    process::exit(1);
+                }
+            };
+
+            if list {
+                for (index, entry) in entries.iter().enumerate() {
+                    println!(
+                        "{} {} {:?} \"{}\"{} ({}){}",
+                        index,
+                        entry.formatted_time(),
+                        entry.action,
+                        entry.original_path.display(),
+                        entry.target.as_ref().map(|e| format!(" -> \"{}\"", e.display())).unwrap_or_default(),
+                        entry.size.file_size(options::BINARY).unwrap(),
+                        if entry.can_be_undone() { "" } else { " - cannot be restored" }
+                    );
+                }
+                return;
+            }
+            if !all && last.is_none() && paths.is_empty() {
+                eprintln!("Choose which files should be restored with --all, --last or --paths");
+                process::exit(1);
+            }
+
+            let restorable: Vec<usize> = entries.iter().enumerate().filter(|(_, e)| e.can_be_undone()).map(|(index, _)| index).collect();
+            let last_indexes = &restorable[restorable.len() - last.unwrap_or(0).min(restorable.len())..];
+            let (restored, diagnostics) = journal.undo(
+                |index, entry| entry.can_be_undone() && (all || last_indexes.contains(&index) || paths.contains(&entry.original_path)),
+                dryrun.dryrun,
+            );
 
-            if let Some(file_name) = file_to_save.file_name() {
-                if !vr.save_results_to_file(file_name) {
-                    vr.get_text_messages().print_messages();
+            let mut text_messages = Messages::new();
+            for entry in &restored {
+                text_messages.messages.push(format!("Restore {}", entry.original_path.display()));
+            }
+            text_messages.extend_diagnostics(diagnostics);
+            if !dryrun.dryrun {
+                println!("Restored {} files", restored.len());
+            }
+            text_messages.print_messages();
+            if !text_messages.warnings.is_empty() || !text_messages.errors.is_empty() {
+                process::exit(1);
+            }
+        }
+        Commands::Watch {
+            directories,
+            excluded_directories,
+            excluded_items,
+            allowed_extensions,
+            minimal_file_size,
+            maximal_file_size,
+            minimal_cached_file_size,
+            hash_type,
+            tools,
+            delay,
+            not_recursive,
+            #[cfg(target_family = "unix")]
+            exclude_other_filesystems,
+        } => {
+            let mut watcher = DirectoryWatcher::new();
+
+            watcher.set_search_settings(SearchSettings {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                allowed_extensions: allowed_extensions.allowed_extensions.join(","),
+                recursive_search: !not_recursive.not_recursive,
+                #[cfg(target_family = "unix")]
+                exclude_other_filesystems: exclude_other_filesystems.exclude_other_filesystems,
+                ..Default::default()
+            });
+            watcher.set_minimal_file_size(minimal_file_size);
+            watcher.set_maximal_file_size(maximal_file_size);
+            watcher.set_minimal_cache_file_size(minimal_cached_file_size);
+            watcher.set_hash_type(hash_type);
+            if !tools.is_empty() {
+                watcher.set_tools(tools);
+            }
+            watcher.set_delay(Duration::from_secs(delay));
+
+            let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+            let watch_thread = thread::spawn(move || {
+                let started = watcher.watch(None, None, &event_sender);
+                (watcher, started)
+            });
+            for event in event_receiver {
+                print_watch_event(event);
+            }
+            let (watcher, started) = watch_thread.join().expect("Failed to join watch thread");
+            watcher.get_text_messages().print_messages();
+            if !started {
+                process::exit(1);
+            }
+        }
+        Commands::Cache {
+            list,
+            prune,
+            remove_prefix,
+            max_size,
+            clear,
+            tools,
+            volume,
+            mark_volume,
+            store_on_volume,
+        } => {
+            if let Some(mark_volume) = mark_volume {
+                match common_volume::mark_volume(&mark_volume, store_on_volume) {
+                    Ok(marker_file) => println!("Created marker file {}", marker_file.display()),
+                    Err(e) => {
+                        eprintln!("Failed to mark drive which contains {}, reason {}", mark_volume.display(), e);
+                        process::exit(1);
+                    }
+                }
+            }
+            let manager = match &volume {
+                Some(volume) => CacheManager::volume_manager(volume).unwrap_or_else(|| {
+                    eprintln!("{} is on root filesystem, its caches are saved in default cache folder", volume.display());
+                    process::exit(1);
+                }),
+                None => CacheManager::default_manager().unwrap_or_else(|| {
+                    eprintln!("Couldn't find cache folder");
                     process::exit(1);
+                }),
+            };
+            let cache_files = manager.list(&tools);
+            let cleaning = CacheCleaning {
+                remove_missing_files: prune,
+                removed_prefixes: remove_prefix,
+                max_file_size: max_size,
+            };
+            let clean = cleaning.remove_missing_files || !cleaning.removed_prefixes.is_empty() || cleaning.max_file_size.is_some();
+
+            let mut text_messages = Messages::new();
+            for cache_file in &cache_files {
+                let file_name = cache_file.path.file_name().unwrap_or_default().to_string_lossy();
+                if clear {
+                    match manager.clear(cache_file) {
+                        Ok(()) => text_messages.messages.push(format!("Removed {}", file_name)),
+                        Err(diagnostic) => text_messages.add_diagnostic(diagnostic),
+                    }
+                } else if clean {
+                    match manager.clean(cache_file, &cleaning) {
+                        Ok(info) => text_messages.messages.push(format!(
+                            "Removed {} entries from {}, {} entries left ({})",
+                            info.removed_entries,
+                            file_name,
+                            info.remaining_entries,
+                            info.file_size.file_size(options::BINARY).unwrap()
+                        )),
+                        Err(diagnostic) => text_messages.add_diagnostic(diagnostic),
+                    }
                 }
             }
 
-            #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
-            vr.print_results();
-            vr.get_text_messages().print_messages();
+            if list || !(clear || clean) {
+                println!("Cache folder {}", manager.get_folder().display());
+                for cache_file in manager.list(&tools) {
+                    println!(
+                        "{:?} \"{}\" - {} entries ({})",
+                        cache_file.tool,
+                        cache_file.path.file_name().unwrap_or_default().to_string_lossy(),
+                        cache_file.entries.map(|e| e.to_string()).unwrap_or_else(|| "unreadable".to_string()),
+                        cache_file.file_size.file_size(options::BINARY).unwrap()
+                    );
+                }
+            }
+            text_messages.print_messages();
+            if !text_messages.errors.is_empty() {
+                process::exit(1);
+            }
         }
         Commands::Tester { test_image } => {
             if test_image {
@@ -433,3 +673,56 @@ fn main() {
         }
     }
 }
+
+/// Runs any tool(or loads its previous results) and then saves and prints its results
+/// Search settings are not used when results are loaded, so directories are not required then
+fn run_tool<T: Tool>(tool: &mut T, search_settings: SearchSettings, file_to_save: &FileToSave, load_results: &LoadResults) {
+    match load_results.file_name() {
+        Some(file_name) => match ExportedResults::load_from_file(file_name) {
+            Ok(results) => tool.load_results(results),
+            Err(diagnostic) => {
+                eprintln!("{}", diagnostic);
+                process::exit(1);
+            }
+        },
+        None => {
+            tool.set_search_settings(search_settings);
+            tool.run(None, None);
+        }
+    }
+
+    if let Some(file_name) = file_to_save.file_name() {
+        match file_to_save.export_format {
+            ExportFormat::Text => {
+                if !tool.save_results_to_file(file_name) {
+                    tool.get_text_messages().print_messages();
+                    process::exit(1);
+                }
+            }
+            format => {
+                if let Err(diagnostic) = tool.get_exported_results().save_to_file(file_name, format) {
+                    eprintln!("{}", diagnostic);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))] // This will show too much probably unnecessary data to debug, comment line only if needed
+    tool.print_results();
+    tool.get_text_messages().print_messages();
+}
+
+fn print_watch_event(event: WatchEvent) {
+    match event {
+        WatchEvent::InitialScanFinished { files } => println!("Initial scan finished, watching {} files", files),
+        WatchEvent::Duplicate { path, group } => {
+            let others: Vec<String> = group.iter().filter(|e| **e != path).map(|e| format!("\"{}\"", e.display())).collect();
+            println!("Duplicate \"{}\" - same content as {}", path.display(), others.join(", "));
+        }
+        WatchEvent::EmptyFile { path } => println!("Empty file \"{}\"", path.display()),
+        WatchEvent::BrokenFile { path, error_string } => println!("Broken file \"{}\" - {}", path.display(), error_string),
+        WatchEvent::Resolved { tool, path } => println!("Resolved {:?} \"{}\"", tool, path.display()),
+        WatchEvent::Warning(diagnostic) => eprintln!("{}", diagnostic.text),
+    }
+}
@@ -2,23 +2,24 @@ use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufWriter;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime};
-use std::{mem, thread};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 
 use crossbeam_channel::Receiver;
 use mime_guess::get_mime_extensions;
 use rayon::prelude::*;
 
-use crate::common::{Common, LOOP_DURATION};
-use crate::common_dir_traversal::{CheckingMethod, DirTraversalBuilder, DirTraversalResult, FileEntry, ProgressData};
+use crate::common::Common;
+use crate::common_dir_traversal::{DirTraversalBuilder, DirTraversalResult, FileEntry};
 use crate::common_directory::Directories;
+use crate::common_export::{ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
+use crate::common_tool::{SearchSettings, Tool};
 use crate::common_traits::*;
 
 // This adds several workarounds for bugs/invalid recognizing types by external libraries
@@ -215,6 +216,7 @@ impl BadExtensions {
             .allowed_extensions(self.allowed_extensions.clone())
             .excluded_items(self.excluded_items.clone())
             .recursive_search(self.recursive_search)
+            .max_stage(1)
             .build()
             .run();
         match result {
@@ -226,7 +228,7 @@ impl BadExtensions {
                 if let Some(files_to_check) = grouped_file_entries.get(&()) {
                     self.files_to_check = files_to_check.clone();
                 }
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages.extend_diagnostics(warnings);
                 Common::print_time(start_time, SystemTime::now(), "check_files".to_string());
                 true
             }
@@ -244,46 +246,23 @@ impl BadExtensions {
 
         let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let entries_to_check = self.files_to_check.len();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: CheckingMethod::None,
-                        current_stage: 1,
-                        max_stage: 1,
-                        entries_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        entries_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::CheckingExtensions, 1, 1).entries_to_check(self.files_to_check.len()),
+        );
 
         let mut files_to_check = Default::default();
         mem::swap(&mut files_to_check, &mut self.files_to_check);
 
-        //// PROGRESS THREAD END
         self.bad_extensions_files = files_to_check
             .into_par_iter() // TODO into par iter after
             .map(|file_entry| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
                     check_was_breaked.store(true, Ordering::Relaxed);
                     return None;
                 }
+                progress_reporter.set_current_path(&file_entry.path);
 
                 let current_extension;
 
@@ -372,9 +351,7 @@ impl BadExtensions {
             .map(|file_entry| file_entry.unwrap())
             .collect::<Vec<_>>();
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         // Break if stop was clicked
         if check_was_breaked.load(Ordering::Relaxed) {
@@ -398,6 +375,50 @@ impl Default for BadExtensions {
     }
 }
 
+impl Tool for BadExtensions {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_bad_extensions_files(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("bad_extensions", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        for entry in results.groups.into_iter().flatten() {
+            self.bad_extensions_files.push(BadFileEntry {
+                current_extension: entry.get_property("current_extension"),
+                proper_extensions: entry.get_property("proper_extensions"),
+                path: entry.path,
+                modified_date: entry.modified_date,
+                size: entry.size,
+            });
+        }
+        self.information.number_of_files_with_bad_extension = self.bad_extensions_files.len();
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_files_with_bad_extension
+    }
+}
+
 impl DebugPrint for BadExtensions {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -435,7 +456,11 @@ impl SaveResults for BadExtensions {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -446,7 +471,11 @@ impl SaveResults for BadExtensions {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -463,6 +492,21 @@ impl SaveResults for BadExtensions {
     }
 }
 
+impl ExportResults for BadExtensions {
+    fn get_exported_results(&self) -> ExportedResults {
+        let mut results = ExportedResults::new("bad_extensions", false);
+        for file_entry in &self.bad_extensions_files {
+            results.add_entry(
+                ExportedEntry::new(&file_entry.path, file_entry.size, file_entry.modified_date)
+                    .info(format!("{} -> {}", file_entry.current_extension, file_entry.proper_extensions))
+                    .property("current_extension", file_entry.current_extension.clone())
+                    .property("proper_extensions", file_entry.proper_extensions.clone()),
+            );
+        }
+        results
+    }
+}
+
 impl PrintResults for BadExtensions {
     /// Print information's about duplicated entries
     /// Only needed for CLI
@@ -1,33 +1,28 @@
 use std::collections::BTreeMap;
+use std::fs;
 use std::fs::{File, Metadata};
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
-use std::sync::atomic::Ordering;
-use std::sync::atomic::{AtomicBool, AtomicU64};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
 
 use crossbeam_channel::Receiver;
 use humansize::{file_size_opts as options, FileSize};
 use rayon::prelude::*;
 
-use crate::common::{Common, LOOP_DURATION};
+use crate::common::Common;
 use crate::common_directory::Directories;
+use crate::common_export::{ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
-use crate::common_traits::{DebugPrint, PrintResults, SaveResults};
+use crate::common_journal::Journal;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
+use crate::common_remove::RemovalMethod;
+use crate::common_tool::{SearchSettings, Tool};
+use crate::common_traits::{DebugPrint, ExportResults, PrintResults, SaveResults};
 use crate::flc;
 use crate::localizer_core::generate_translation_hashmap;
 
-#[derive(Debug)]
-pub struct ProgressData {
-    pub files_checked: usize,
-}
-
 #[derive(Clone)]
 pub struct FileEntry {
     pub path: PathBuf,
@@ -64,6 +59,8 @@ pub struct BigFile {
     recursive_search: bool,
     number_of_files_to_check: usize,
     delete_method: DeleteMethod,
+    removal_method: RemovalMethod,
+    journal: Option<Journal>,
     stopped_search: bool,
 }
 
@@ -79,6 +76,8 @@ impl BigFile {
             recursive_search: true,
             number_of_files_to_check: 50,
             delete_method: DeleteMethod::None,
+            removal_method: RemovalMethod::Permanent,
+            journal: None,
             stopped_search: false,
         }
     }
@@ -112,6 +111,16 @@ impl BigFile {
         self.delete_method = delete_method;
     }
 
+    /// Decides if removed files are deleted permanently, moved to trash or to quarantine
+    pub fn set_removal_method(&mut self, removal_method: RemovalMethod) {
+        self.removal_method = removal_method;
+    }
+
+    /// Removed files are saved to journal, so they can be restored later
+    pub fn set_journal(&mut self, journal: Option<Journal>) {
+        self.journal = journal;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
         self.recursive_search = recursive_search;
     }
@@ -135,42 +144,18 @@ impl BigFile {
             folders_to_check.push(id.clone());
         }
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicU64::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
+        let progress_reporter = ProgressReporter::new(progress_sender, ProgressData::new(ProgressStage::CollectingFiles, 0, 0));
 
-        //// PROGRESS THREAD END
         while !folders_to_check.is_empty() {
             if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
+                progress_reporter.end();
                 return false;
             }
 
             let segments: Vec<_> = folders_to_check
                 .par_iter()
                 .map(|current_folder| {
+                    progress_reporter.set_current_path(current_folder);
                     let mut dir_result = vec![];
                     let mut warnings = vec![];
                     let mut fe_result = vec![];
@@ -178,10 +163,17 @@ impl BigFile {
                     let read_dir = match fs::read_dir(&current_folder) {
                         Ok(t) => t,
                         Err(e) => {
-                            warnings.push(flc!(
-                                "core_cannot_open_dir",
-                                generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                            ));
+                            warnings.push(
+                                Diagnostic::warning(
+                                    DiagnosticKind::CannotOpenDir,
+                                    flc!(
+                                        "core_cannot_open_dir",
+                                        generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                    ),
+                                )
+                                .path(current_folder)
+                                .io_error(&e),
+                            );
                             return (dir_result, warnings, fe_result);
                         }
                     };
@@ -191,20 +183,34 @@ impl BigFile {
                         let entry_data = match entry {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_entry_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadEntry,
+                                        flc!(
+                                            "core_cannot_read_entry_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
                         let metadata: Metadata = match entry_data.metadata() {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_metadata_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadMetadata,
+                                        flc!(
+                                            "core_cannot_read_metadata_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
@@ -226,22 +232,29 @@ impl BigFile {
                             if self.directories.exclude_other_filesystems() {
                                 match self.directories.is_on_other_filesystems(&next_folder) {
                                     Ok(true) => continue 'dir,
-                                    Err(e) => warnings.push(e.to_string()),
+                                    Err(e) => warnings.push(e),
                                     _ => (),
                                 }
                             }
 
                             dir_result.push(next_folder);
                         } else if metadata.is_file() {
-                            atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                            progress_reporter.add_entries(1);
+                            progress_reporter.add_bytes(metadata.len());
 
                             let file_name_lowercase: String = match entry_data.file_name().into_string() {
                                 Ok(t) => t,
                                 Err(_inspected) => {
-                                    warnings.push(flc!(
-                                        "core_file_not_utf8_name",
-                                        generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
-                                    ));
+                                    warnings.push(
+                                        Diagnostic::warning(
+                                            DiagnosticKind::NonUtf8Name,
+                                            flc!(
+                                                "core_file_not_utf8_name",
+                                                generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
+                                            ),
+                                        )
+                                        .path(&entry_data.path()),
+                                    );
                                     continue 'dir;
                                 }
                             }
@@ -263,18 +276,31 @@ impl BigFile {
                                     Ok(t) => match t.duration_since(UNIX_EPOCH) {
                                         Ok(d) => d.as_secs(),
                                         Err(_inspected) => {
-                                            warnings.push(flc!(
-                                                "core_file_modified_before_epoch",
-                                                generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
-                                            ));
+                                            warnings.push(
+                                                Diagnostic::warning(
+                                                    DiagnosticKind::ModifiedBeforeEpoch,
+                                                    flc!(
+                                                        "core_file_modified_before_epoch",
+                                                        generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
+                                                    ),
+                                                )
+                                                .path(&current_file_name),
+                                            );
                                             0
                                         }
                                     },
                                     Err(e) => {
-                                        warnings.push(flc!(
-                                            "core_file_no_modification_date",
-                                            generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
-                                        ));
+                                        warnings.push(
+                                            Diagnostic::warning(
+                                                DiagnosticKind::NoModificationDate,
+                                                flc!(
+                                                    "core_file_no_modification_date",
+                                                    generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
+                                                ),
+                                            )
+                                            .path(&current_file_name)
+                                            .io_error(&e),
+                                        );
                                         0
                                     }
                                 },
@@ -293,7 +319,8 @@ impl BigFile {
             // Process collected data
             for (segment, warnings, fe_result) in segments {
                 folders_to_check.extend(segment);
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages
+                    .extend_diagnostics(warnings.into_iter().map(|warning| warning.stage(ProgressStage::CollectingFiles)));
                 for (size, fe) in fe_result {
                     self.big_files.entry(size).or_insert_with(Vec::new);
                     self.big_files.get_mut(&size).unwrap().push(fe);
@@ -301,9 +328,7 @@ impl BigFile {
             }
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         // Extract n biggest files to new TreeMap
         let mut new_map: BTreeMap<u64, Vec<FileEntry>> = Default::default();
@@ -359,8 +384,15 @@ impl BigFile {
             DeleteMethod::Delete => {
                 for vec_file_entry in self.big_files.values() {
                     for file_entry in vec_file_entry {
-                        if fs::remove_file(file_entry.path.clone()).is_err() {
-                            self.text_messages.warnings.push(file_entry.path.display().to_string());
+                        match self.removal_method.remove(&file_entry.path, self.journal.as_ref()) {
+                            Ok(journal_warning) => self.text_messages.extend_diagnostics(journal_warning),
+                            Err(e) => {
+                                self.text_messages.add_diagnostic(
+                                    Diagnostic::warning(DiagnosticKind::CannotRemoveFile, file_entry.path.display().to_string())
+                                        .path(&file_entry.path)
+                                        .io_error(&e),
+                                );
+                            }
                         }
                     }
                 }
@@ -380,6 +412,51 @@ impl Default for BigFile {
     }
 }
 
+impl Tool for BigFile {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_big_files(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("big_files", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        for entry in results.groups.into_iter().flatten() {
+            self.big_files.entry(entry.size).or_default().push(FileEntry {
+                path: entry.path,
+                size: entry.size,
+                modified_date: entry.modified_date,
+            });
+            self.information.number_of_real_files += 1;
+        }
+        self.delete_files();
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_real_files
+    }
+}
+
 impl DebugPrint for BigFile {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -421,7 +498,11 @@ impl SaveResults for BigFile {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -432,7 +513,11 @@ impl SaveResults for BigFile {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -452,6 +537,18 @@ impl SaveResults for BigFile {
     }
 }
 
+impl ExportResults for BigFile {
+    fn get_exported_results(&self) -> ExportedResults {
+        let mut results = ExportedResults::new("big_files", false);
+        for (size, files) in self.big_files.iter().rev() {
+            for file_entry in files {
+                results.add_entry(ExportedEntry::new(&file_entry.path, *size, file_entry.modified_date));
+            }
+        }
+        results
+    }
+}
+
 impl PrintResults for BigFile {
     fn print_results(&self) {
         let start_time: SystemTime = SystemTime::now();
@@ -1,36 +1,33 @@
 use std::collections::BTreeMap;
 use std::fs::{File, Metadata};
 use std::io::prelude::*;
-use std::io::{BufReader, BufWriter};
+use std::io::BufWriter;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, mem, panic, thread};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, mem, panic};
 
 use crossbeam_channel::Receiver;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::common::{open_cache_folder, Common, LOOP_DURATION};
+use crate::common::Common;
+use crate::common_cache::{load_cache_from_file_with_identities, save_cache_to_file_with_identities, CacheEntry, MovedFiles};
 use crate::common_directory::Directories;
+use crate::common_export::{ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_journal::Journal;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
+use crate::common_remove::RemovalMethod;
+use crate::common_tool::{SearchSettings, Tool};
 use crate::common_traits::*;
+use crate::common_volume::Volumes;
 use crate::flc;
 use crate::localizer_core::generate_translation_hashmap;
 use crate::similar_images::{AUDIO_FILES_EXTENSIONS, IMAGE_RS_BROKEN_FILES_EXTENSIONS, ZIP_FILES_EXTENSIONS};
 
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub files_checked: usize,
-    pub files_to_check: usize,
-}
-
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum DeleteMethod {
     None,
@@ -46,6 +43,21 @@ pub struct FileEntry {
     pub error_string: String,
 }
 
+impl CacheEntry for FileEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TypeOfFile {
     Unknown = -1,
@@ -77,6 +89,8 @@ pub struct BrokenFiles {
     excluded_items: ExcludedItems,
     recursive_search: bool,
     delete_method: DeleteMethod,
+    removal_method: RemovalMethod,
+    journal: Option<Journal>,
     stopped_search: bool,
     use_cache: bool,
     delete_outdated_cache: bool, // TODO add this to GUI
@@ -94,6 +108,8 @@ impl BrokenFiles {
             excluded_items: ExcludedItems::new(),
             files_to_check: Default::default(),
             delete_method: DeleteMethod::None,
+            removal_method: RemovalMethod::Permanent,
+            journal: None,
             stopped_search: false,
             broken_files: Default::default(),
             use_cache: true,
@@ -136,6 +152,16 @@ impl BrokenFiles {
         self.delete_method = delete_method;
     }
 
+    /// Decides if removed files are deleted permanently, moved to trash or to quarantine
+    pub fn set_removal_method(&mut self, removal_method: RemovalMethod) {
+        self.removal_method = removal_method;
+    }
+
+    /// Removed files are saved to journal, so they can be restored later
+    pub fn set_journal(&mut self, journal: Option<Journal>) {
+        self.journal = journal;
+    }
+
     pub fn set_save_also_as_json(&mut self, save_also_as_json: bool) {
         self.save_also_as_json = save_also_as_json;
     }
@@ -177,45 +203,18 @@ impl BrokenFiles {
             folders_to_check.push(id.clone());
         }
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 1,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(progress_sender, ProgressData::new(ProgressStage::CollectingFiles, 0, 1));
 
         while !folders_to_check.is_empty() {
             if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
+                progress_reporter.end();
                 return false;
             }
 
             let segments: Vec<_> = folders_to_check
                 .par_iter()
                 .map(|current_folder| {
+                    progress_reporter.set_current_path(current_folder);
                     let mut dir_result = vec![];
                     let mut warnings = vec![];
                     let mut fe_result = vec![];
@@ -223,10 +222,17 @@ impl BrokenFiles {
                     let read_dir = match fs::read_dir(&current_folder) {
                         Ok(t) => t,
                         Err(e) => {
-                            warnings.push(flc!(
-                                "core_cannot_open_dir",
-                                generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                            ));
+                            warnings.push(
+                                Diagnostic::warning(
+                                    DiagnosticKind::CannotOpenDir,
+                                    flc!(
+                                        "core_cannot_open_dir",
+                                        generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                    ),
+                                )
+                                .path(current_folder)
+                                .io_error(&e),
+                            );
                             return (dir_result, warnings, fe_result);
                         }
                     };
@@ -236,20 +242,34 @@ impl BrokenFiles {
                         let entry_data = match entry {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_entry_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadEntry,
+                                        flc!(
+                                            "core_cannot_read_entry_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
                         let metadata: Metadata = match entry_data.metadata() {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_metadata_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadMetadata,
+                                        flc!(
+                                            "core_cannot_read_metadata_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
@@ -271,22 +291,29 @@ impl BrokenFiles {
                             if self.directories.exclude_other_filesystems() {
                                 match self.directories.is_on_other_filesystems(&next_folder) {
                                     Ok(true) => continue 'dir,
-                                    Err(e) => warnings.push(e.to_string()),
+                                    Err(e) => warnings.push(e),
                                     _ => (),
                                 }
                             }
 
                             dir_result.push(next_folder);
                         } else if metadata.is_file() {
-                            atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                            progress_reporter.add_entries(1);
+                            progress_reporter.add_bytes(metadata.len());
 
                             let file_name_lowercase: String = match entry_data.file_name().into_string() {
                                 Ok(t) => t,
                                 Err(_inspected) => {
-                                    warnings.push(flc!(
-                                        "core_file_not_utf8_name",
-                                        generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
-                                    ));
+                                    warnings.push(
+                                        Diagnostic::warning(
+                                            DiagnosticKind::NonUtf8Name,
+                                            flc!(
+                                                "core_file_not_utf8_name",
+                                                generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
+                                            ),
+                                        )
+                                        .path(&entry_data.path()),
+                                    );
                                     continue 'dir;
                                 }
                             }
@@ -312,18 +339,31 @@ impl BrokenFiles {
                                     Ok(t) => match t.duration_since(UNIX_EPOCH) {
                                         Ok(d) => d.as_secs(),
                                         Err(_inspected) => {
-                                            warnings.push(flc!(
-                                                "core_file_modified_before_epoch",
-                                                generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
-                                            ));
+                                            warnings.push(
+                                                Diagnostic::warning(
+                                                    DiagnosticKind::ModifiedBeforeEpoch,
+                                                    flc!(
+                                                        "core_file_modified_before_epoch",
+                                                        generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
+                                                    ),
+                                                )
+                                                .path(&current_file_name),
+                                            );
                                             0
                                         }
                                     },
                                     Err(e) => {
-                                        warnings.push(flc!(
-                                            "core_file_no_modification_date",
-                                            generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
-                                        ));
+                                        warnings.push(
+                                            Diagnostic::warning(
+                                                DiagnosticKind::NoModificationDate,
+                                                flc!(
+                                                    "core_file_no_modification_date",
+                                                    generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
+                                                ),
+                                            )
+                                            .path(&current_file_name)
+                                            .io_error(&e),
+                                        );
                                         0
                                     }
                                 },
@@ -345,16 +385,15 @@ impl BrokenFiles {
             // Process collected data
             for (segment, warnings, fe_result) in segments {
                 folders_to_check.extend(segment);
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages
+                    .extend_diagnostics(warnings.into_iter().map(|warning| warning.stage(ProgressStage::CollectingFiles)));
                 for (name, fe) in fe_result {
                     self.files_to_check.insert(name, fe);
                 }
             }
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         Common::print_time(start_time, SystemTime::now(), "check_files".to_string());
         true
@@ -362,22 +401,31 @@ impl BrokenFiles {
     fn look_for_broken_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let system_time = SystemTime::now();
 
-        let loaded_hash_map;
+        let loaded_hash_map: BTreeMap<String, FileEntry>;
+        let moved_files: MovedFiles<FileEntry>;
+        let volumes = if self.use_cache {
+            Volumes::mounted_for(&self.directories.included_directories)
+        } else {
+            Volumes::default()
+        };
 
         let mut records_already_cached: BTreeMap<String, FileEntry> = Default::default();
         let mut non_cached_files_to_check: BTreeMap<String, FileEntry> = Default::default();
 
         if self.use_cache {
-            loaded_hash_map = match load_cache_from_file(&mut self.text_messages, self.delete_outdated_cache) {
-                Some(t) => t,
-                None => Default::default(),
-            };
+            let (cached_hash_map, cached_moved_files) =
+                load_cache_from_file_with_identities(&get_cache_file(), &volumes, self.delete_outdated_cache, &mut self.text_messages).unwrap_or_default();
+            loaded_hash_map = cached_hash_map;
+            moved_files = cached_moved_files;
 
             for (name, file_entry) in &self.files_to_check {
                 #[allow(clippy::if_same_then_else)]
                 if !loaded_hash_map.contains_key(name) {
-                    // If loaded data doesn't contains current image info
-                    non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                    // If loaded data doesn't contains current image info, but file may be moved or renamed since previous scan
+                    match moved_files.find(&file_entry.path) {
+                        Some(moved_entry) => records_already_cached.insert(name.clone(), moved_entry),
+                        None => non_cached_files_to_check.insert(name.clone(), file_entry.clone()),
+                    };
                 } else if file_entry.size != loaded_hash_map.get(name).unwrap().size || file_entry.modified_date != loaded_hash_map.get(name).unwrap().modified_date {
                     // When size or modification date of image changed, then it is clear that is different image
                     non_cached_files_to_check.insert(name.clone(), file_entry.clone());
@@ -388,110 +436,38 @@ impl BrokenFiles {
             }
         } else {
             loaded_hash_map = Default::default();
+            moved_files = Default::default();
             mem::swap(&mut self.files_to_check, &mut non_cached_files_to_check);
         }
 
         let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let files_to_check = non_cached_files_to_check.len();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 1,
-                        max_stage: 1,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::CheckingBrokenFiles, 1, 1)
+                .entries_to_check(non_cached_files_to_check.len())
+                .bytes_to_check(non_cached_files_to_check.values().map(|e| e.size).sum()),
+        );
+
         let mut vec_file_entry: Vec<FileEntry> = non_cached_files_to_check
             .into_par_iter()
-            .map(|(_, mut file_entry)| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+            .map(|(_, file_entry)| {
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
                     check_was_breaked.store(true, Ordering::Relaxed);
                     return None;
                 }
+                progress_reporter.set_current_path(&file_entry.path);
+                progress_reporter.add_bytes(file_entry.size);
 
-                match file_entry.type_of_file {
-                    TypeOfFile::Image => {
-                        let file_entry_clone = file_entry.clone();
-
-                        let result = panic::catch_unwind(|| {
-                            match image::open(&file_entry.path) {
-                                Ok(_) => Some(None),
-                                Err(t) => {
-                                    let error_string = t.to_string();
-                                    // This error is a problem with image library, remove check when https://github.com/image-rs/jpeg-decoder/issues/130 will be fixed
-                                    if !error_string.contains("spectral selection is not allowed in non-progressive scan") {
-                                        file_entry.error_string = error_string;
-                                        Some(Some(file_entry))
-                                    } else {
-                                        Some(None)
-                                    }
-                                }
-                            }
-                        });
-
-                        // If image crashed during opening, we just skip checking its hash and go on
-                        if let Ok(image_result) = result {
-                             image_result
-                        } else {
-                            println!("Image-rs library crashed when opening \"{:?}\" image, please check if problem happens with latest image-rs version(this can be checked via https://github.com/qarmin/ImageOpening tool) and if it is not reported, please report bug here - https://github.com/image-rs/image/issues", file_entry_clone.path);
-                             Some(Some(file_entry_clone))
-                        }
-                    }
-                    TypeOfFile::ArchiveZip => match fs::File::open(&file_entry.path) {
-                        Ok(file) => match zip::ZipArchive::new(file) {
-                            Ok(_) => Some(None),
-                            Err(e) => {
-                                // TODO Maybe filter out unnecessary types of errors
-                                file_entry.error_string = e.to_string();
-                                Some(Some(file_entry))
-                            }
-                        },
-                        Err(_inspected) => Some(None), // TODO maybe throw error or something
-                    },
-                    #[cfg(feature = "broken_audio")]
-                    TypeOfFile::Audio => match fs::File::open(&file_entry.path) {
-                        Ok(file) => match rodio::Decoder::new(BufReader::new(file)) {
-                            Ok(_) => Some(None),
-                            Err(e) => {
-                                file_entry.error_string = e.to_string();
-                                Some(Some(file_entry))
-                            }
-                        },
-                        Err(_inspected) => Some(None), // TODO maybe throw error or something
-                    },
-
-                    // This means that cache read invalid value because maybe cache comes from different czkawka version
-                    TypeOfFile::Unknown => Some(None),
-                }
+                Some(check_broken_file(file_entry))
             })
             .while_some()
             .filter(|file_entry| file_entry.is_some())
             .map(|file_entry| file_entry.unwrap())
             .collect::<Vec<FileEntry>>();
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         // Break if stop was clicked
         if check_was_breaked.load(Ordering::Relaxed) {
@@ -518,7 +494,7 @@ impl BrokenFiles {
             for (_name, file_entry) in loaded_hash_map {
                 all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
             }
-            save_cache_to_file(&all_results, &mut self.text_messages, self.save_also_as_json);
+            save_cache_to_file(&all_results, &moved_files, &volumes, &mut self.text_messages, self.save_also_as_json);
         }
 
         self.information.number_of_broken_files = self.broken_files.len();
@@ -537,8 +513,15 @@ impl BrokenFiles {
         match self.delete_method {
             DeleteMethod::Delete => {
                 for file_entry in self.broken_files.iter() {
-                    if fs::remove_file(&file_entry.path).is_err() {
-                        self.text_messages.warnings.push(file_entry.path.display().to_string());
+                    match self.removal_method.remove(&file_entry.path, self.journal.as_ref()) {
+                        Ok(journal_warning) => self.text_messages.extend_diagnostics(journal_warning),
+                        Err(e) => {
+                            self.text_messages.add_diagnostic(
+                                Diagnostic::warning(DiagnosticKind::CannotRemoveFile, file_entry.path.display().to_string())
+                                    .path(&file_entry.path)
+                                    .io_error(&e),
+                            );
+                        }
                     }
                 }
             }
@@ -557,6 +540,54 @@ impl Default for BrokenFiles {
     }
 }
 
+impl Tool for BrokenFiles {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_broken_files(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("broken_files", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        for entry in results.groups.into_iter().flatten() {
+            let file_name_lowercase = entry.path.file_name().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+            self.broken_files.push(FileEntry {
+                type_of_file: check_extension_avaibility(&file_name_lowercase),
+                error_string: entry.info.unwrap_or_default(),
+                path: entry.path,
+                modified_date: entry.modified_date,
+                size: entry.size,
+            });
+        }
+        self.information.number_of_broken_files = self.broken_files.len();
+        self.delete_files();
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_broken_files
+    }
+}
+
 impl DebugPrint for BrokenFiles {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -597,7 +628,11 @@ impl SaveResults for BrokenFiles {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -608,7 +643,11 @@ impl SaveResults for BrokenFiles {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -625,6 +664,16 @@ impl SaveResults for BrokenFiles {
     }
 }
 
+impl ExportResults for BrokenFiles {
+    fn get_exported_results(&self) -> ExportedResults {
+        let mut results = ExportedResults::new("broken_files", false);
+        for file_entry in &self.broken_files {
+            results.add_entry(ExportedEntry::new(&file_entry.path, file_entry.size, file_entry.modified_date).info(file_entry.error_string.clone()));
+        }
+        results
+    }
+}
+
 impl PrintResults for BrokenFiles {
     /// Print information's about duplicated entries
     /// Only needed for CLI
@@ -639,85 +688,80 @@ impl PrintResults for BrokenFiles {
     }
 }
 
-fn save_cache_to_file(old_hashmap: &BTreeMap<String, FileEntry>, text_messages: &mut Messages, save_also_as_json: bool) {
+fn save_cache_to_file(old_hashmap: &BTreeMap<String, FileEntry>, moved_files: &MovedFiles<FileEntry>, volumes: &Volumes, text_messages: &mut Messages, save_also_as_json: bool) {
     let mut hashmap: BTreeMap<String, FileEntry> = Default::default();
     for (path, fe) in old_hashmap {
         if fe.size > 1024 {
             hashmap.insert(path.clone(), fe.clone());
         }
     }
-    let hashmap = &hashmap;
+    save_cache_to_file_with_identities(&get_cache_file(), &hashmap, Some(moved_files), volumes, save_also_as_json, text_messages);
+}
 
-    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) = open_cache_folder(&get_cache_file(), true, save_also_as_json, &mut text_messages.warnings) {
-        {
-            let writer = BufWriter::new(file_handler.unwrap()); // Unwrap because cannot fail here
-            if let Err(e) = bincode::serialize_into(writer, hashmap) {
-                text_messages
-                    .warnings
-                    .push(format!("Cannot write data to cache file {}, reason {}", cache_file.display(), e));
-                return;
-            }
-        }
-        if save_also_as_json {
-            if let Some(file_handler_json) = file_handler_json {
-                let writer = BufWriter::new(file_handler_json);
-                if let Err(e) = serde_json::to_writer(writer, hashmap) {
-                    text_messages
-                        .warnings
-                        .push(format!("Cannot write data to cache file {}, reason {}", cache_file_json.display(), e));
-                    return;
+/// Opens file with library matching its type, returns entry with error when file is broken
+/// Entry with empty error is returned when library crashed while opening file
+pub(crate) fn check_broken_file(mut file_entry: FileEntry) -> Option<FileEntry> {
+    match file_entry.type_of_file {
+        TypeOfFile::Image => {
+            let file_entry_clone = file_entry.clone();
+
+            let result = panic::catch_unwind(|| {
+                match image::open(&file_entry.path) {
+                    Ok(_) => None,
+                    Err(t) => {
+                        let error_string = t.to_string();
+                        // This error is a problem with image library, remove check when https://github.com/image-rs/jpeg-decoder/issues/130 will be fixed
+                        if !error_string.contains("spectral selection is not allowed in non-progressive scan") {
+                            file_entry.error_string = error_string;
+                            Some(file_entry)
+                        } else {
+                            None
+                        }
+                    }
                 }
+            });
+
+            // If image crashed during opening, we just skip checking its hash and go on
+            if let Ok(image_result) = result {
+                image_result
+            } else {
+                println!("Image-rs library crashed when opening \"{:?}\" image, please check if problem happens with latest image-rs version(this can be checked via https://github.com/qarmin/ImageOpening tool) and if it is not reported, please report bug here - https://github.com/image-rs/image/issues", file_entry_clone.path);
+                Some(file_entry_clone)
             }
         }
-
-        text_messages.messages.push(format!("Properly saved to file {} cache entries.", hashmap.len()));
-    }
-}
-
-fn load_cache_from_file(text_messages: &mut Messages, delete_outdated_cache: bool) -> Option<BTreeMap<String, FileEntry>> {
-    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) = open_cache_folder(&get_cache_file(), false, true, &mut text_messages.warnings) {
-        let mut hashmap_loaded_entries: BTreeMap<String, FileEntry>;
-        if let Some(file_handler) = file_handler {
-            let reader = BufReader::new(file_handler);
-            hashmap_loaded_entries = match bincode::deserialize_from(reader) {
-                Ok(t) => t,
+        TypeOfFile::ArchiveZip => match fs::File::open(&file_entry.path) {
+            Ok(file) => match zip::ZipArchive::new(file) {
+                Ok(_) => None,
                 Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load data from cache file {}, reason {}", cache_file.display(), e));
-                    return None;
+                    // TODO Maybe filter out unnecessary types of errors
+                    file_entry.error_string = e.to_string();
+                    Some(file_entry)
                 }
-            };
-        } else {
-            let reader = BufReader::new(file_handler_json.unwrap()); // Unwrap cannot fail, because at least one file must be valid
-            hashmap_loaded_entries = match serde_json::from_reader(reader) {
-                Ok(t) => t,
+            },
+            Err(_inspected) => None, // TODO maybe throw error or something
+        },
+        #[cfg(feature = "broken_audio")]
+        TypeOfFile::Audio => match fs::File::open(&file_entry.path) {
+            Ok(file) => match rodio::Decoder::new(BufReader::new(file)) {
+                Ok(_) => None,
                 Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load data from cache file {}, reason {}", cache_file_json.display(), e));
-                    return None;
+                    file_entry.error_string = e.to_string();
+                    Some(file_entry)
                 }
-            };
-        }
-
-        // Don't load cache data if destination file not exists
-        if delete_outdated_cache {
-            hashmap_loaded_entries.retain(|src_path, _file_entry| Path::new(src_path).exists());
-        }
-
-        text_messages.messages.push(format!("Properly loaded {} cache entries.", hashmap_loaded_entries.len()));
+            },
+            Err(_inspected) => None, // TODO maybe throw error or something
+        },
 
-        return Some(hashmap_loaded_entries);
+        // This means that cache read invalid value because maybe cache comes from different czkawka version
+        TypeOfFile::Unknown => None,
     }
-    None
 }
 
 fn get_cache_file() -> String {
     "cache_broken_files.bin".to_string()
 }
 
-fn check_extension_avaibility(file_name_lowercase: &str) -> TypeOfFile {
+pub(crate) fn check_extension_avaibility(file_name_lowercase: &str) -> TypeOfFile {
     if IMAGE_RS_BROKEN_FILES_EXTENSIONS.iter().any(|e| file_name_lowercase.ends_with(e)) {
         TypeOfFile::Image
     } else if ZIP_FILES_EXTENSIONS.iter().any(|e| file_name_lowercase.ends_with(e)) {
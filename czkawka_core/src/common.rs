@@ -8,15 +8,20 @@ use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+
 /// Class for common functions used across other class/functions
 
 pub const LOOP_DURATION: u32 = 200; //ms
 
 pub struct Common();
 
-pub fn open_cache_folder(cache_file_name: &str, save_to_cache: bool, use_json: bool, warnings: &mut Vec<String>) -> Option<((Option<File>, PathBuf), (Option<File>, PathBuf))> {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
+pub fn get_cache_folder() -> Option<PathBuf> {
+    ProjectDirs::from("pl", "Qarmin", "Czkawka").map(|proj_dirs| PathBuf::from(proj_dirs.cache_dir()))
+}
+
+pub fn open_cache_folder(cache_file_name: &str, save_to_cache: bool, use_json: bool, text_messages: &mut Messages) -> Option<((Option<File>, PathBuf), (Option<File>, PathBuf))> {
+    if let Some(cache_dir) = get_cache_folder() {
         let cache_file = cache_dir.join(cache_file_name);
         let cache_file_json = cache_dir.join(cache_file_name.replace(".bin", ".json"));
 
@@ -26,30 +31,19 @@ pub fn open_cache_folder(cache_file_name: &str, save_to_cache: bool, use_json: b
         if save_to_cache {
             if cache_dir.exists() {
                 if !cache_dir.is_dir() {
-                    warnings.push(format!("Config dir {} is a file!", cache_dir.display()));
+                    text_messages.add_diagnostic(Diagnostic::warning(DiagnosticKind::InvalidCache, format!("Config dir {} is a file!", cache_dir.display())).path(&cache_dir));
                     return None;
                 }
             } else if let Err(e) = fs::create_dir_all(&cache_dir) {
-                warnings.push(format!("Cannot create config dir {}, reason {}", cache_dir.display(), e));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(DiagnosticKind::CannotCreateFile, format!("Cannot create config dir {}, reason {}", cache_dir.display(), e))
+                        .path(&cache_dir)
+                        .io_error(&e),
+                );
                 return None;
             }
 
-            file_handler_default = Some(match OpenOptions::new().truncate(true).write(true).create(true).open(&cache_file) {
-                Ok(t) => t,
-                Err(e) => {
-                    warnings.push(format!("Cannot create or open cache file {}, reason {}", cache_file.display(), e));
-                    return None;
-                }
-            });
-            if use_json {
-                file_handler_json = Some(match OpenOptions::new().truncate(true).write(true).create(true).open(&cache_file_json) {
-                    Ok(t) => t,
-                    Err(e) => {
-                        warnings.push(format!("Cannot create or open cache file {}, reason {}", cache_file_json.display(), e));
-                        return None;
-                    }
-                });
-            }
+            // Files are not opened, because they are replaced atomically when saving
         } else {
             if let Ok(t) = OpenOptions::new().read(true).open(&cache_file) {
                 file_handler_default = Some(t);
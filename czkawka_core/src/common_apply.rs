@@ -0,0 +1,227 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::common_export::ExportedResults;
+use crate::common_journal::{record_operation, Journal, JournalAction, JournalEntry};
+use crate::common_keep_policy::{KeepCandidate, KeepPolicy};
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_remove::{move_path, quarantine_path, RemovalMethod};
+use crate::duplicate::{make_hard_link, make_reflink, make_symlink, SymlinkType};
+
+/// Operation done on every file which is not kept
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApplyAction {
+    Remove(RemovalMethod),
+    HardLink,
+    Symlink(SymlinkType),
+    Reflink,
+    /// Moves files into given folder, full original path is recreated inside it, so files with same name don't collide
+    Move(PathBuf),
+}
+
+impl ApplyAction {
+    /// Links must point to file which is kept, so they can be only created in grouped results
+    pub fn needs_kept_file(&self) -> bool {
+        matches!(self, ApplyAction::HardLink | ApplyAction::Symlink(_) | ApplyAction::Reflink)
+    }
+
+    pub fn diagnostic_kind(&self) -> DiagnosticKind {
+        if self.needs_kept_file() {
+            DiagnosticKind::CannotCreateLink
+        } else {
+            DiagnosticKind::CannotRemoveFile
+        }
+    }
+
+    /// Performs action on file, done operation is saved to journal when it is given
+    /// Returned warning means that action was done, but saving it to journal failed
+    pub fn perform(&self, path: &Path, hash: Option<&str>, kept: Option<&Path>, journal: Option<&Journal>) -> io::Result<Option<Diagnostic>> {
+        let kept_file = || kept.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "No file to link to"));
+        let size = || fs::symlink_metadata(path).map(|e| e.len()).unwrap_or(0);
+        let entry = match self {
+            ApplyAction::Remove(removal_method) => return removal_method.remove_with_hash(path, hash, journal),
+            ApplyAction::HardLink => {
+                let size = size();
+                make_hard_link(kept_file()?, path)?;
+                JournalEntry::new(JournalAction::HardLink, path, size).target(kept_file()?)
+            }
+            ApplyAction::Symlink(symlink_type) => {
+                let size = size();
+                make_symlink(kept_file()?, path, *symlink_type)?;
+                JournalEntry::new(JournalAction::Symlink, path, size).target(kept_file()?)
+            }
+            // Reflinked file is still independent copy with same content, so there is nothing to restore
+            ApplyAction::Reflink => return make_reflink(kept_file()?, path).map(|_| None),
+            ApplyAction::Move(destination) => {
+                let destination_file = quarantine_path(destination, path);
+                if let Some(parent) = destination_file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let size = size();
+                move_path(path, &destination_file)?;
+                JournalEntry::new(JournalAction::Move, path, size).target(&destination_file)
+            }
+        };
+        Ok(record_operation(journal, &entry.hash(hash)))
+    }
+
+    /// Text used in dry run instead of performing action
+    pub fn describe(&self, path: &Path, kept: Option<&Path>) -> String {
+        let kept = kept.map(|e| e.display().to_string()).unwrap_or_default();
+        match self {
+            ApplyAction::Remove(removal_method) => removal_method.describe(path),
+            ApplyAction::HardLink => format!("Replace file {} with hard link to {}", path.display(), kept),
+            ApplyAction::Symlink(SymlinkType::Absolute) => format!("Replace file {} with symbolic link to {}", path.display(), kept),
+            ApplyAction::Symlink(SymlinkType::Relative) => format!("Replace file {} with relative symbolic link to {}", path.display(), kept),
+            ApplyAction::Reflink => format!("Share content of file {} with {} using reflink", path.display(), kept),
+            ApplyAction::Move(destination) => format!("Move {} to {}", path.display(), quarantine_path(destination, path).display()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ApplyInfo {
+    pub processed_files: usize,
+    pub failed_files: usize,
+    pub skipped_groups: usize,
+    pub processed_size: u64,
+}
+
+/// Applies action to saved results, entries changed since saving are skipped
+/// One file from every group(reference file if exists) is kept, results of tools which don't group files are processed entirely
+pub fn apply_to_results(mut results: ExportedResults, keep: &KeepPolicy, action: &ApplyAction, dryrun: bool, journal: Option<&Journal>, text_messages: &mut Messages) -> ApplyInfo {
+    let mut info = ApplyInfo::default();
+    if !results.grouped && action.needs_kept_file() {
+        text_messages.add_diagnostic(Diagnostic::error(
+            DiagnosticKind::InvalidSettings,
+            format!("Results of {} tool are not grouped, so files cannot be replaced with links", results.tool),
+        ));
+        return info;
+    }
+
+    let diagnostics = results.revalidate();
+    text_messages.extend_diagnostics(diagnostics);
+
+    let grouped = results.grouped;
+    for (reference, mut group) in results.into_split_groups() {
+        let kept = match reference {
+            Some(reference) => Some(reference),
+            None if grouped => match keep.file_to_keep(&group.iter().map(KeepCandidate::from).collect::<Vec<_>>()) {
+                Some(index) => Some(group.remove(index)),
+                None => {
+                    info.skipped_groups += 1;
+                    text_messages
+                        .messages
+                        .push(format!("Skipped group with {} files, because none of them matches keep rules", group.len()));
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        for entry in &group {
+            let kept_path = kept.as_ref().map(|e| e.path.as_path());
+            if dryrun {
+                text_messages.messages.push(action.describe(&entry.path, kept_path));
+                info.processed_files += 1;
+                info.processed_size += entry.size;
+                continue;
+            }
+            match action.perform(&entry.path, entry.hash.as_deref(), kept_path, journal) {
+                Ok(journal_warning) => {
+                    text_messages.extend_diagnostics(journal_warning);
+                    info.processed_files += 1;
+                    info.processed_size += entry.size;
+                }
+                Err(e) => {
+                    info.failed_files += 1;
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(action.diagnostic_kind(), format!("Failed to process {} ({})", entry.path.display(), e))
+                            .path(&entry.path)
+                            .io_error(&e),
+                    );
+                }
+            }
+        }
+    }
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::time::UNIX_EPOCH;
+
+    use crate::common_apply::{apply_to_results, ApplyAction};
+    use crate::common_export::{ExportedEntry, ExportedResults};
+    use crate::common_keep_policy::{KeepPolicy, KeepRule};
+    use crate::common_messages::Messages;
+    use crate::common_remove::{quarantine_path, RemovalMethod};
+
+    fn create_entry(path: &Path, content: &str) -> ExportedEntry {
+        fs::write(path, content).unwrap();
+        let metadata = fs::metadata(path).unwrap();
+        let modified_date = metadata.modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        ExportedEntry::new(path, metadata.len(), modified_date)
+    }
+
+    #[test]
+    fn test_apply_delete_keeps_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut results = ExportedResults::new("duplicate", true);
+        results.add_group(vec![create_entry(&dir.path().join("a"), "abc"), create_entry(&dir.path().join("b"), "abc")]);
+
+        let mut messages = Messages::new();
+        let info = apply_to_results(
+            results.clone(),
+            &KeepPolicy::new(vec![KeepRule::InPath(dir.path().join("b"))]),
+            &ApplyAction::Remove(RemovalMethod::Permanent),
+            true,
+            None,
+            &mut messages,
+        );
+        assert_eq!(info.processed_files, 1);
+        assert!(dir.path().join("a").exists());
+
+        let info = apply_to_results(
+            results,
+            &KeepPolicy::new(vec![KeepRule::InPath(dir.path().join("b"))]),
+            &ApplyAction::Remove(RemovalMethod::Permanent),
+            false,
+            None,
+            &mut messages,
+        );
+        assert_eq!((info.processed_files, info.failed_files, info.processed_size), (1, 0, 3));
+        assert!(!dir.path().join("a").exists());
+        assert!(dir.path().join("b").exists());
+    }
+
+    #[test]
+    fn test_apply_move_keeps_original_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        for folder in ["x", "y", "z"] {
+            fs::create_dir(dir.path().join(folder)).unwrap();
+        }
+        let mut results = ExportedResults::new("duplicate", true);
+        results.add_group(["x", "y", "z"].iter().map(|folder| create_entry(&dir.path().join(folder).join("a"), "abc")).collect());
+
+        let destination = dir.path().join("moved");
+        let mut messages = Messages::new();
+        let info = apply_to_results(
+            results,
+            &KeepPolicy::new(vec![KeepRule::InPath(dir.path().join("z"))]),
+            &ApplyAction::Move(destination.clone()),
+            false,
+            None,
+            &mut messages,
+        );
+        // Files with same name are moved into separate folders
+        assert_eq!((info.processed_files, info.failed_files), (2, 0));
+        assert!(quarantine_path(&destination, &dir.path().join("x").join("a")).exists());
+        assert!(quarantine_path(&destination, &dir.path().join("y").join("a")).exists());
+        assert!(dir.path().join("z").join("a").exists());
+    }
+}
@@ -0,0 +1,242 @@
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufReader, Error, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::common_dir_traversal::FileEntry;
+
+/// Separates path of archive from path of file inside it, e.g. `/home/rafal/photos.zip!/2021/a.jpg`
+pub const ARCHIVE_PATH_SEPARATOR: &str = "!/";
+
+/// Extensions which must be allowed when searching for archives, `.gz` etc. also match `.tar.gz`
+pub const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".tar", ".gz", ".tgz", ".bz2", ".tbz2", ".xz", ".txz"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveType {
+    Zip,
+    Tar,
+    TarGz,
+    TarBz2,
+    TarXz,
+}
+
+impl ArchiveType {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        [
+            (".zip", ArchiveType::Zip),
+            (".tar", ArchiveType::Tar),
+            (".tar.gz", ArchiveType::TarGz),
+            (".tgz", ArchiveType::TarGz),
+            (".tar.bz2", ArchiveType::TarBz2),
+            (".tbz2", ArchiveType::TarBz2),
+            (".tar.xz", ArchiveType::TarXz),
+            (".txz", ArchiveType::TarXz),
+        ]
+        .into_iter()
+        .find(|(extension, _)| file_name.ends_with(extension))
+        .map(|(_, archive_type)| archive_type)
+    }
+
+    fn decompressed<'a, R: Read + 'a>(self, reader: R) -> Box<dyn Read + 'a> {
+        match self {
+            ArchiveType::Zip | ArchiveType::Tar => Box::new(reader),
+            ArchiveType::TarGz => Box::new(flate2::read::GzDecoder::new(reader)),
+            ArchiveType::TarBz2 => Box::new(bzip2::read::BzDecoder::new(reader)),
+            ArchiveType::TarXz => Box::new(xz2::read::XzDecoder::new(reader)),
+        }
+    }
+}
+
+/// Reader of normal file or file inside archive
+/// Files inside archives can be read only sequentially, so seeking forward is emulated by skipping data
+pub enum FileReader<'a> {
+    File(File),
+    ArchiveEntry { reader: Box<dyn Read + 'a>, position: u64 },
+}
+
+impl FileReader<'_> {
+    /// Inside archives offset cannot be lower than current position
+    pub fn seek_to(&mut self, offset: u64) -> io::Result<()> {
+        match self {
+            FileReader::File(file) => file.seek(SeekFrom::Start(offset)).map(|_| ()),
+            FileReader::ArchiveEntry { reader, position } => {
+                if offset < *position {
+                    return Err(Error::new(ErrorKind::Unsupported, "Cannot seek backwards inside archive"));
+                }
+                *position += io::copy(&mut reader.take(offset - *position), &mut io::sink())?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Read for FileReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            FileReader::File(file) => file.read(buf),
+            FileReader::ArchiveEntry { reader, position } => {
+                let n = reader.read(buf)?;
+                *position += n as u64;
+                Ok(n)
+            }
+        }
+    }
+}
+
+pub fn archive_entry_path(archive: &Path, name_inside: &str) -> PathBuf {
+    let mut path = OsString::from(archive.as_os_str());
+    path.push(ARCHIVE_PATH_SEPARATOR);
+    path.push(name_inside);
+    PathBuf::from(path)
+}
+
+/// Returns path of archive and name of file inside it, when path points to file inside existing archive
+pub fn split_archive_entry_path(path: &Path) -> Option<(PathBuf, String)> {
+    let path = path.to_str()?;
+    path.match_indices(ARCHIVE_PATH_SEPARATOR).find_map(|(index, _)| {
+        let archive = Path::new(&path[..index]);
+        if ArchiveType::from_path(archive).is_some() && archive.is_file() {
+            Some((archive.to_path_buf(), path[index + ARCHIVE_PATH_SEPARATOR.len()..].to_string()))
+        } else {
+            None
+        }
+    })
+}
+
+pub fn is_archive_entry(path: &Path) -> bool {
+    split_archive_entry_path(path).is_some()
+}
+
+/// Files inside archives are only compared with other files, they can't be removed or replaced with links
+pub fn ensure_not_archive_entry(path: &Path) -> io::Result<()> {
+    if is_archive_entry(path) {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is inside archive and cannot be modified", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Lists normal files inside archive as virtual entries
+/// Modification date of archive is used for all of them, so cached hashes are invalidated when archive changes
+pub fn list_archive_entries(archive: &FileEntry) -> io::Result<Vec<FileEntry>> {
+    let archive_type = ArchiveType::from_path(&archive.path).ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Not supported archive type"))?;
+    let to_entry = |name_inside: &str, size: u64| FileEntry {
+        path: archive_entry_path(&archive.path, name_inside),
+        size,
+        modified_date: archive.modified_date,
+        device: archive.device,
+        ..Default::default()
+    };
+
+    let file = BufReader::new(File::open(&archive.path)?);
+    let mut entries = Vec::new();
+    if archive_type == ArchiveType::Zip {
+        let mut zip = zip::ZipArchive::new(file)?;
+        for index in 0..zip.len() {
+            // Raw access doesn't need password, so encrypted files are also listed
+            let entry = zip.by_index_raw(index)?;
+            if entry.is_file() {
+                entries.push(to_entry(entry.name(), entry.size()));
+            }
+        }
+    } else {
+        let mut tar = tar::Archive::new(archive_type.decompressed(file));
+        for entry in tar.entries()? {
+            let entry = entry?;
+            if entry.header().entry_type().is_file() {
+                entries.push(to_entry(&tar_entry_name(&entry.path()?), entry.size()));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Opens normal file or file inside archive and passes it to given function
+/// Finding file in compressed tar archive requires decompressing everything before it
+pub fn open_file<T>(path: &Path, function: impl FnOnce(&mut FileReader) -> io::Result<T>) -> io::Result<T> {
+    let (archive, name_inside) = match split_archive_entry_path(path) {
+        Some(t) => t,
+        None => return function(&mut FileReader::File(File::open(path)?)),
+    };
+    let archive_type = ArchiveType::from_path(&archive).ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Not supported archive type"))?;
+
+    let file = BufReader::new(File::open(&archive)?);
+    if archive_type == ArchiveType::Zip {
+        let mut zip = zip::ZipArchive::new(file)?;
+        let entry = zip.by_name(&name_inside)?;
+        return function(&mut FileReader::ArchiveEntry {
+            reader: Box::new(entry),
+            position: 0,
+        });
+    }
+    let mut tar = tar::Archive::new(archive_type.decompressed(file));
+    for entry in tar.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() && tar_entry_name(&entry.path()?) == name_inside {
+            return function(&mut FileReader::ArchiveEntry {
+                reader: Box::new(entry),
+                position: 0,
+            });
+        }
+    }
+    Err(Error::new(ErrorKind::NotFound, format!("{} not found inside archive {}", name_inside, archive.display())))
+}
+
+fn tar_entry_name(path: &Path) -> String {
+    path.to_string_lossy().trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::{self, Read, Write};
+
+    use crate::common_archive::{archive_entry_path, ensure_not_archive_entry, list_archive_entries, open_file};
+    use crate::common_dir_traversal::FileEntry;
+
+    #[test]
+    fn test_archive_entries() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let zip_path = dir.path().join("a.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path)?);
+        zip.start_file("dir/file.txt", Default::default())?;
+        zip.write_all(b"zip content")?;
+        zip.finish()?;
+
+        let tar_path = dir.path().join("a.tar.gz");
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(File::create(&tar_path)?, Default::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(11);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "dir/file.txt", &b"tar content"[..])?;
+        tar.into_inner()?.finish()?;
+
+        for (archive_path, content) in [(&zip_path, "zip content"), (&tar_path, "tar content")] {
+            let archive = FileEntry {
+                path: archive_path.clone(),
+                modified_date: 5,
+                ..Default::default()
+            };
+            let entries = list_archive_entries(&archive)?;
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].path, archive_entry_path(archive_path, "dir/file.txt"));
+            assert_eq!((entries[0].size, entries[0].modified_date), (11, 5));
+
+            let mut read_content = String::new();
+            open_file(&entries[0].path, |reader| {
+                reader.seek_to(4)?;
+                reader.read_to_string(&mut read_content)
+            })?;
+            assert_eq!(read_content, content[4..]);
+            assert!(ensure_not_archive_entry(&entries[0].path).is_err());
+        }
+        assert!(ensure_not_archive_entry(&zip_path).is_ok());
+        fs::remove_file(&zip_path)?;
+        assert!(ensure_not_archive_entry(&archive_entry_path(&zip_path, "dir/file.txt")).is_ok());
+        Ok(())
+    }
+}
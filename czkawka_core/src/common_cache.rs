@@ -0,0 +1,696 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Cursor, Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use fs2::FileExt;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::common::open_cache_folder;
+use crate::common_archive::split_archive_entry_path;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_volume::{is_volume_path, Volumes, VOLUME_CACHE_FOLDER};
+
+/// Every binary cache file starts with these bytes, followed by version of its content
+pub const CACHE_MAGIC: &[u8; 8] = b"CZKCACHE";
+
+/// Writes header with version and content of cache serialized with bincode
+pub fn write_versioned_cache<W: Write, T: Serialize + ?Sized>(mut writer: W, version: u32, data: &T) -> io::Result<()> {
+    writer.write_all(CACHE_MAGIC)?;
+    writer.write_all(&version.to_le_bytes())?;
+    bincode::serialize_into(&mut writer, data).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    writer.flush()
+}
+
+/// Reads header of cache and returns version of content, which must be read by caller
+pub fn read_versioned_cache_header<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut header = [0u8; CACHE_MAGIC.len() + 4];
+    reader.read_exact(&mut header)?;
+    if &header[..CACHE_MAGIC.len()] != CACHE_MAGIC {
+        return Err(Error::new(ErrorKind::InvalidData, "File is not a valid cache file"));
+    }
+    Ok(u32::from_le_bytes(header[CACHE_MAGIC.len()..].try_into().unwrap()))
+}
+
+pub fn read_cache_content<R: Read, T: DeserializeOwned>(reader: R) -> io::Result<T> {
+    bincode::deserialize_from(reader).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Exclusive lock of cache file, taken by every instance of app before saving it and released when dropped
+/// Lock is held on separate file, because cache file itself is replaced when saving
+pub struct CacheLock {
+    _file: File,
+}
+
+impl CacheLock {
+    pub fn lock(cache_file: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(cache_file.with_extension("lock"))?;
+        file.lock_exclusive()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Content is written to temporary file in the same folder, which replaces target file only when everything was written
+/// This way crash or full disk never leave partially written cache
+pub fn write_file_atomically<F>(file: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<&File>) -> io::Result<()>,
+{
+    let folder = file.parent().unwrap_or_else(|| Path::new("."));
+    let temporary_file = tempfile::Builder::new().prefix(".czkawka_cache").tempfile_in(folder)?;
+    {
+        let mut writer = BufWriter::new(temporary_file.as_file());
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    temporary_file.as_file().sync_all()?;
+    temporary_file.persist(file).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Entries of files inside archives exist as long as archive exists
+pub fn cached_file_exists(path: &Path) -> bool {
+    match split_archive_entry_path(path) {
+        Some((archive, _name_inside)) => archive.exists(),
+        None => path.exists(),
+    }
+}
+
+/// Version of caches which contain also identities of files
+const CACHE_WITH_IDENTITIES_VERSION: u32 = 1;
+
+/// Device, inode, size and modification date identify file, also after moving or renaming it inside same filesystem
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileIdentity {
+    pub device: u64,
+    pub inode: u64,
+    pub size: u64,
+    pub modified_date: u64,
+}
+
+impl FileIdentity {
+    /// None when file doesn't exists, is inside archive or system doesn't use inodes
+    #[cfg(target_family = "unix")]
+    pub fn of_file(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = fs::metadata(path).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        Some(Self {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+            size: metadata.len(),
+            modified_date: metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs(),
+        })
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    pub fn of_file(_path: &Path) -> Option<Self> {
+        None
+    }
+}
+
+/// Entry of cache which may be reused after moving file
+pub trait CacheEntry: Clone {
+    fn get_path(&self) -> &Path;
+    fn get_size(&self) -> u64;
+    fn get_modified_date(&self) -> u64;
+    fn set_path(&mut self, path: PathBuf);
+}
+
+/// Identity of file is saved only when file still has size and modification date from entry
+/// Files which don't exist anymore keep identity loaded from cache, so they may be found later in new place
+pub fn identity_of_entry<T: CacheEntry>(entry: &T, moved_files: Option<&MovedFiles<T>>) -> Option<FileIdentity> {
+    match FileIdentity::of_file(entry.get_path()) {
+        Some(identity) if identity.size == entry.get_size() && identity.modified_date == entry.get_modified_date() => Some(identity),
+        Some(_) => None,
+        None => moved_files.and_then(|moved_files| moved_files.missing_identities.get(entry.get_path()).copied()),
+    }
+}
+
+/// Cache entries of files which don't exist under saved path
+pub struct MovedFiles<T> {
+    entries: HashMap<FileIdentity, T>,
+    missing_identities: HashMap<PathBuf, FileIdentity>,
+}
+
+impl<T: CacheEntry> Default for MovedFiles<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: CacheEntry> MovedFiles<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Default::default(),
+            missing_identities: Default::default(),
+        }
+    }
+
+    pub fn add(&mut self, identity: FileIdentity, entry: T) {
+        self.missing_identities.insert(entry.get_path().to_path_buf(), identity);
+        self.entries.insert(identity, entry);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns entry of file which was moved or renamed to given path, with path already changed
+    pub fn find(&self, path: &Path) -> Option<T> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let identity = FileIdentity::of_file(path)?;
+        let mut entry = self.entries.get(&identity)?.clone();
+        entry.set_path(path.to_path_buf());
+        Some(entry)
+    }
+}
+
+/// Writes cache content together with identities of its files
+pub fn write_cache_with_identities<W: Write, T: Serialize>(writer: W, data: &T, identities: &[(Vec<u8>, FileIdentity)]) -> io::Result<()> {
+    write_versioned_cache(writer, CACHE_WITH_IDENTITIES_VERSION, &(data, identities))
+}
+
+/// Reads cache content and identities of its files, caches saved by older versions contain only content
+pub fn read_cache_with_identities<R: Read, T: DeserializeOwned>(mut reader: R) -> io::Result<(T, HashMap<PathBuf, FileIdentity>)> {
+    let mut header = [0u8; CACHE_MAGIC.len()];
+    let read_bytes = read_prefix(&mut reader, &mut header)?;
+    if read_bytes < header.len() || &header != CACHE_MAGIC {
+        let data = read_cache_content(Cursor::new(&header[..read_bytes]).chain(reader))?;
+        return Ok((data, HashMap::new()));
+    }
+    let mut reader = Cursor::new(&header[..]).chain(reader);
+    match read_versioned_cache_header(&mut reader)? {
+        CACHE_WITH_IDENTITIES_VERSION => {
+            let (data, identities): (T, Vec<(Vec<u8>, FileIdentity)>) = read_cache_content(reader)?;
+            Ok((data, identities.into_iter().map(|(path, identity)| (path_from_bytes(path), identity)).collect()))
+        }
+        version => Err(Error::new(ErrorKind::InvalidData, format!("Unsupported cache version {}", version))),
+    }
+}
+
+/// Reads as many bytes as possible to fill buffer, stops earlier only at end of file
+fn read_prefix<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut read_bytes = 0;
+    while read_bytes < buffer.len() {
+        match reader.read(&mut buffer[read_bytes..]) {
+            Ok(0) => break,
+            Ok(n) => read_bytes += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read_bytes)
+}
+
+/// Entries of cache file with identities of their files
+pub(crate) type CacheEntries<T> = Vec<(T, Option<FileIdentity>)>;
+
+/// Reads and writes cache files of one tool, with identities of files kept unchanged
+pub(crate) trait CacheFormat {
+    type Entry: CacheEntry;
+    fn read<R: Read>(reader: R) -> io::Result<CacheEntries<Self::Entry>>;
+    fn write(entries: &[(Self::Entry, Option<FileIdentity>)]) -> io::Result<Vec<u8>>;
+}
+
+pub(crate) fn read_cache_file<F: CacheFormat>(file: &Path) -> io::Result<CacheEntries<F::Entry>> {
+    F::read(BufReader::new(File::open(file)?))
+}
+
+/// Caches of tools other than duplicate finder are maps from path to entry
+pub(crate) struct ToolCacheFormat<T>(PhantomData<T>);
+
+impl<T: CacheEntry + Serialize + DeserializeOwned> CacheFormat for ToolCacheFormat<T> {
+    type Entry = T;
+
+    fn read<R: Read>(reader: R) -> io::Result<CacheEntries<T>> {
+        let (hashmap, identities): (BTreeMap<String, T>, _) = read_cache_with_identities(reader)?;
+        Ok(hashmap
+            .into_values()
+            .map(|entry| {
+                let identity = identities.get(entry.get_path()).copied();
+                (entry, identity)
+            })
+            .collect())
+    }
+
+    fn write(entries: &[(T, Option<FileIdentity>)]) -> io::Result<Vec<u8>> {
+        let identities: Vec<(Vec<u8>, FileIdentity)> = entries
+            .iter()
+            .filter_map(|(entry, identity)| identity.map(|identity| (path_to_bytes(entry.get_path()), identity)))
+            .collect();
+        let mut data = Vec::new();
+        write_cache_with_identities(&mut data, &tool_cache_hashmap(entries), &identities)?;
+        Ok(data)
+    }
+}
+
+fn tool_cache_hashmap<T>(entries: &[(T, Option<FileIdentity>)]) -> BTreeMap<String, &T>
+where
+    T: CacheEntry,
+{
+    entries.iter().map(|(entry, _identity)| (entry.get_path().to_string_lossy().to_string(), entry)).collect()
+}
+
+/// Changes path of entry saved relative to volume into absolute path, false when volume is not mounted now
+pub fn decode_cache_entry<T: CacheEntry>(volumes: &Volumes, entry: &mut T) -> bool {
+    if !is_volume_path(entry.get_path()) {
+        return true;
+    }
+    match volumes.from_cache_path(entry.get_path()) {
+        Some(path) => {
+            entry.set_path(path);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Saves entries to cache file, entries of files on volumes which keep own caches are saved in cache folder on volume
+/// Paths of files on volumes are saved relative to volume, so they stay valid when volume is mounted in other place
+/// Entries saved in meantime by other scan or instance of app are merged, unless this instance has entry for the same file or file doesn't exist anymore
+pub(crate) fn save_cache_entries<F: CacheFormat>(
+    cache_file: &Path,
+    entries: CacheEntries<F::Entry>,
+    volumes: &Volumes,
+    keep_other_entry: impl Fn(&F::Entry) -> bool,
+) -> Vec<(PathBuf, io::Result<CacheEntries<F::Entry>>)> {
+    let file_name = cache_file.file_name().unwrap_or_default();
+    let mut entries_by_file: BTreeMap<PathBuf, CacheEntries<F::Entry>> = BTreeMap::new();
+    entries_by_file.insert(cache_file.to_path_buf(), Vec::new());
+    for (entry, identity) in entries {
+        let file = match volumes.cache_folder_of(entry.get_path()) {
+            Some(folder) => folder.join(file_name),
+            None => cache_file.to_path_buf(),
+        };
+        entries_by_file.entry(file).or_default().push((entry, identity));
+    }
+    entries_by_file
+        .into_iter()
+        .map(|(file, entries)| {
+            let result = save_entries_to_cache_file::<F>(&file, entries, volumes, &keep_other_entry);
+            (file, result)
+        })
+        .collect()
+}
+
+fn save_entries_to_cache_file<F: CacheFormat>(
+    cache_file: &Path,
+    entries: CacheEntries<F::Entry>,
+    volumes: &Volumes,
+    keep_other_entry: &impl Fn(&F::Entry) -> bool,
+) -> io::Result<CacheEntries<F::Entry>> {
+    if let Some(folder) = cache_file.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    let _lock = CacheLock::lock(cache_file)?;
+
+    let mut saved_entries: BTreeMap<PathBuf, (F::Entry, Option<FileIdentity>)> = BTreeMap::new();
+    // Other scan or instance of app could save new entries after this cache was loaded, so file is always read again under lock
+    for (entry, identity) in read_cache_file::<F>(cache_file).unwrap_or_default() {
+        // Files on not mounted volumes may still exist
+        let exists = volumes.from_cache_path(entry.get_path()).map_or(true, |path| cached_file_exists(&path));
+        if exists && keep_other_entry(&entry) {
+            saved_entries.insert(entry.get_path().to_path_buf(), (entry, identity));
+        }
+    }
+    for (mut entry, identity) in entries {
+        let cache_path = volumes.to_cache_path(entry.get_path());
+        entry.set_path(cache_path.clone());
+        saved_entries.insert(cache_path, (entry, identity));
+    }
+
+    let saved_entries: CacheEntries<F::Entry> = saved_entries.into_values().collect();
+    let data = F::write(&saved_entries)?;
+    write_file_atomically(cache_file, |writer| writer.write_all(&data))?;
+    Ok(saved_entries)
+}
+
+/// Reads cache files saved on mounted volumes, entries keep paths relative to volume
+pub(crate) fn load_volume_cache_entries<F: CacheFormat>(cache_file_name: &str, volumes: &Volumes) -> Vec<(PathBuf, io::Result<CacheEntries<F::Entry>>)> {
+    volumes
+        .get_volumes()
+        .iter()
+        .filter(|volume| volume.cache_on_volume)
+        .map(|volume| volume.mount_point.join(VOLUME_CACHE_FOLDER).join(cache_file_name))
+        .filter(|cache_file| cache_file.is_file())
+        .map(|cache_file| {
+            let result = read_cache_file::<F>(&cache_file);
+            (cache_file, result)
+        })
+        .collect()
+}
+
+/// Saves cache of tool keyed by path, binary file contains also identities of files
+pub fn save_cache_to_file_with_identities<'a, M, T>(
+    cache_file_name: &str,
+    hashmap: &'a M,
+    moved_files: Option<&MovedFiles<T>>,
+    volumes: &Volumes,
+    save_also_as_json: bool,
+    text_messages: &mut Messages,
+) where
+    &'a M: IntoIterator<Item = (&'a String, &'a T)>,
+    T: CacheEntry + Serialize + DeserializeOwned + Send + Sync + 'a,
+{
+    if let Some(((_file_handler, cache_file), (_file_handler_json, cache_file_json))) = open_cache_folder(cache_file_name, true, save_also_as_json, text_messages) {
+        let own_entries: Vec<&T> = hashmap.into_iter().map(|(_path, entry)| entry).collect();
+        let entries: CacheEntries<T> = own_entries.par_iter().map(|entry| ((*entry).clone(), identity_of_entry(*entry, moved_files))).collect();
+
+        let mut saved_entries = 0;
+        for (file, result) in save_cache_entries::<ToolCacheFormat<T>>(&cache_file, entries, volumes, |_entry| true) {
+            let entries = match result {
+                Ok(t) => t,
+                Err(e) => {
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(DiagnosticKind::CannotWriteFile, format!("Cannot write data to cache file {}, reason {}", file.display(), e))
+                            .path(&file)
+                            .io_error(&e),
+                    );
+                    continue;
+                }
+            };
+            saved_entries += entries.len();
+            if save_also_as_json && file == cache_file {
+                if let Err(e) = write_file_atomically(&cache_file_json, |writer| {
+                    serde_json::to_writer(writer, &tool_cache_hashmap(&entries)).map_err(io::Error::from)
+                }) {
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::CannotWriteFile,
+                            format!("Cannot write data to cache file {}, reason {}", cache_file_json.display(), e),
+                        )
+                        .path(&cache_file_json)
+                        .io_error(&e),
+                    );
+                }
+            }
+        }
+
+        text_messages.messages.push(format!("Properly saved to file {} cache entries.", saved_entries));
+    }
+}
+
+/// Loads cache of tool keyed by path, entries of not existing files with known identity are returned also as moved files
+/// Caches saved on mounted volumes are loaded too, entries of files on not mounted volumes are kept unchanged
+pub fn load_cache_from_file_with_identities<M, T>(cache_file_name: &str, volumes: &Volumes, delete_outdated_cache: bool, text_messages: &mut Messages) -> Option<(M, MovedFiles<T>)>
+where
+    M: DeserializeOwned + IntoIterator<Item = (String, T)> + FromIterator<(String, T)>,
+    T: CacheEntry + Serialize + DeserializeOwned,
+{
+    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) = open_cache_folder(cache_file_name, false, true, text_messages) {
+        let (hashmap_loaded_entries, identities): (M, HashMap<PathBuf, FileIdentity>) = if let Some(file_handler) = file_handler {
+            match read_cache_with_identities(BufReader::new(file_handler)) {
+                Ok(t) => t,
+                Err(e) => {
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::InvalidCache,
+                            format!("Failed to load data from cache file {}, reason {}", cache_file.display(), e),
+                        )
+                        .path(&cache_file)
+                        .io_error(&e),
+                    );
+                    return None;
+                }
+            }
+        } else {
+            let reader = BufReader::new(file_handler_json.unwrap()); // Unwrap cannot fail, because at least one file must be valid
+            match serde_json::from_reader(reader) {
+                Ok(t) => (t, HashMap::new()),
+                Err(e) => {
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::InvalidCache,
+                            format!("Failed to load data from cache file {}, reason {}", cache_file_json.display(), e),
+                        )
+                        .path(&cache_file_json)
+                        .io_error(&io::Error::from(e)),
+                    );
+                    return None;
+                }
+            }
+        };
+
+        let mut entries: CacheEntries<T> = hashmap_loaded_entries
+            .into_iter()
+            .map(|(_path, entry)| {
+                let identity = identities.get(entry.get_path()).copied();
+                (entry, identity)
+            })
+            .collect();
+        for (file, result) in load_volume_cache_entries::<ToolCacheFormat<T>>(cache_file_name, volumes) {
+            match result {
+                Ok(t) => entries.extend(t),
+                Err(e) => text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidCache,
+                        format!("Failed to load data from cache file {}, reason {}", file.display(), e),
+                    )
+                    .path(&file)
+                    .io_error(&e),
+                ),
+            }
+        }
+
+        let mut moved_files = MovedFiles::new();
+        let mut loaded_entries = 0;
+        let hashmap_loaded_entries: M = entries
+            .into_iter()
+            .filter_map(|(mut file_entry, identity)| {
+                if !decode_cache_entry(volumes, &mut file_entry) {
+                    return Some(file_entry);
+                }
+                if identity.is_none() && !delete_outdated_cache {
+                    return Some(file_entry);
+                }
+                if file_entry.get_path().exists() {
+                    return Some(file_entry);
+                }
+                if let Some(identity) = identity {
+                    moved_files.add(identity, file_entry.clone());
+                }
+                // Don't load cache data if destination file not exists
+                if delete_outdated_cache {
+                    None
+                } else {
+                    Some(file_entry)
+                }
+            })
+            .map(|file_entry| (file_entry.get_path().to_string_lossy().to_string(), file_entry))
+            .inspect(|_| loaded_entries += 1)
+            .collect();
+
+        text_messages.messages.push(format!("Properly loaded {} cache entries.", loaded_entries));
+
+        return Some((hashmap_loaded_entries, moved_files));
+    }
+    None
+}
+
+/// Paths are saved as raw bytes, because not every path is valid UTF-8
+#[cfg(target_family = "unix")]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(target_family = "unix")]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(target_family = "windows")]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::windows::ffi::OsStrExt;
+    path.as_os_str().encode_wide().flat_map(u16::to_le_bytes).collect()
+}
+
+#[cfg(target_family = "windows")]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::windows::ffi::OsStringExt;
+    let wide: Vec<u16> = bytes.chunks_exact(2).map(|e| u16::from_le_bytes([e[0], e[1]])).collect();
+    PathBuf::from(std::ffi::OsString::from_wide(&wide))
+}
+
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs::{self, OpenOptions};
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+
+    use fs2::FileExt;
+
+    use crate::common_cache::{
+        decode_cache_entry, identity_of_entry, load_volume_cache_entries, path_to_bytes, read_cache_file, read_cache_with_identities, save_cache_entries,
+        write_cache_with_identities, write_file_atomically, CacheLock, FileIdentity, MovedFiles,
+    };
+    use crate::common_dir_traversal::FileEntry;
+    use crate::common_volume::{Volume, Volumes, VOLUME_CACHE_FOLDER};
+    use crate::duplicate::DuplicateCacheFormat;
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_moved_files() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let old_path = dir.path().join("old");
+        let new_path = dir.path().join("subfolder").join("new");
+        fs::write(&old_path, "content").unwrap();
+        let identity = FileIdentity::of_file(&old_path).unwrap();
+        let file_entry = FileEntry {
+            path: old_path.clone(),
+            size: identity.size,
+            modified_date: identity.modified_date,
+            hash: "hash".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(identity_of_entry(&file_entry, None), Some(identity));
+        assert_eq!(identity_of_entry(&FileEntry { size: 1, ..file_entry.clone() }, None), None);
+
+        let mut hashmap: BTreeMap<String, u64> = BTreeMap::new();
+        hashmap.insert(old_path.to_string_lossy().to_string(), file_entry.size);
+        let mut data = Vec::new();
+        write_cache_with_identities(&mut data, &hashmap, &[(path_to_bytes(&old_path), identity)]).unwrap();
+
+        fs::create_dir(dir.path().join("subfolder")).unwrap();
+        fs::rename(&old_path, &new_path).unwrap();
+        assert_eq!(FileIdentity::of_file(&new_path), Some(identity));
+
+        let (loaded, identities): (BTreeMap<String, u64>, HashMap<PathBuf, FileIdentity>) = read_cache_with_identities(data.as_slice()).unwrap();
+        assert_eq!(loaded, hashmap);
+        let mut moved_files = MovedFiles::new();
+        moved_files.add(identities[&old_path], file_entry.clone());
+        let moved_entry = moved_files.find(&new_path).unwrap();
+        assert_eq!((moved_entry.path.as_path(), moved_entry.hash.as_str()), (new_path.as_path(), "hash"));
+        assert!(moved_files.find(&dir.path().join("not_existing")).is_none());
+        // Missing file keeps its identity, so it may be found also after next scan
+        assert_eq!(identity_of_entry(&file_entry, Some(&moved_files)), Some(identity));
+
+        // Caches saved by older versions contain only content
+        let legacy_data = bincode::serialize(&hashmap).unwrap();
+        let (loaded, identities): (BTreeMap<String, u64>, _) = read_cache_with_identities(legacy_data.as_slice()).unwrap();
+        assert_eq!(loaded, hashmap);
+        assert!(identities.is_empty());
+        assert!(read_cache_with_identities::<_, BTreeMap<String, u64>>(&b"CZK"[..]).is_err());
+    }
+
+    #[test]
+    fn test_atomic_cache_saving() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let cache_file = dir.path().join("cache_test.bin");
+        write_file_atomically(&cache_file, |writer| writer.write_all(b"first")).unwrap();
+        assert_eq!(fs::read(&cache_file).unwrap(), b"first");
+
+        // Failed write leaves previous content and no temporary files
+        let result = write_file_atomically(&cache_file, |writer| {
+            writer.write_all(b"partial")?;
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read(&cache_file).unwrap(), b"first");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let lock = CacheLock::lock(&cache_file).unwrap();
+        let other_lock_file = OpenOptions::new().write(true).open(dir.path().join("cache_test.lock")).unwrap();
+        assert!(other_lock_file.try_lock_exclusive().is_err());
+        drop(lock);
+        assert!(other_lock_file.try_lock_exclusive().is_ok());
+    }
+
+    #[test]
+    fn test_cache_saving_merges_entries() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let cache_file = dir.path().join("cache_duplicates_Blake3.bin");
+        let file_entry = |name: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, name).unwrap();
+            let file_entry = FileEntry {
+                path,
+                hash: name.to_string(),
+                ..Default::default()
+            };
+            vec![(file_entry, None)]
+        };
+
+        // Two scans in the same process save their entries one after another, without loading cache in meantime
+        let volumes = Volumes::default();
+        save_cache_entries::<DuplicateCacheFormat>(&cache_file, file_entry("a"), &volumes, |_file_entry| true);
+        save_cache_entries::<DuplicateCacheFormat>(&cache_file, file_entry("b"), &volumes, |_file_entry| true);
+        let saved_hashes: Vec<String> = read_cache_file::<DuplicateCacheFormat>(&cache_file)
+            .unwrap()
+            .into_iter()
+            .map(|(e, _identity)| e.hash)
+            .collect();
+        assert_eq!(saved_hashes, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_volume_caches() {
+        let cache_folder = tempfile::Builder::new().tempdir().unwrap();
+        let drive = tempfile::Builder::new().tempdir().unwrap();
+        let remounted_drive = tempfile::Builder::new().tempdir().unwrap();
+        let volume = |mount_point: &Path| Volume {
+            id: Some("usb".to_string()),
+            mount_point: mount_point.to_path_buf(),
+            root: PathBuf::from("/"),
+            cache_on_volume: true,
+        };
+        let file_entries = [drive.path().join("a"), PathBuf::from("/not_on_drive/b")]
+            .into_iter()
+            .map(|path| {
+                let file_entry = FileEntry {
+                    path,
+                    hash: "hash".to_string(),
+                    ..Default::default()
+                };
+                (file_entry, None)
+            })
+            .collect();
+
+        // Entries of files on drive are saved on drive, with paths relative to it
+        let cache_file = cache_folder.path().join("cache_duplicates_Blake3.bin");
+        let volumes = Volumes::new(vec![volume(drive.path())]);
+        let results = save_cache_entries::<DuplicateCacheFormat>(&cache_file, file_entries, &volumes, |_file_entry| true);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_file, result)| result.as_ref().map(|e| e.len()).ok() == Some(1)));
+        let volume_cache_file = drive.path().join(VOLUME_CACHE_FOLDER).join("cache_duplicates_Blake3.bin");
+        let saved_entries = read_cache_file::<DuplicateCacheFormat>(&volume_cache_file).unwrap();
+        assert_eq!(saved_entries[0].0.path, PathBuf::from("czkawka-volume:usb/a"));
+        assert_eq!(read_cache_file::<DuplicateCacheFormat>(&cache_file).unwrap()[0].0.path, PathBuf::from("/not_on_drive/b"));
+
+        // Drive mounted in other place is still recognized, when it is not mounted its entries keep relative paths
+        fs::create_dir(remounted_drive.path().join(VOLUME_CACHE_FOLDER)).unwrap();
+        fs::copy(&volume_cache_file, remounted_drive.path().join(VOLUME_CACHE_FOLDER).join("cache_duplicates_Blake3.bin")).unwrap();
+        let volumes = Volumes::new(vec![volume(remounted_drive.path())]);
+        let mut loaded = load_volume_cache_entries::<DuplicateCacheFormat>("cache_duplicates_Blake3.bin", &volumes);
+        let (mut file_entry, _identity) = loaded.remove(0).1.unwrap().remove(0);
+        assert!(!decode_cache_entry(&Volumes::default(), &mut file_entry.clone()));
+        assert!(decode_cache_entry(&volumes, &mut file_entry));
+        assert_eq!(file_entry.path, remounted_drive.path().join("a"));
+    }
+}
@@ -0,0 +1,303 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::common::get_cache_folder;
+use crate::common_cache::{cached_file_exists, read_cache_file, write_file_atomically, CacheEntry, CacheFormat, CacheLock, ToolCacheFormat};
+use crate::common_messages::{Diagnostic, DiagnosticKind};
+use crate::common_volume::{Volumes, VOLUME_CACHE_FOLDER};
+use crate::duplicate::DuplicateCacheFormat;
+use crate::{broken_files, same_music, similar_images, similar_videos};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CacheTool {
+    Duplicates,
+    SimilarImages,
+    SimilarVideos,
+    SameMusic,
+    BrokenFiles,
+}
+
+impl CacheTool {
+    pub const ALL: [CacheTool; 5] = [
+        CacheTool::Duplicates,
+        CacheTool::SimilarImages,
+        CacheTool::SimilarVideos,
+        CacheTool::SameMusic,
+        CacheTool::BrokenFiles,
+    ];
+
+    /// Every tool may use multiple cache files, e.g. one per hash type
+    fn cache_file_prefix(&self) -> &'static str {
+        match self {
+            CacheTool::Duplicates => "cache_duplicates_",
+            CacheTool::SimilarImages => "cache_similar_images_",
+            CacheTool::SimilarVideos => "cache_similar_videos",
+            CacheTool::SameMusic => "cache_same_music",
+            CacheTool::BrokenFiles => "cache_broken_files",
+        }
+    }
+
+    pub fn from_cache_file_name(file_name: &str) -> Option<Self> {
+        if !file_name.ends_with(".bin") {
+            return None;
+        }
+        CacheTool::ALL.into_iter().find(|tool| file_name.starts_with(tool.cache_file_prefix()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheFileInfo {
+    pub path: PathBuf,
+    pub tool: CacheTool,
+    pub file_size: u64,
+    /// None when cache file cannot be read
+    pub entries: Option<usize>,
+}
+
+/// Entries removed from cache file, all conditions are checked
+#[derive(Clone, Debug, Default)]
+pub struct CacheCleaning {
+    /// Removes entries of files which don't exist anymore
+    pub remove_missing_files: bool,
+    /// Removes entries of files inside these folders
+    pub removed_prefixes: Vec<PathBuf>,
+    /// Maximum size of cache file in bytes, entries of smallest files are removed first, because they are the fastest to compute again
+    pub max_file_size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheCleaningInfo {
+    pub removed_entries: usize,
+    pub remaining_entries: usize,
+    pub file_size: u64,
+}
+
+/// Inspects and cleans cache files of all tools
+pub struct CacheManager {
+    folder: PathBuf,
+}
+
+impl CacheManager {
+    pub fn new(folder: PathBuf) -> Self {
+        Self { folder }
+    }
+
+    /// Manager of folder used by all tools, None when it cannot be found
+    pub fn default_manager() -> Option<Self> {
+        get_cache_folder().map(Self::new)
+    }
+
+    /// Manager of cache folder on volume which contains path, None when path is on root filesystem
+    pub fn volume_manager(path: &Path) -> Option<Self> {
+        let path = path.canonicalize().ok()?;
+        Volumes::mounted().volume_of(&path).map(|volume| Self::new(volume.mount_point.join(VOLUME_CACHE_FOLDER)))
+    }
+
+    pub fn get_folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Lists cache files of given tools, all tools are used when list is empty
+    pub fn list(&self, tools: &[CacheTool]) -> Vec<CacheFileInfo> {
+        let read_dir = match fs::read_dir(&self.folder) {
+            Ok(t) => t,
+            Err(_) => return Vec::new(),
+        };
+        let mut cache_files: Vec<CacheFileInfo> = read_dir
+            .flatten()
+            .filter_map(|entry| {
+                let tool = CacheTool::from_cache_file_name(&entry.file_name().to_string_lossy())?;
+                if !tools.is_empty() && !tools.contains(&tool) {
+                    return None;
+                }
+                let path = entry.path();
+                let entries = match tool {
+                    CacheTool::Duplicates => read_cache_file::<DuplicateCacheFormat>(&path).map(|e| e.len()),
+                    CacheTool::SimilarImages => read_cache_file::<ToolCacheFormat<similar_images::FileEntry>>(&path).map(|e| e.len()),
+                    CacheTool::SimilarVideos => read_cache_file::<ToolCacheFormat<similar_videos::FileEntry>>(&path).map(|e| e.len()),
+                    CacheTool::SameMusic => read_cache_file::<ToolCacheFormat<same_music::MusicEntry>>(&path).map(|e| e.len()),
+                    CacheTool::BrokenFiles => read_cache_file::<ToolCacheFormat<broken_files::FileEntry>>(&path).map(|e| e.len()),
+                };
+                Some(CacheFileInfo {
+                    tool,
+                    file_size: entry.metadata().map(|e| e.len()).unwrap_or_default(),
+                    entries: entries.ok(),
+                    path,
+                })
+            })
+            .collect();
+        cache_files.sort_by(|a, b| (a.tool, &a.path).cmp(&(b.tool, &b.path)));
+        cache_files
+    }
+
+    /// Removes chosen entries from cache file, file is saved again only when something was removed
+    pub fn clean(&self, cache_file: &CacheFileInfo, cleaning: &CacheCleaning) -> Result<CacheCleaningInfo, Diagnostic> {
+        match cache_file.tool {
+            CacheTool::Duplicates => clean_cache_file::<DuplicateCacheFormat>(&cache_file.path, cleaning),
+            CacheTool::SimilarImages => clean_cache_file::<ToolCacheFormat<similar_images::FileEntry>>(&cache_file.path, cleaning),
+            CacheTool::SimilarVideos => clean_cache_file::<ToolCacheFormat<similar_videos::FileEntry>>(&cache_file.path, cleaning),
+            CacheTool::SameMusic => clean_cache_file::<ToolCacheFormat<same_music::MusicEntry>>(&cache_file.path, cleaning),
+            CacheTool::BrokenFiles => clean_cache_file::<ToolCacheFormat<broken_files::FileEntry>>(&cache_file.path, cleaning),
+        }
+    }
+
+    /// Removes cache file together with its JSON version
+    pub fn clear(&self, cache_file: &CacheFileInfo) -> Result<(), Diagnostic> {
+        let _lock = lock_cache_file(&cache_file.path)?;
+        let json_file = cache_file.path.with_extension("json");
+        for file in [&cache_file.path, &json_file] {
+            if let Err(e) = fs::remove_file(file) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    return Err(
+                        Diagnostic::error(DiagnosticKind::CannotRemoveFile, format!("Failed to remove cache file {}, reason {}", file.display(), e))
+                            .path(file)
+                            .io_error(&e),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn lock_cache_file(file: &Path) -> Result<CacheLock, Diagnostic> {
+    CacheLock::lock(file).map_err(|e| {
+        Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Cannot lock cache file {}, reason {}", file.display(), e))
+            .path(file)
+            .io_error(&e)
+    })
+}
+
+fn clean_cache_file<F: CacheFormat>(file: &Path, cleaning: &CacheCleaning) -> Result<CacheCleaningInfo, Diagnostic> {
+    let _lock = lock_cache_file(file)?;
+    let mut entries = read_cache_file::<F>(file).map_err(|e| {
+        Diagnostic::error(
+            DiagnosticKind::InvalidCache,
+            format!("Failed to load data from cache file {}, reason {}", file.display(), e),
+        )
+        .path(file)
+        .io_error(&e)
+    })?;
+    let loaded_entries = entries.len();
+
+    // Entries of files on not mounted volumes are never treated as missing
+    let mut probed_folders = cleaning.removed_prefixes.clone();
+    probed_folders.push(file.to_path_buf());
+    let volumes = Volumes::mounted_for(&probed_folders);
+    entries.retain(|(entry, _identity)| match volumes.from_cache_path(entry.get_path()) {
+        Some(path) => !cleaning.removed_prefixes.iter().any(|prefix| path.starts_with(prefix)) && (!cleaning.remove_missing_files || cached_file_exists(&path)),
+        None => true,
+    });
+
+    let to_write_error = |e: io::Error| {
+        Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Cannot write data to cache file {}, reason {}", file.display(), e))
+            .path(file)
+            .io_error(&e)
+    };
+    let mut data = None;
+    if let Some(max_file_size) = cleaning.max_file_size {
+        entries.sort_by_key(|(entry, _identity)| Reverse(entry.get_size()));
+        loop {
+            let current_data = F::write(&entries).map_err(to_write_error)?;
+            if current_data.len() as u64 <= max_file_size || entries.is_empty() {
+                data = Some(current_data);
+                break;
+            }
+            // Size of entries is similar, so number of removed entries is estimated from size of file
+            let estimated_entries = (entries.len() as u128 * max_file_size as u128 / current_data.len() as u128) as usize;
+            entries.truncate(estimated_entries.min(entries.len() - 1));
+        }
+    }
+
+    if entries.len() == loaded_entries {
+        return Ok(CacheCleaningInfo {
+            removed_entries: 0,
+            remaining_entries: loaded_entries,
+            file_size: fs::metadata(file).map(|e| e.len()).unwrap_or_default(),
+        });
+    }
+    let data = match data {
+        Some(data) => data,
+        None => F::write(&entries).map_err(to_write_error)?,
+    };
+    write_file_atomically(file, |writer| writer.write_all(&data)).map_err(to_write_error)?;
+    Ok(CacheCleaningInfo {
+        removed_entries: loaded_entries - entries.len(),
+        remaining_entries: entries.len(),
+        file_size: data.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::common_cache_manager::{CacheCleaning, CacheManager, CacheTool};
+    use crate::common_dir_traversal::FileEntry;
+    use crate::duplicate::write_duplicate_cache;
+
+    #[test]
+    fn test_cache_manager() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let existing_file = dir.path().join("existing");
+        fs::write(&existing_file, "a").unwrap();
+        let file_entries: Vec<(FileEntry, _)> = [
+            existing_file.clone(),
+            PathBuf::from("/not_existing/a"),
+            PathBuf::from("/removed/b"),
+            PathBuf::from("/removed/c"),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let file_entry = FileEntry {
+                path,
+                size: 100 - index as u64,
+                hash: "hash".to_string(),
+                ..Default::default()
+            };
+            (file_entry, None)
+        })
+        .collect();
+        let mut data = Vec::new();
+        write_duplicate_cache(&mut data, &file_entries.iter().map(|(e, identity)| (e, *identity)).collect::<Vec<_>>()).unwrap();
+        fs::write(dir.path().join("cache_duplicates_Blake3.bin"), &data).unwrap();
+        fs::write(dir.path().join("cache_duplicates_Blake3.json"), "[]").unwrap();
+        fs::write(dir.path().join("cache_broken_files.bin"), "invalid").unwrap();
+        fs::write(dir.path().join("other.bin"), "").unwrap();
+
+        let manager = CacheManager::new(dir.path().to_path_buf());
+        let cache_files = manager.list(&[]);
+        assert_eq!(cache_files.len(), 2);
+        assert_eq!((cache_files[0].tool, cache_files[0].entries), (CacheTool::Duplicates, Some(4)));
+        assert_eq!((cache_files[1].tool, cache_files[1].entries), (CacheTool::BrokenFiles, None));
+        assert!(manager.clean(&cache_files[1], &CacheCleaning::default()).is_err());
+
+        let cleaning = CacheCleaning {
+            removed_prefixes: vec![PathBuf::from("/removed")],
+            ..Default::default()
+        };
+        let info = manager.clean(&cache_files[0], &cleaning).unwrap();
+        assert_eq!((info.removed_entries, info.remaining_entries), (2, 2));
+
+        // Entry of the biggest file is kept when there is place only for one entry
+        let cleaning = CacheCleaning {
+            max_file_size: Some(info.file_size - 1),
+            ..Default::default()
+        };
+        let info = manager.clean(&cache_files[0], &cleaning).unwrap();
+        assert_eq!(info.remaining_entries, 1);
+        let cleaning = CacheCleaning {
+            remove_missing_files: true,
+            ..Default::default()
+        };
+        assert_eq!(manager.clean(&cache_files[0], &cleaning).unwrap().removed_entries, 0);
+
+        manager.clear(&cache_files[0]).unwrap();
+        assert!(!dir.path().join("cache_duplicates_Blake3.json").exists());
+        assert_eq!(manager.list(&[CacheTool::Duplicates]), Vec::new());
+    }
+}
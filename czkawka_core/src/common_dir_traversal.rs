@@ -1,31 +1,21 @@
 use std::collections::BTreeMap;
+use std::fs;
 use std::fs::Metadata;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::common::LOOP_DURATION;
 use crossbeam_channel::Receiver;
 use rayon::prelude::*;
 
+use crate::common_cache::CacheEntry;
 use crate::common_directory::Directories;
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
+use crate::common_messages::{Diagnostic, DiagnosticKind};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
 use crate::flc;
 use crate::localizer_core::generate_translation_hashmap;
 
-#[derive(Debug)]
-pub struct ProgressData {
-    pub checking_method: CheckingMethod,
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub entries_checked: usize,
-    pub entries_to_check: usize,
-}
-
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 pub enum CheckingMethod {
     None,
@@ -41,6 +31,23 @@ pub struct FileEntry {
     pub modified_date: u64,
     pub hash: String,
     pub symlink_info: Option<SymlinkInfo>,
+    /// Device on which file is stored, None when it is not known
+    pub device: Option<u64>,
+}
+
+impl CacheEntry for FileEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
 }
 
 // Symlinks
@@ -265,12 +272,12 @@ impl<'a, 'b, F> DirTraversalBuilder<'a, 'b, F> {
 pub enum DirTraversalResult<T: Ord + PartialOrd> {
     SuccessFiles {
         start_time: SystemTime,
-        warnings: Vec<String>,
+        warnings: Vec<Diagnostic>,
         grouped_file_entries: BTreeMap<T, Vec<FileEntry>>,
     },
     SuccessFolders {
         start_time: SystemTime,
-        warnings: Vec<String>,
+        warnings: Vec<Diagnostic>,
         folder_entries: BTreeMap<PathBuf, FolderEntry>, // Path, FolderEntry
     },
     Stopped,
@@ -289,6 +296,17 @@ fn entry_type(metadata: &Metadata) -> EntryType {
     }
 }
 
+#[cfg(target_family = "unix")]
+fn device_of_metadata(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn device_of_metadata(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
 impl<'a, 'b, F, T> DirTraversal<'a, 'b, F>
 where
     F: Fn(&FileEntry) -> T,
@@ -317,37 +335,11 @@ where
         // Add root folders for finding
         folders_to_check.extend(self.root_dirs);
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_entry_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = self.progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_entry_counter = atomic_entry_counter.clone();
-            let checking_method = self.checking_method;
-            let max_stage = self.max_stage;
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method,
-                        current_stage: 0,
-                        max_stage,
-                        entries_checked: atomic_entry_counter.load(Ordering::Relaxed) as usize,
-                        entries_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
+        let stage = match self.collect {
+            Collect::EmptyFolders => ProgressStage::CollectingFolders,
+            _ => ProgressStage::CollectingFiles,
         };
-
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(self.progress_sender, ProgressData::new(stage, 0, self.max_stage).checking_method(self.checking_method));
 
         let DirTraversal {
             collect,
@@ -363,15 +355,14 @@ where
 
         while !folders_to_check.is_empty() {
             if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
+                progress_reporter.end();
                 return DirTraversalResult::Stopped;
             }
 
             let segments: Vec<_> = folders_to_check
                 .par_iter()
                 .map(|current_folder| {
+                    progress_reporter.set_current_path(current_folder);
                     let mut dir_result = vec![];
                     let mut warnings = vec![];
                     let mut fe_result = vec![];
@@ -381,10 +372,17 @@ where
                     let read_dir = match fs::read_dir(&current_folder) {
                         Ok(t) => t,
                         Err(e) => {
-                            warnings.push(flc!(
-                                "core_cannot_open_dir",
-                                generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                            ));
+                            warnings.push(
+                                Diagnostic::warning(
+                                    DiagnosticKind::CannotOpenDir,
+                                    flc!(
+                                        "core_cannot_open_dir",
+                                        generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                    ),
+                                )
+                                .path(current_folder)
+                                .io_error(&e),
+                            );
                             return (dir_result, warnings, fe_result, set_as_not_empty_folder_list, folder_entries_list);
                         }
                     };
@@ -394,20 +392,34 @@ where
                         let entry_data = match entry {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_entry_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadEntry,
+                                        flc!(
+                                            "core_cannot_read_entry_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
                         let metadata: Metadata = match entry_data.metadata() {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_metadata_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadMetadata,
+                                        flc!(
+                                            "core_cannot_read_metadata_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
@@ -430,7 +442,7 @@ where
                                 if directories.exclude_other_filesystems() {
                                     match directories.is_on_other_filesystems(&next_folder) {
                                         Ok(true) => continue 'dir,
-                                        Err(e) => warnings.push(e.to_string()),
+                                        Err(e) => warnings.push(e),
                                         _ => (),
                                     }
                                 }
@@ -438,7 +450,7 @@ where
                                 dir_result.push(next_folder);
                             }
                             (EntryType::Dir, Collect::EmptyFolders) => {
-                                atomic_entry_counter.fetch_add(1, Ordering::Relaxed);
+                                progress_reporter.add_entries(1);
                                 let next_folder = current_folder.join(entry_data.file_name());
                                 if excluded_items.is_excluded(&next_folder) || directories.is_excluded(&next_folder) {
                                     set_as_not_empty_folder_list.push(current_folder.clone());
@@ -449,7 +461,7 @@ where
                                 if directories.exclude_other_filesystems() {
                                     match directories.is_on_other_filesystems(&next_folder) {
                                         Ok(true) => continue 'dir,
-                                        Err(e) => warnings.push(e.to_string()),
+                                        Err(e) => warnings.push(e),
                                         _ => (),
                                     }
                                 }
@@ -464,18 +476,31 @@ where
                                             Ok(t) => match t.duration_since(UNIX_EPOCH) {
                                                 Ok(d) => d.as_secs(),
                                                 Err(_inspected) => {
-                                                    warnings.push(flc!(
-                                                        "core_folder_modified_before_epoch",
-                                                        generate_translation_hashmap(vec![("name", current_folder.display().to_string())])
-                                                    ));
+                                                    warnings.push(
+                                                        Diagnostic::warning(
+                                                            DiagnosticKind::ModifiedBeforeEpoch,
+                                                            flc!(
+                                                                "core_folder_modified_before_epoch",
+                                                                generate_translation_hashmap(vec![("name", current_folder.display().to_string())])
+                                                            ),
+                                                        )
+                                                        .path(current_folder),
+                                                    );
                                                     0
                                                 }
                                             },
                                             Err(e) => {
-                                                warnings.push(flc!(
-                                                    "core_folder_no_modification_date",
-                                                    generate_translation_hashmap(vec![("name", current_folder.display().to_string()), ("reason", e.to_string())])
-                                                ));
+                                                warnings.push(
+                                                    Diagnostic::warning(
+                                                        DiagnosticKind::NoModificationDate,
+                                                        flc!(
+                                                            "core_folder_no_modification_date",
+                                                            generate_translation_hashmap(vec![("name", current_folder.display().to_string()), ("reason", e.to_string())])
+                                                        ),
+                                                    )
+                                                    .path(current_folder)
+                                                    .io_error(&e),
+                                                );
                                                 0
                                             }
                                         },
@@ -483,15 +508,22 @@ where
                                 ));
                             }
                             (EntryType::File, Collect::Files) => {
-                                atomic_entry_counter.fetch_add(1, Ordering::Relaxed);
+                                progress_reporter.add_entries(1);
+                                progress_reporter.add_bytes(metadata.len());
 
                                 let file_name_lowercase: String = match entry_data.file_name().into_string() {
                                     Ok(t) => t,
                                     Err(_inspected) => {
-                                        warnings.push(flc!(
-                                            "core_file_not_utf8_name",
-                                            generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
-                                        ));
+                                        warnings.push(
+                                            Diagnostic::warning(
+                                                DiagnosticKind::NonUtf8Name,
+                                                flc!(
+                                                    "core_file_not_utf8_name",
+                                                    generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
+                                                ),
+                                            )
+                                            .path(&entry_data.path()),
+                                        );
                                         continue 'dir;
                                     }
                                 }
@@ -511,7 +543,7 @@ where
                                     if directories.exclude_other_filesystems() {
                                         match directories.is_on_other_filesystems(&current_file_name) {
                                             Ok(true) => continue 'dir,
-                                            Err(e) => warnings.push(e.to_string()),
+                                            Err(e) => warnings.push(e),
                                             _ => (),
                                         }
                                     }
@@ -524,23 +556,37 @@ where
                                             Ok(t) => match t.duration_since(UNIX_EPOCH) {
                                                 Ok(d) => d.as_secs(),
                                                 Err(_inspected) => {
-                                                    warnings.push(flc!(
-                                                        "core_file_modified_before_epoch",
-                                                        generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
-                                                    ));
+                                                    warnings.push(
+                                                        Diagnostic::warning(
+                                                            DiagnosticKind::ModifiedBeforeEpoch,
+                                                            flc!(
+                                                                "core_file_modified_before_epoch",
+                                                                generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
+                                                            ),
+                                                        )
+                                                        .path(&current_file_name),
+                                                    );
                                                     0
                                                 }
                                             },
                                             Err(e) => {
-                                                warnings.push(flc!(
-                                                    "core_file_no_modification_date",
-                                                    generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
-                                                ));
+                                                warnings.push(
+                                                    Diagnostic::warning(
+                                                        DiagnosticKind::NoModificationDate,
+                                                        flc!(
+                                                            "core_file_no_modification_date",
+                                                            generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
+                                                        ),
+                                                    )
+                                                    .path(&current_file_name)
+                                                    .io_error(&e),
+                                                );
                                                 0
                                             }
                                         },
                                         hash: "".to_string(),
                                         symlink_info: None,
+                                        device: device_of_metadata(&metadata),
                                     };
 
                                     fe_result.push(fe);
@@ -551,7 +597,7 @@ where
                                 if directories.exclude_other_filesystems() {
                                     match directories.is_on_other_filesystems(&current_folder) {
                                         Ok(true) => continue 'dir,
-                                        Err(e) => warnings.push(e.to_string()),
+                                        Err(e) => warnings.push(e),
                                         _ => (),
                                     }
                                 }
@@ -559,18 +605,24 @@ where
                                 set_as_not_empty_folder_list.push(current_folder.clone());
                             }
                             (EntryType::File, Collect::InvalidSymlinks) => {
-                                atomic_entry_counter.fetch_add(1, Ordering::Relaxed);
+                                progress_reporter.add_entries(1);
                             }
                             (EntryType::Symlink, Collect::InvalidSymlinks) => {
-                                atomic_entry_counter.fetch_add(1, Ordering::Relaxed);
+                                progress_reporter.add_entries(1);
 
                                 let file_name_lowercase: String = match entry_data.file_name().into_string() {
                                     Ok(t) => t,
                                     Err(_inspected) => {
-                                        warnings.push(flc!(
-                                            "core_file_not_utf8_name",
-                                            generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
-                                        ));
+                                        warnings.push(
+                                            Diagnostic::warning(
+                                                DiagnosticKind::NonUtf8Name,
+                                                flc!(
+                                                    "core_file_not_utf8_name",
+                                                    generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
+                                                ),
+                                            )
+                                            .path(&entry_data.path()),
+                                        );
                                         continue 'dir;
                                     }
                                 }
@@ -589,7 +641,7 @@ where
                                 if directories.exclude_other_filesystems() {
                                     match directories.is_on_other_filesystems(&current_folder) {
                                         Ok(true) => continue 'dir,
-                                        Err(e) => warnings.push(e.to_string()),
+                                        Err(e) => warnings.push(e),
                                         _ => (),
                                     }
                                 }
@@ -636,24 +688,38 @@ where
                                         Ok(t) => match t.duration_since(UNIX_EPOCH) {
                                             Ok(d) => d.as_secs(),
                                             Err(_inspected) => {
-                                                warnings.push(flc!(
-                                                    "core_file_modified_before_epoch",
-                                                    generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
-                                                ));
+                                                warnings.push(
+                                                    Diagnostic::warning(
+                                                        DiagnosticKind::ModifiedBeforeEpoch,
+                                                        flc!(
+                                                            "core_file_modified_before_epoch",
+                                                            generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
+                                                        ),
+                                                    )
+                                                    .path(&current_file_name),
+                                                );
                                                 0
                                             }
                                         },
                                         Err(e) => {
-                                            warnings.push(flc!(
-                                                "core_file_no_modification_date",
-                                                generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
-                                            ));
+                                            warnings.push(
+                                                Diagnostic::warning(
+                                                    DiagnosticKind::NoModificationDate,
+                                                    flc!(
+                                                        "core_file_no_modification_date",
+                                                        generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
+                                                    ),
+                                                )
+                                                .path(&current_file_name)
+                                                .io_error(&e),
+                                            );
                                             0
                                         }
                                     },
                                     size: 0,
                                     hash: "".to_string(),
                                     symlink_info: Some(SymlinkInfo { destination_path, type_of_error }),
+                                    device: device_of_metadata(&metadata),
                                 };
 
                                 // Adding files to Vector
@@ -674,7 +740,7 @@ where
             // Process collected data
             for (segment, warnings, fe_result, set_as_not_empty_folder_list, fe_list) in segments {
                 folders_to_check.extend(segment);
-                all_warnings.extend(warnings);
+                all_warnings.extend(warnings.into_iter().map(|warning| warning.stage(stage)));
                 for fe in fe_result {
                     let key = (self.group_by)(&fe);
                     grouped_file_entries.entry(key).or_insert_with(Vec::new).push(fe);
@@ -688,9 +754,7 @@ where
             }
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         match collect {
             Collect::Files | Collect::InvalidSymlinks => DirTraversalResult::SuccessFiles {
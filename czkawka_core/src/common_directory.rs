@@ -4,7 +4,7 @@ use std::time::SystemTime;
 use std::{fs, os::unix::fs::MetadataExt};
 
 use crate::common::Common;
-use crate::common_messages::Messages;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
 use crate::flc;
 use crate::localizer_core::generate_translation_hashmap;
 
@@ -33,7 +33,7 @@ impl Directories {
         let start_time: SystemTime = SystemTime::now();
 
         if included_directory.is_empty() {
-            text_messages.errors.push(flc!("core_missing_no_chosen_included_directory"));
+            text_messages.add_diagnostic(Diagnostic::error(DiagnosticKind::InvalidSettings, flc!("core_missing_no_chosen_included_directory")));
             return false;
         }
 
@@ -42,49 +42,76 @@ impl Directories {
         let mut checked_directories: Vec<PathBuf> = Vec::new();
         for directory in directories {
             if directory.to_string_lossy().contains('*') {
-                text_messages.warnings.push(flc!(
-                    "core_directory_wildcard_no_supported",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_wildcard_no_supported",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
 
             #[cfg(not(target_family = "windows"))]
             if directory.is_relative() {
-                text_messages.warnings.push(flc!(
-                    "core_directory_relative_path",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_relative_path",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
             #[cfg(target_family = "windows")]
             if directory.is_relative() && !directory.starts_with("\\") {
-                text_messages.warnings.push(flc!(
-                    "core_directory_relative_path",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_relative_path",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
 
             if !directory.exists() {
-                text_messages.warnings.push(flc!(
-                    "core_directory_must_exists",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!("core_directory_must_exists", generate_translation_hashmap(vec![("path", directory.display().to_string())])),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
             if !directory.is_dir() {
-                text_messages.warnings.push(flc!(
-                    "core_directory_must_be_directory",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_must_be_directory",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
             checked_directories.push(directory);
         }
 
         if checked_directories.is_empty() {
-            text_messages.warnings.push(flc!("core_included_directory_zero_valid_directories"));
+            text_messages.add_diagnostic(Diagnostic::warning(DiagnosticKind::InvalidSettings, flc!("core_included_directory_zero_valid_directories")));
             return false;
         }
 
@@ -107,30 +134,48 @@ impl Directories {
         for directory in directories {
             let directory_as_string = directory.to_string_lossy();
             if directory_as_string == "/" {
-                text_messages.errors.push(flc!("core_excluded_directory_pointless_slash"));
+                text_messages.add_diagnostic(Diagnostic::error(DiagnosticKind::InvalidSettings, flc!("core_excluded_directory_pointless_slash")));
                 break;
             }
             if directory_as_string.contains('*') {
-                text_messages.warnings.push(flc!(
-                    "core_directory_wildcard_no_supported",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_wildcard_no_supported",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
             #[cfg(not(target_family = "windows"))]
             if directory.is_relative() {
-                text_messages.warnings.push(flc!(
-                    "core_directory_relative_path",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_relative_path",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
             #[cfg(target_family = "windows")]
             if directory.is_relative() && !directory.starts_with("\\") {
-                text_messages.warnings.push(flc!(
-                    "core_directory_relative_path",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_relative_path",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
 
@@ -139,10 +184,16 @@ impl Directories {
                 continue;
             }
             if !directory.is_dir() {
-                text_messages.warnings.push(flc!(
-                    "core_directory_must_be_directory",
-                    generate_translation_hashmap(vec![("path", directory.display().to_string())])
-                ));
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidSettings,
+                        flc!(
+                            "core_directory_must_be_directory",
+                            generate_translation_hashmap(vec![("path", directory.display().to_string())])
+                        ),
+                    )
+                    .path(&directory),
+                );
                 continue;
             }
             checked_directories.push(directory);
@@ -287,7 +338,7 @@ impl Directories {
         }
 
         if self.included_directories.is_empty() {
-            text_messages.errors.push(flc!("core_directory_overlap"));
+            text_messages.add_diagnostic(Diagnostic::error(DiagnosticKind::InvalidSettings, flc!("core_directory_overlap")));
             return false;
         }
 
@@ -302,10 +353,17 @@ impl Directories {
             for d in &self.included_directories {
                 match fs::metadata(d) {
                     Ok(m) => self.included_dev_ids.push(m.dev()),
-                    Err(_) => text_messages.errors.push(flc!(
-                        "core_directory_unable_to_get_device_id",
-                        generate_translation_hashmap(vec![("path", d.display().to_string())])
-                    )),
+                    Err(e) => text_messages.add_diagnostic(
+                        Diagnostic::error(
+                            DiagnosticKind::CannotGetDeviceId,
+                            flc!(
+                                "core_directory_unable_to_get_device_id",
+                                generate_translation_hashmap(vec![("path", d.display().to_string())])
+                            ),
+                        )
+                        .path(d)
+                        .io_error(&e),
+                    ),
                 }
             }
         }
@@ -330,14 +388,19 @@ impl Directories {
     /// Checks whether a specified directory is on other filesystems rather then include
     /// directories
     #[cfg(target_family = "unix")]
-    pub fn is_on_other_filesystems(&self, path: impl AsRef<Path>) -> Result<bool, String> {
+    pub fn is_on_other_filesystems(&self, path: impl AsRef<Path>) -> Result<bool, Diagnostic> {
         let path = path.as_ref();
         match fs::metadata(path) {
             Ok(m) => Ok(!self.included_dev_ids.iter().any(|&id| id == m.dev())),
-            Err(_) => Err(flc!(
-                "core_directory_unable_to_get_device_id",
-                generate_translation_hashmap(vec![("path", path.display().to_string())])
-            )),
+            Err(e) => Err(Diagnostic::warning(
+                DiagnosticKind::CannotGetDeviceId,
+                flc!(
+                    "core_directory_unable_to_get_device_id",
+                    generate_translation_hashmap(vec![("path", path.display().to_string())])
+                ),
+            )
+            .path(path)
+            .io_error(&e)),
         }
     }
 }
@@ -0,0 +1,404 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+
+/// Format of file with results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Text,
+    Json,
+    Csv,
+    /// Same format as output of sha256sum/md5sum, only entries with hash are saved
+    Checksums,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Some(ExportFormat::Text),
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "checksums" | "sha256sum" => Some(ExportFormat::Checksums),
+            _ => None,
+        }
+    }
+}
+
+/// Single found file or folder
+/// Fields which have no meaning for tool(e.g. hash in empty folders) are left empty
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified_date: u64,
+    pub hash: Option<String>,
+    pub similarity: Option<String>,
+    pub is_reference: bool,
+    pub info: Option<String>,
+    /// Tool specific values(e.g. music tags), needed to recreate results after loading them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl ExportedEntry {
+    pub fn new(path: &Path, size: u64, modified_date: u64) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            size,
+            modified_date,
+            hash: None,
+            similarity: None,
+            is_reference: false,
+            info: None,
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn hash(mut self, hash: String) -> Self {
+        self.hash = Some(hash);
+        self
+    }
+
+    pub fn similarity(mut self, similarity: String) -> Self {
+        self.similarity = Some(similarity);
+        self
+    }
+
+    pub fn reference(mut self, is_reference: bool) -> Self {
+        self.is_reference = is_reference;
+        self
+    }
+
+    pub fn info(mut self, info: String) -> Self {
+        self.info = Some(info);
+        self
+    }
+
+    pub fn property(mut self, name: &str, value: String) -> Self {
+        self.properties.insert(name.to_string(), value);
+        self
+    }
+
+    /// Returns empty string when property was not saved
+    pub fn get_property(&self, name: &str) -> String {
+        self.properties.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Checks if entry still exists and has same size and modification date as when results were saved
+    pub fn check_unchanged(&self) -> Result<(), Diagnostic> {
+        // Symlinks are checked instead of their destination, because invalid symlinks have none
+        let metadata = fs::symlink_metadata(&self.path).map_err(|e| {
+            Diagnostic::warning(DiagnosticKind::CannotReadMetadata, format!("Cannot read metadata of {}, reason {}", self.path.display(), e))
+                .path(&self.path)
+                .io_error(&e)
+        })?;
+        let modified_date = match metadata.modified() {
+            Ok(t) => t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            Err(_) => 0,
+        };
+        if (!metadata.is_dir() && metadata.len() != self.size) || modified_date != self.modified_date {
+            return Err(Diagnostic::warning(DiagnosticKind::ChangedSinceScan, format!("{} was changed since results were saved", self.path.display())).path(&self.path));
+        }
+        Ok(())
+    }
+}
+
+/// Results of tool in form which don't depend on tool internals
+/// Tools which don't group results(e.g. big files) put every entry into separate group
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedResults {
+    pub tool: String,
+    pub checking_method: Option<String>,
+    pub grouped: bool,
+    pub groups: Vec<Vec<ExportedEntry>>,
+}
+
+impl ExportedResults {
+    pub fn new(tool: &str, grouped: bool) -> Self {
+        Self {
+            tool: tool.to_string(),
+            checking_method: None,
+            grouped,
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn checking_method(mut self, checking_method: &str) -> Self {
+        self.checking_method = Some(checking_method.to_string());
+        self
+    }
+
+    pub fn add_entry(&mut self, entry: ExportedEntry) {
+        self.groups.push(vec![entry]);
+    }
+
+    pub fn add_group(&mut self, group: Vec<ExportedEntry>) {
+        self.groups.push(group);
+    }
+
+    /// Reference file is always first entry in group
+    pub fn add_referenced_group(&mut self, reference: ExportedEntry, group: Vec<ExportedEntry>) {
+        let mut full_group = Vec::with_capacity(group.len() + 1);
+        full_group.push(reference.reference(true));
+        full_group.extend(group);
+        self.groups.push(full_group);
+    }
+
+    /// Loads results saved in JSON format
+    pub fn load_from_file(file_name: &str) -> Result<Self, Diagnostic> {
+        let file_handler = File::open(file_name).map_err(|e| {
+            Diagnostic::error(DiagnosticKind::CannotOpenFile, format!("Failed to open file {}, reason {}", file_name, e))
+                .path(Path::new(file_name))
+                .io_error(&e)
+        })?;
+        serde_json::from_reader(BufReader::new(file_handler))
+            .map_err(|e| Diagnostic::error(DiagnosticKind::InvalidFile, format!("Failed to load results from file {}, reason {}", file_name, e)).path(Path::new(file_name)))
+    }
+
+    /// Removes entries which were deleted or changed since saving results
+    /// Groups which no longer contain duplicates or lost their reference file are removed too
+    pub fn revalidate(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let grouped = self.grouped;
+        let groups = std::mem::take(&mut self.groups);
+        self.groups = groups
+            .into_iter()
+            .filter_map(|mut group| {
+                let reference_in_group = matches!(group.first(), Some(e) if e.is_reference);
+                group.retain(|entry| match entry.check_unchanged() {
+                    Ok(()) => true,
+                    Err(diagnostic) => {
+                        diagnostics.push(diagnostic);
+                        false
+                    }
+                });
+                let keep_group = if !grouped {
+                    !group.is_empty()
+                } else if reference_in_group {
+                    group.len() >= 2 && group[0].is_reference
+                } else {
+                    group.len() >= 2
+                };
+                if keep_group {
+                    Some(group)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        diagnostics
+    }
+
+    pub fn has_reference_groups(&self) -> bool {
+        self.groups.iter().any(|group| matches!(group.first(), Some(e) if e.is_reference))
+    }
+
+    /// Splits every group into reference entry(if exists) and rest of entries
+    pub fn into_split_groups(self) -> Vec<(Option<ExportedEntry>, Vec<ExportedEntry>)> {
+        self.groups
+            .into_iter()
+            .map(|mut group| {
+                if matches!(group.first(), Some(e) if e.is_reference) {
+                    let reference = group.remove(0);
+                    (Some(reference), group)
+                } else {
+                    (None, group)
+                }
+            })
+            .collect()
+    }
+
+    /// Checks if results were created by given tool and drops outdated entries, problems are added to messages
+    pub fn validate_for_tool(mut self, tool: &str, text_messages: &mut Messages) -> Option<Self> {
+        if self.tool != tool {
+            text_messages.add_diagnostic(Diagnostic::error(
+                DiagnosticKind::InvalidFile,
+                format!("Loaded results were created by {} tool, but expected {}", self.tool, tool),
+            ));
+            return None;
+        }
+        let diagnostics = self.revalidate();
+        text_messages.extend_diagnostics(diagnostics);
+        Some(self)
+    }
+
+    pub fn number_of_entries(&self) -> usize {
+        self.groups.iter().map(Vec::len).sum()
+    }
+
+    pub fn save_to_file(&self, file_name: &str, format: ExportFormat) -> Result<(), Diagnostic> {
+        let file_handler = File::create(file_name).map_err(|e| {
+            Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                .path(Path::new(file_name))
+                .io_error(&e)
+        })?;
+        let mut writer = BufWriter::new(file_handler);
+
+        let result = match format {
+            ExportFormat::Json => serde_json::to_writer_pretty(&mut writer, self).map_err(|e| e.to_string()),
+            ExportFormat::Csv => self.write_csv(&mut writer).map_err(|e| e.to_string()),
+            ExportFormat::Checksums => self.write_checksums(&mut writer).map_err(|e| e.to_string()),
+            ExportFormat::Text => Err("Text format is saved directly by tool".to_string()),
+        };
+        result
+            .and_then(|_| writer.flush().map_err(|e| e.to_string()))
+            .map_err(|e| Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e)).path(Path::new(file_name)))
+    }
+
+    /// One row per entry, entries from same group share group number
+    fn write_csv<W: Write>(&self, writer: W) -> csv::Result<()> {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        csv_writer.write_record(["group", "path", "size", "modified_date", "hash", "similarity", "is_reference", "info"])?;
+        for (group_index, group) in self.groups.iter().enumerate() {
+            for entry in group {
+                csv_writer.write_record([
+                    group_index.to_string(),
+                    entry.path.to_string_lossy().to_string(),
+                    entry.size.to_string(),
+                    entry.modified_date.to_string(),
+                    entry.hash.clone().unwrap_or_default(),
+                    entry.similarity.clone().unwrap_or_default(),
+                    entry.is_reference.to_string(),
+                    entry.info.clone().unwrap_or_default(),
+                ])?;
+            }
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
+    /// Every file is saved once even if it is in multiple groups
+    /// Like in coreutils, line starts with backslash when path contains backslash or new line which are escaped
+    fn write_checksums<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let mut saved_paths = HashSet::new();
+        for entry in self.groups.iter().flatten() {
+            let hash = match &entry.hash {
+                Some(hash) => hash,
+                None => continue,
+            };
+            if !saved_paths.insert(&entry.path) {
+                continue;
+            }
+            let path = entry.path.to_string_lossy();
+            if path.contains(['\\', '\n']) {
+                writeln!(writer, "\\{}  {}", hash, path.replace('\\', "\\\\").replace('\n', "\\n"))?;
+            } else {
+                writeln!(writer, "{}  {}", hash, path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|e| format!("{:02x}", e)).collect()
+}
+
+pub fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|chunk| match chunk {
+            [_, _] => u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::common_export::{bytes_to_hex, hex_to_bytes, ExportFormat, ExportedEntry, ExportedResults};
+    use crate::common_messages::DiagnosticKind;
+
+    fn get_results() -> ExportedResults {
+        let mut results = ExportedResults::new("duplicate", true).checking_method("hash");
+        results.add_referenced_group(
+            ExportedEntry::new(Path::new("/ref/a,b.txt"), 10, 5).hash("abc".to_string()),
+            vec![ExportedEntry::new(Path::new("/tmp/a.txt"), 10, 6).hash("abc".to_string())],
+        );
+        results.add_group(vec![ExportedEntry::new(Path::new("/tmp/c"), 3, 1), ExportedEntry::new(Path::new("/tmp/d"), 3, 2)]);
+        results
+    }
+
+    #[test]
+    fn test_export_json_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_name = dir.path().join("results.json");
+        let results = get_results();
+        results.save_to_file(file_name.to_str().unwrap(), ExportFormat::Json).unwrap();
+
+        let loaded: ExportedResults = serde_json::from_str(&fs::read_to_string(&file_name).unwrap()).unwrap();
+        assert_eq!(loaded, results);
+        assert!(loaded.groups[0][0].is_reference);
+        assert_eq!(loaded.number_of_entries(), 4);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_name = dir.path().join("results.csv");
+        get_results().save_to_file(file_name.to_str().unwrap(), ExportFormat::Csv).unwrap();
+
+        let content = fs::read_to_string(&file_name).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "group,path,size,modified_date,hash,similarity,is_reference,info");
+        assert_eq!(lines[1], "0,\"/ref/a,b.txt\",10,5,abc,,true,");
+        assert_eq!(lines[4], "1,/tmp/d,3,2,,,false,");
+    }
+
+    #[test]
+    fn test_export_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_name = dir.path().join("SHA256SUMS");
+        let mut results = get_results();
+        results.add_entry(ExportedEntry::new(Path::new("/tmp/new\nline"), 3, 1).hash("def".to_string()));
+        results.save_to_file(file_name.to_str().unwrap(), ExportFormat::Checksums).unwrap();
+
+        let content = fs::read_to_string(&file_name).unwrap();
+        assert_eq!(content, "abc  /ref/a,b.txt\nabc  /tmp/a.txt\n\\def  /tmp/new\\nline\n");
+    }
+
+    #[test]
+    fn test_revalidate_removes_changed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<_> = ["a", "b", "c"].iter().map(|name| dir.path().join(name)).collect();
+        let mut group = Vec::new();
+        for file in &files {
+            fs::write(file, "1234").unwrap();
+            let metadata = fs::metadata(file).unwrap();
+            let modified_date = metadata.modified().unwrap().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+            group.push(ExportedEntry::new(file, 4, modified_date));
+        }
+        let mut results = ExportedResults::new("duplicate", true);
+        results.add_group(group.clone());
+        results.add_group(group[..2].to_vec());
+
+        fs::write(&files[0], "12345").unwrap();
+        fs::remove_file(&files[1]).unwrap();
+
+        let diagnostics = results.revalidate();
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ChangedSinceScan);
+        assert_eq!(diagnostics[1].io_error_kind, Some(std::io::ErrorKind::NotFound));
+        // Group with only one remaining file is no longer useful
+        assert!(results.groups.is_empty());
+    }
+
+    #[test]
+    fn test_hex_conversion() {
+        assert_eq!(bytes_to_hex(&[0, 15, 255]), "000fff");
+        assert_eq!(hex_to_bytes("000fff"), Some(vec![0, 15, 255]));
+        assert_eq!(hex_to_bytes("000ff"), None);
+        assert_eq!(hex_to_bytes("zz"), None);
+    }
+}
@@ -1,7 +1,7 @@
 use std::time::SystemTime;
 
 use crate::common::Common;
-use crate::common_messages::Messages;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
 
 #[derive(Clone, Default)]
 pub struct Extensions {
@@ -35,7 +35,10 @@ impl Extensions {
             }
 
             if extension[1..].contains('.') {
-                text_messages.warnings.push(format!("{} is not valid extension because contains dot inside", extension));
+                text_messages.add_diagnostic(Diagnostic::warning(
+                    DiagnosticKind::InvalidSettings,
+                    format!("{} is not valid extension because contains dot inside", extension),
+                ));
                 continue;
             }
 
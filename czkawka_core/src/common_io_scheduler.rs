@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::common_archive::split_archive_entry_path;
+
+/// Decides how many files are read at once from every device
+/// Reading many files at once from spinning disk is much slower than reading them one after another, because of seeking
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IoScheduler {
+    /// Readers for devices without own limit, 0 uses all threads
+    pub default_threads: usize,
+    /// Readers for rotational disks(HDD), detected only on Linux, 0(default) uses default limit
+    pub rotational_threads: usize,
+    /// Readers for devices which contain given paths, more important than other limits
+    pub device_threads: Vec<(PathBuf, usize)>,
+}
+
+impl Default for IoScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoScheduler {
+    pub fn new() -> Self {
+        Self {
+            default_threads: 0,
+            rotational_threads: 0,
+            device_threads: Vec::new(),
+        }
+    }
+
+    /// Number of readers for device, 0 means no limit
+    pub fn threads_for_device(&self, device: u64) -> usize {
+        if let Some((_path, threads)) = self.device_threads.iter().find(|(path, _threads)| device_of_path(path) == Some(device)) {
+            return *threads;
+        }
+        if self.rotational_threads > 0 && is_rotational(device) == Some(true) {
+            return self.rotational_threads;
+        }
+        self.default_threads
+    }
+
+    /// Splits items by device on which they are stored and processes every device at the same time with its own number of readers
+    /// Items from one device are processed in order of paths, which usually reduces seeking
+    /// Device is checked only for items for which it was not found earlier(e.g. when collecting files)
+    /// Processing stops early when function returns None, so results may be incomplete
+    pub fn process_by_device<T, R, P, D, F>(&self, items: Vec<T>, path_of: P, device_of: D, function: F) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        P: Fn(&T) -> &Path,
+        D: Fn(&T) -> Option<u64>,
+        F: Fn(T) -> Option<R> + Sync,
+    {
+        let mut items_by_device: BTreeMap<u64, Vec<T>> = BTreeMap::new();
+        for item in items {
+            let device = device_of(&item).or_else(|| device_of_path(path_of(&item))).unwrap_or_default();
+            items_by_device.entry(device).or_default().push(item);
+        }
+        for items in items_by_device.values_mut() {
+            items.sort_by(|a, b| path_of(a).cmp(path_of(b)));
+        }
+
+        let items_by_device: Vec<(usize, Vec<T>)> = items_by_device.into_iter().map(|(device, items)| (self.threads_for_device(device), items)).collect();
+        let function = &function;
+        // Devices are processed at the same time, every limited device uses its own pool of readers
+        let results_by_device: Vec<Vec<R>> = items_by_device
+            .into_par_iter()
+            .map(|(threads, items)| {
+                let process = || items.into_par_iter().map(function).while_some().collect::<Vec<R>>();
+                if threads == 0 {
+                    return process();
+                }
+                match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                    Ok(pool) => pool.install(process),
+                    Err(_) => process(),
+                }
+            })
+            .collect();
+        results_by_device.into_iter().flatten().collect()
+    }
+}
+
+/// Files inside archives are stored on device of archive, None when file doesn't exists
+#[cfg(target_family = "unix")]
+pub fn device_of_path(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    match split_archive_entry_path(path) {
+        Some((archive, _name_inside)) => archive.metadata().ok().map(|e| e.dev()),
+        None => path.metadata().ok().map(|e| e.dev()),
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+pub fn device_of_path(path: &Path) -> Option<u64> {
+    if split_archive_entry_path(path).is_some() || path.exists() {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Checks in sysfs if device is spinning disk, partitions use value of whole disk
+#[cfg(target_os = "linux")]
+pub fn is_rotational(device: u64) -> Option<bool> {
+    let major = ((device >> 8) & 0xfff) | ((device >> 32) & !0xfff);
+    let minor = (device & 0xff) | ((device >> 12) & !0xff);
+    let block_device = PathBuf::from(format!("/sys/dev/block/{}:{}", major, minor));
+    [block_device.join("queue/rotational"), block_device.join("../queue/rotational")]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|content| content.trim() == "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_rotational(_device: u64) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::common_io_scheduler::{device_of_path, IoScheduler};
+
+    #[test]
+    fn test_process_by_device() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let paths: Vec<PathBuf> = (0..20).map(|index| dir.path().join(format!("{:02}", index))).collect();
+        for path in &paths {
+            fs::write(path, "a").unwrap();
+        }
+        let device = device_of_path(dir.path()).unwrap();
+        assert_eq!(device_of_path(&dir.path().join("not_existing")), None);
+
+        let scheduler = IoScheduler {
+            device_threads: vec![(dir.path().to_path_buf(), 1)],
+            ..IoScheduler::new()
+        };
+        assert_eq!(scheduler.threads_for_device(device), 1);
+
+        // With one reader files are processed one after another, in order of paths
+        let running = AtomicUsize::new(0);
+        let results = scheduler.process_by_device(
+            paths.iter().rev().cloned().collect(),
+            |path: &PathBuf| path.as_path(),
+            |_path| None,
+            |path| {
+                assert_eq!(running.fetch_add(1, Ordering::SeqCst), 0);
+                running.fetch_sub(1, Ordering::SeqCst);
+                Some(path)
+            },
+        );
+        assert_eq!(results, paths);
+
+        let results = scheduler.process_by_device(
+            paths.clone(),
+            |path: &PathBuf| path.as_path(),
+            |_path| Some(device),
+            |path| if path.ends_with("05") { None } else { Some(path) },
+        );
+        assert!(results.len() <= 5);
+        assert!(results.iter().all(|path| path.as_path() < Path::new(&paths[5])));
+    }
+}
@@ -0,0 +1,310 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use directories_next::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::common_messages::{Diagnostic, DiagnosticKind};
+use crate::common_remove::move_path;
+
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+const QUARANTINE_FOLDER_NAME: &str = "quarantine";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalAction {
+    Delete,
+    Trash,
+    Quarantine,
+    HardLink,
+    Symlink,
+    Move,
+}
+
+/// Single destructive operation done on file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub original_path: PathBuf,
+    pub action: JournalAction,
+    /// Place where file was moved(trash, quarantine, move) or file to which link points
+    pub target: Option<PathBuf>,
+    pub size: u64,
+    pub hash: Option<String>,
+    pub time: u64,
+}
+
+impl JournalEntry {
+    pub fn new(action: JournalAction, original_path: &Path, size: u64) -> Self {
+        Self {
+            original_path: original_path.to_path_buf(),
+            action,
+            target: None,
+            size,
+            hash: None,
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map(|e| e.as_secs()).unwrap_or(0),
+        }
+    }
+
+    pub fn target(mut self, target: &Path) -> Self {
+        self.target = Some(target.to_path_buf());
+        self
+    }
+
+    pub fn hash(mut self, hash: Option<&str>) -> Self {
+        self.hash = hash.map(str::to_string);
+        self
+    }
+
+    /// Permanently deleted files and files moved to system trash without known location cannot be restored
+    pub fn can_be_undone(&self) -> bool {
+        self.action != JournalAction::Delete && self.target.is_some()
+    }
+
+    pub fn formatted_time(&self) -> String {
+        match chrono::NaiveDateTime::from_timestamp_opt(self.time as i64, 0) {
+            Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => self.time.to_string(),
+        }
+    }
+
+    /// Restores file to state from before operation
+    /// Links are replaced by copy of file to which they point, so restored file is again independent of it
+    pub fn undo(&self) -> io::Result<()> {
+        let target = match &self.target {
+            Some(target) if self.can_be_undone() => target,
+            _ => return Err(Error::new(ErrorKind::Unsupported, format!("{} cannot be restored", self.original_path.display()))),
+        };
+
+        match self.action {
+            JournalAction::Trash | JournalAction::Quarantine | JournalAction::Move => {
+                if fs::symlink_metadata(&self.original_path).is_ok() {
+                    return Err(Error::new(ErrorKind::AlreadyExists, format!("{} already exists", self.original_path.display())));
+                }
+                if let Some(parent) = self.original_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                move_path(target, &self.original_path)?;
+                if self.action == JournalAction::Trash {
+                    remove_trash_info(target);
+                }
+                Ok(())
+            }
+            JournalAction::HardLink | JournalAction::Symlink => {
+                let dst_dir = self.original_path.parent().ok_or_else(|| Error::new(ErrorKind::Other, "No parent"))?;
+                let temp = dst_dir.join(format!(".czkawka_restore_{}", self.time));
+                fs::copy(target, &temp)?;
+                fs::rename(&temp, &self.original_path).map_err(|e| {
+                    let _ = fs::remove_file(&temp);
+                    e
+                })
+            }
+            JournalAction::Delete => unreachable!(),
+        }
+    }
+}
+
+/// Info file is not needed after restoring file from trash, failing to remove it only leaves orphaned file
+fn remove_trash_info(trashed_file: &Path) {
+    if let (Some(files_folder), Some(file_name)) = (trashed_file.parent(), trashed_file.file_name()) {
+        if let Some(trash_folder) = files_folder.parent() {
+            let _ = fs::remove_file(trash_folder.join("info").join(format!("{}.trashinfo", file_name.to_string_lossy())));
+        }
+    }
+}
+
+/// Append only file with one JSON entry per line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Journal {
+    file: PathBuf,
+}
+
+impl Journal {
+    pub fn new(file: PathBuf) -> Self {
+        Self { file }
+    }
+
+    /// Journal shared by all frontends, kept in app data folder
+    pub fn default_journal() -> Option<Self> {
+        ProjectDirs::from("pl", "Qarmin", "Czkawka").map(|proj_dirs| Self::new(proj_dirs.data_dir().join(JOURNAL_FILE_NAME)))
+    }
+
+    pub fn get_file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn record(&self, entry: &JournalEntry) -> io::Result<()> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        line.push('\n');
+        OpenOptions::new().create(true).append(true).open(&self.file)?.write_all(line.as_bytes())
+    }
+
+    /// Not existing journal is treated as empty one
+    pub fn load(&self) -> Result<Vec<JournalEntry>, Diagnostic> {
+        let file_handler = match File::open(&self.file) {
+            Ok(t) => t,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(
+                    Diagnostic::error(DiagnosticKind::CannotOpenFile, format!("Failed to open journal {}, reason {}", self.file.display(), e))
+                        .path(&self.file)
+                        .io_error(&e),
+                )
+            }
+        };
+
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file_handler).lines().enumerate() {
+            let line = line.map_err(|e| {
+                Diagnostic::error(DiagnosticKind::CannotReadFile, format!("Failed to read journal {}, reason {}", self.file.display(), e))
+                    .path(&self.file)
+                    .io_error(&e)
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|e| {
+                Diagnostic::error(
+                    DiagnosticKind::InvalidFile,
+                    format!("Failed to load line number {} from journal {}, reason {}", index + 1, self.file.display(), e),
+                )
+                .path(&self.file)
+            })?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Journal is replaced atomically, so it is never left half written
+    fn save(&self, entries: &[JournalEntry]) -> io::Result<()> {
+        let temp_file = self.file.with_extension("jsonl.tmp");
+        let mut writer = BufWriter::new(File::create(&temp_file)?);
+        for entry in entries {
+            serde_json::to_writer(&mut writer, entry).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(temp_file, &self.file)
+    }
+
+    /// Restores all entries accepted by filter(which gets index of entry in journal), newest first
+    /// Restored entries are removed from journal, failed ones are kept to allow retrying
+    pub fn undo<F: Fn(usize, &JournalEntry) -> bool>(&self, filter: F, dryrun: bool) -> (Vec<JournalEntry>, Vec<Diagnostic>) {
+        let mut entries = match self.load() {
+            Ok(t) => t,
+            Err(diagnostic) => return (Vec::new(), vec![diagnostic]),
+        };
+
+        let mut restored = Vec::new();
+        let mut diagnostics = Vec::new();
+        for index in (0..entries.len()).rev() {
+            let entry = &entries[index];
+            if !filter(index, entry) {
+                continue;
+            }
+            if dryrun {
+                restored.push(entry.clone());
+                continue;
+            }
+            match entry.undo() {
+                Ok(()) => restored.push(entries.remove(index)),
+                Err(e) => diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::CannotRestoreFile,
+                        format!("Failed to restore {}, reason {}", entry.original_path.display(), e),
+                    )
+                    .path(&entry.original_path)
+                    .io_error(&e),
+                ),
+            }
+        }
+
+        if !dryrun && !restored.is_empty() {
+            if let Err(e) = self.save(&entries) {
+                diagnostics.push(
+                    Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save journal {}, reason {}", self.file.display(), e))
+                        .path(&self.file)
+                        .io_error(&e),
+                );
+            }
+        }
+        (restored, diagnostics)
+    }
+}
+
+/// Operation was already done, so failing to save it to journal is only returned as warning
+pub fn record_operation(journal: Option<&Journal>, entry: &JournalEntry) -> Option<Diagnostic> {
+    let journal = journal?;
+    let e = journal.record(entry).err()?;
+    Some(
+        Diagnostic::warning(
+            DiagnosticKind::CannotWriteFile,
+            format!(
+                "{} was processed, but saving it to journal {} failed, reason {}",
+                entry.original_path.display(),
+                journal.get_file().display(),
+                e
+            ),
+        )
+        .path(journal.get_file())
+        .io_error(&e),
+    )
+}
+
+/// Managed quarantine folder, every run uses new subfolder, so files with same path never collide
+pub fn default_quarantine_folder() -> Option<PathBuf> {
+    ProjectDirs::from("pl", "Qarmin", "Czkawka").map(|proj_dirs| {
+        proj_dirs
+            .data_dir()
+            .join(QUARANTINE_FOLDER_NAME)
+            .join(chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::common_journal::{Journal, JournalAction};
+    use crate::common_messages::{DiagnosticKind, DiagnosticLevel};
+    use crate::common_remove::RemovalMethod;
+
+    #[test]
+    fn test_undo_quarantine() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+        let file = dir.path().join("a").join("b.txt");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "abc").unwrap();
+
+        RemovalMethod::Quarantine(dir.path().join("quarantine")).remove(&file, Some(&journal)).unwrap();
+        RemovalMethod::Permanent.remove(&dir.path().join("a"), Some(&journal)).unwrap();
+        let entries = journal.load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, JournalAction::Quarantine);
+        assert_eq!(entries[0].size, 3);
+
+        let (restored, diagnostics) = journal.undo(|_, e| e.can_be_undone(), false);
+        assert_eq!((restored.len(), diagnostics.len()), (1, 0));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "abc");
+        assert_eq!(journal.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_journal_failure_is_only_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        // Folder cannot be opened as journal file
+        let journal = Journal::new(dir.path().to_path_buf());
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "abc").unwrap();
+
+        let journal_warning = RemovalMethod::Permanent.remove(&file, Some(&journal)).unwrap().unwrap();
+        assert!(!file.exists());
+        assert_eq!(journal_warning.kind, DiagnosticKind::CannotWriteFile);
+        assert_eq!(journal_warning.level, DiagnosticLevel::Warning);
+    }
+}
@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+use crate::common_dir_traversal::FileEntry;
+use crate::common_export::ExportedEntry;
+
+/// Typical endings of names of copied files, checked without extension and letter case
+const COPY_SUFFIXES: &[&str] = &[" - copy", " copy", "_copy", "(copy)", " - kopia", "(kopia)"];
+
+/// Properties of file used to decide if it should be kept
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeepCandidate {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified_date: u64,
+    /// Width and height of image, only known for similar images
+    pub dimensions: Option<(u64, u64)>,
+}
+
+impl From<&FileEntry> for KeepCandidate {
+    fn from(file_entry: &FileEntry) -> Self {
+        Self {
+            path: file_entry.path.clone(),
+            size: file_entry.size,
+            modified_date: file_entry.modified_date,
+            dimensions: None,
+        }
+    }
+}
+
+impl From<&ExportedEntry> for KeepCandidate {
+    /// Dimensions of similar images are saved as info in `1920x1080` format
+    fn from(entry: &ExportedEntry) -> Self {
+        Self {
+            path: entry.path.clone(),
+            size: entry.size,
+            modified_date: entry.modified_date,
+            dimensions: entry.info.as_deref().and_then(parse_dimensions),
+        }
+    }
+}
+
+pub fn parse_dimensions(text: &str) -> Option<(u64, u64)> {
+    let (width, height) = text.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+/// Single preference used to choose file which is kept from group
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeepRule {
+    Newest,
+    Oldest,
+    Biggest,
+    Smallest,
+    /// Only files inside given folder can be kept, groups without them are skipped
+    InPath(PathBuf),
+    /// Files inside given folder are preferred, but other files can be kept when there is none
+    PreferPath(PathBuf),
+    ShortestPath,
+    ShortestName,
+    /// Prefers names without endings like ` (1)` or ` - Copy`
+    WithoutCopySuffix,
+    HighestResolution,
+    LowestResolution,
+}
+
+impl KeepRule {
+    /// Parses rule from name used in CLI and GUI settings, e.g. `oldest` or `prefer-path:/srv/master`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if let Some((rule, folder)) = name.split_once(':') {
+            return match rule.to_ascii_lowercase().as_str() {
+                "in-path" => Some(KeepRule::InPath(PathBuf::from(folder))),
+                "prefer-path" => Some(KeepRule::PreferPath(PathBuf::from(folder))),
+                _ => None,
+            };
+        }
+        match name.to_ascii_lowercase().as_str() {
+            "newest" => Some(KeepRule::Newest),
+            "oldest" => Some(KeepRule::Oldest),
+            "biggest" => Some(KeepRule::Biggest),
+            "smallest" => Some(KeepRule::Smallest),
+            "shortest-path" => Some(KeepRule::ShortestPath),
+            "shortest-name" => Some(KeepRule::ShortestName),
+            "without-copy-suffix" => Some(KeepRule::WithoutCopySuffix),
+            "highest-resolution" => Some(KeepRule::HighestResolution),
+            "lowest-resolution" => Some(KeepRule::LowestResolution),
+            _ => None,
+        }
+    }
+
+    /// Returns index of file to keep, when rule is used alone
+    pub fn file_to_keep(&self, group: &[KeepCandidate]) -> Option<usize> {
+        KeepPolicy::new(vec![self.clone()]).file_to_keep(group)
+    }
+
+    /// Leaves only indexes of preferred files, None means that no file can be kept
+    fn filter(&self, group: &[KeepCandidate], indexes: Vec<usize>) -> Option<Vec<usize>> {
+        let prefer = |indexes: Vec<usize>, condition: &dyn Fn(&KeepCandidate) -> bool| {
+            let preferred: Vec<usize> = indexes.iter().copied().filter(|index| condition(&group[*index])).collect();
+            if preferred.is_empty() {
+                indexes
+            } else {
+                preferred
+            }
+        };
+        let best = |indexes: Vec<usize>, value: &dyn Fn(&KeepCandidate) -> Option<i128>| {
+            let best_value = indexes.iter().filter_map(|index| value(&group[*index])).max();
+            match best_value {
+                Some(best_value) => indexes.into_iter().filter(|index| value(&group[*index]) == Some(best_value)).collect(),
+                None => indexes,
+            }
+        };
+
+        let indexes = match self {
+            KeepRule::Newest => best(indexes, &|e| Some(e.modified_date as i128)),
+            KeepRule::Oldest => best(indexes, &|e| Some(-(e.modified_date as i128))),
+            KeepRule::Biggest => best(indexes, &|e| Some(e.size as i128)),
+            KeepRule::Smallest => best(indexes, &|e| Some(-(e.size as i128))),
+            KeepRule::InPath(folder) => indexes.into_iter().filter(|index| group[*index].path.starts_with(folder)).collect(),
+            KeepRule::PreferPath(folder) => prefer(indexes, &|e| e.path.starts_with(folder)),
+            KeepRule::ShortestPath => best(indexes, &|e| Some(-(e.path.as_os_str().len() as i128))),
+            KeepRule::ShortestName => best(indexes, &|e| Some(-(e.path.file_name().unwrap_or_default().len() as i128))),
+            KeepRule::WithoutCopySuffix => prefer(indexes, &|e| !has_copy_suffix(&e.path)),
+            KeepRule::HighestResolution => best(indexes, &|e| e.dimensions.map(|(width, height)| (width as i128).saturating_mul(height as i128))),
+            KeepRule::LowestResolution => best(indexes, &|e| e.dimensions.map(|(width, height)| -(width as i128).saturating_mul(height as i128))),
+        };
+        if indexes.is_empty() {
+            None
+        } else {
+            Some(indexes)
+        }
+    }
+}
+
+/// Ordered list of rules, every next rule is used only to choose between files equally good for previous rules
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeepPolicy {
+    pub rules: Vec<KeepRule>,
+}
+
+impl KeepPolicy {
+    pub fn new(rules: Vec<KeepRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Parses comma separated list of rules, e.g. `prefer-path:/srv/master,shortest-path,oldest`
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let rules = text
+            .split(',')
+            .filter(|e| !e.trim().is_empty())
+            .map(|name| KeepRule::from_name(name).ok_or_else(|| format!("Unknown keep rule {}", name.trim())))
+            .collect::<Result<Vec<KeepRule>, String>>()?;
+        Ok(Self::new(rules))
+    }
+
+    /// Returns index of file to keep, None when group is empty or no file is inside folder required by rule
+    /// When rules don't decide, first of equally good files is kept
+    pub fn file_to_keep(&self, group: &[KeepCandidate]) -> Option<usize> {
+        let mut indexes: Vec<usize> = (0..group.len()).collect();
+        for rule in &self.rules {
+            indexes = rule.filter(group, indexes)?;
+        }
+        indexes.first().copied()
+    }
+}
+
+fn has_copy_suffix(path: &Path) -> bool {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+    if COPY_SUFFIXES.iter().any(|suffix| stem.ends_with(suffix)) {
+        return true;
+    }
+    // Numbered copies like `photo (1)`
+    match stem.strip_suffix(')').and_then(|e| e.rsplit_once(" (")) {
+        Some((_name, number)) => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::common_keep_policy::{KeepCandidate, KeepPolicy, KeepRule};
+
+    fn candidate(path: &str, size: u64, modified_date: u64) -> KeepCandidate {
+        KeepCandidate {
+            path: PathBuf::from(path),
+            size,
+            modified_date,
+            dimensions: None,
+        }
+    }
+
+    #[test]
+    fn test_keep_rules() {
+        let group = vec![candidate("/a/1", 10, 5), candidate("/b/2", 30, 1), candidate("/c/3", 20, 9)];
+        assert_eq!(KeepRule::Newest.file_to_keep(&group), Some(2));
+        assert_eq!(KeepRule::Oldest.file_to_keep(&group), Some(1));
+        assert_eq!(KeepRule::Biggest.file_to_keep(&group), Some(1));
+        assert_eq!(KeepRule::InPath("/a".into()).file_to_keep(&group), Some(0));
+        assert_eq!(KeepRule::InPath("/d".into()).file_to_keep(&group), None);
+        assert_eq!(KeepRule::PreferPath("/d".into()).file_to_keep(&group), Some(0));
+    }
+
+    #[test]
+    fn test_keep_policy() {
+        let group = vec![
+            candidate("/srv/copy/photo (1).jpg", 10, 1),
+            candidate("/srv/master/deep/photo.jpg", 10, 5),
+            candidate("/srv/master/photo (1).jpg", 10, 3),
+            candidate("/srv/master/photo.jpg", 10, 7),
+        ];
+        let policy = KeepPolicy::from_text("prefer-path:/srv/master, without-copy-suffix, shortest-path, oldest").unwrap();
+        assert_eq!(policy.file_to_keep(&group), Some(3));
+        let policy = KeepPolicy::from_text("prefer-path:/srv/master,without-copy-suffix,oldest").unwrap();
+        assert_eq!(policy.file_to_keep(&group), Some(1));
+        assert_eq!(KeepPolicy::from_text("oldest").unwrap().file_to_keep(&group), Some(0));
+        assert!(KeepPolicy::from_text("oldest,unknown").is_err());
+
+        let mut images = vec![candidate("/a.jpg", 10, 1), candidate("/b.jpg", 5, 1)];
+        images[1].dimensions = Some((1920, 1080));
+        images[0].dimensions = Some((800, 600));
+        assert_eq!(KeepRule::HighestResolution.file_to_keep(&images), Some(1));
+        // Dimensions come from text of results, so they may be too big to multiply as integers of the same size
+        images[0].dimensions = Some((u64::MAX, u64::MAX));
+        assert_eq!(KeepRule::HighestResolution.file_to_keep(&images), Some(0));
+        assert_eq!(KeepRule::LowestResolution.file_to_keep(&images), Some(1));
+    }
+}
@@ -1,14 +1,150 @@
-#[derive(Default)]
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common_progress::ProgressStage;
+
+/// Decides in which list text of diagnostic is visible
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticLevel {
+    Message,
+    Warning,
+    Error,
+}
+
+/// Class of problem, allows to react to specific failures without parsing localized text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    CannotOpenDir,
+    CannotReadEntry,
+    CannotReadMetadata,
+    CannotGetDeviceId,
+    NonUtf8Name,
+    ModifiedBeforeEpoch,
+    NoModificationDate,
+    CannotOpenFile,
+    CannotReadFile,
+    CannotCreateFile,
+    CannotWriteFile,
+    CannotRemoveFile,
+    CannotRemoveFolder,
+    CannotCreateLink,
+    CannotRestoreFile,
+    InvalidCache,
+    ChangedSinceScan,
+    HashCollision,
+    InvalidFile,
+    InvalidSettings,
+    MissingDependency,
+    Other,
+}
+
+/// Typed version of warning or error, text is the same as the one added to `Messages` text lists
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    pub kind: DiagnosticKind,
+    pub path: Option<PathBuf>,
+    #[serde(with = "io_error_kind_serde")]
+    pub io_error_kind: Option<io::ErrorKind>,
+    pub stage: Option<ProgressStage>,
+    pub text: String,
+}
+
+impl Diagnostic {
+    pub fn new(level: DiagnosticLevel, kind: DiagnosticKind, text: String) -> Self {
+        Self {
+            level,
+            kind,
+            path: None,
+            io_error_kind: None,
+            stage: None,
+            text,
+        }
+    }
+
+    pub fn message(kind: DiagnosticKind, text: String) -> Self {
+        Self::new(DiagnosticLevel::Message, kind, text)
+    }
+
+    pub fn warning(kind: DiagnosticKind, text: String) -> Self {
+        Self::new(DiagnosticLevel::Warning, kind, text)
+    }
+
+    pub fn error(kind: DiagnosticKind, text: String) -> Self {
+        Self::new(DiagnosticLevel::Error, kind, text)
+    }
+
+    pub fn path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    pub fn io_error(mut self, error: &io::Error) -> Self {
+        self.io_error_kind = Some(error.kind());
+        self
+    }
+
+    pub fn stage(mut self, stage: ProgressStage) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Texts are only visible to user, while `diagnostics` contains typed info about every warning and error added by `add_diagnostic`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Messages {
     pub messages: Vec<String>,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl Messages {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Adds text of diagnostic to proper list and keeps typed diagnostic
+    pub fn add_diagnostic(&mut self, diagnostic: Diagnostic) {
+        match diagnostic.level {
+            DiagnosticLevel::Message => self.messages.push(diagnostic.text.clone()),
+            DiagnosticLevel::Warning => self.warnings.push(diagnostic.text.clone()),
+            DiagnosticLevel::Error => self.errors.push(diagnostic.text.clone()),
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn extend_diagnostics(&mut self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        for diagnostic in diagnostics {
+            self.add_diagnostic(diagnostic);
+        }
+    }
+
+    /// Appends messages of other tool, e.g. one used internally to do part of work
+    pub fn extend_with_another_messages(&mut self, messages: Messages) {
+        self.messages.extend(messages.messages);
+        self.warnings.extend(messages.warnings);
+        self.errors.extend(messages.errors);
+        self.diagnostics.extend(messages.diagnostics);
+    }
+
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn get_diagnostics_of_kind(&self, kind: DiagnosticKind) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(move |e| e.kind == kind)
+    }
+
     pub fn print_messages(&self) {
         println!("{}", self.create_messages_text());
     }
@@ -47,3 +183,86 @@ impl Messages {
         text_to_return
     }
 }
+
+/// io::ErrorKind doesn't implement serde traits, so it is saved as its name
+mod io_error_kind_serde {
+    use std::io::ErrorKind;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const KNOWN_KINDS: [ErrorKind; 18] = [
+        ErrorKind::NotFound,
+        ErrorKind::PermissionDenied,
+        ErrorKind::ConnectionRefused,
+        ErrorKind::ConnectionReset,
+        ErrorKind::ConnectionAborted,
+        ErrorKind::NotConnected,
+        ErrorKind::AddrInUse,
+        ErrorKind::AddrNotAvailable,
+        ErrorKind::BrokenPipe,
+        ErrorKind::AlreadyExists,
+        ErrorKind::WouldBlock,
+        ErrorKind::InvalidInput,
+        ErrorKind::InvalidData,
+        ErrorKind::TimedOut,
+        ErrorKind::WriteZero,
+        ErrorKind::Interrupted,
+        ErrorKind::Unsupported,
+        ErrorKind::UnexpectedEof,
+    ];
+
+    pub fn serialize<S: Serializer>(kind: &Option<ErrorKind>, serializer: S) -> Result<S::Ok, S::Error> {
+        match kind {
+            Some(kind) => serializer.serialize_some(&format!("{:?}", kind)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ErrorKind>, D::Error> {
+        let name: Option<String> = Option::deserialize(deserializer)?;
+        // Kinds unknown to this version are saved as Other, to not fail loading whole file
+        Ok(name.map(|name| KNOWN_KINDS.iter().find(|kind| format!("{:?}", kind) == name).copied().unwrap_or(ErrorKind::Other)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use crate::common_messages::{Diagnostic, DiagnosticKind, DiagnosticLevel, Messages};
+    use crate::common_progress::ProgressStage;
+
+    #[test]
+    fn test_add_diagnostic_keeps_text_and_kind() {
+        let mut messages = Messages::new();
+        let error = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        messages.add_diagnostic(
+            Diagnostic::warning(DiagnosticKind::CannotOpenDir, "Cannot open dir".to_string())
+                .path(Path::new("/tmp/a"))
+                .io_error(&error),
+        );
+        messages.add_diagnostic(Diagnostic::error(DiagnosticKind::CannotRemoveFile, "Cannot remove".to_string()));
+
+        assert_eq!(messages.warnings, vec!["Cannot open dir".to_string()]);
+        assert_eq!(messages.errors, vec!["Cannot remove".to_string()]);
+        assert_eq!(messages.get_diagnostics_of_kind(DiagnosticKind::CannotOpenDir).count(), 1);
+        assert_eq!(messages.get_diagnostics()[0].io_error_kind, Some(io::ErrorKind::PermissionDenied));
+        assert_eq!(messages.get_diagnostics()[0].path, Some(PathBuf::from("/tmp/a")));
+    }
+
+    #[test]
+    fn test_diagnostic_serialization() {
+        let error = io::Error::new(io::ErrorKind::NotFound, "missing");
+        let diagnostic = Diagnostic::warning(DiagnosticKind::CannotReadMetadata, "text".to_string())
+            .path(Path::new("/tmp/b"))
+            .io_error(&error)
+            .stage(ProgressStage::CollectingFiles);
+
+        let serialized = serde_json::to_string(&diagnostic).unwrap();
+        assert!(serialized.contains("\"NotFound\""));
+        let deserialized: Diagnostic = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, diagnostic);
+        assert_eq!(deserialized.level, DiagnosticLevel::Warning);
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::{sleep, JoinHandle};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::LOOP_DURATION;
+use crate::common_dir_traversal::CheckingMethod;
+
+/// Stage of search, every tool goes through one or more of them
+#[derive(PartialEq, Eq, Clone, Debug, Copy, Serialize, Deserialize)]
+pub enum ProgressStage {
+    CollectingFiles,
+    CollectingFolders,
+    CalculatingPrehashes,
+    CalculatingHashes,
+    ReadingMusicTags,
+    ComparingMusicTags,
+    HashingImages,
+    ComparingImageHashes,
+    HashingVideos,
+    CheckingBrokenFiles,
+    CheckingExtensions,
+}
+
+impl ProgressStage {
+    /// Stable, not translated name of stage, usable by non GUI frontends
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ProgressStage::CollectingFiles => "collecting_files",
+            ProgressStage::CollectingFolders => "collecting_folders",
+            ProgressStage::CalculatingPrehashes => "calculating_prehashes",
+            ProgressStage::CalculatingHashes => "calculating_hashes",
+            ProgressStage::ReadingMusicTags => "reading_music_tags",
+            ProgressStage::ComparingMusicTags => "comparing_music_tags",
+            ProgressStage::HashingImages => "hashing_images",
+            ProgressStage::ComparingImageHashes => "comparing_image_hashes",
+            ProgressStage::HashingVideos => "hashing_videos",
+            ProgressStage::CheckingBrokenFiles => "checking_broken_files",
+            ProgressStage::CheckingExtensions => "checking_extensions",
+        }
+    }
+}
+
+/// Progress event sent periodically by every tool
+/// `entries_to_check` and `bytes_to_check` are 0 when total amount is not known(e.g. when collecting files)
+#[derive(Clone, Debug)]
+pub struct ProgressData {
+    pub checking_method: CheckingMethod,
+    pub stage: ProgressStage,
+    pub current_stage: u8,
+    pub max_stage: u8,
+    pub entries_checked: usize,
+    pub entries_to_check: usize,
+    pub bytes_checked: u64,
+    pub bytes_to_check: u64,
+    pub current_path: Option<PathBuf>,
+}
+
+impl ProgressData {
+    pub fn new(stage: ProgressStage, current_stage: u8, max_stage: u8) -> Self {
+        Self {
+            checking_method: CheckingMethod::None,
+            stage,
+            current_stage,
+            max_stage,
+            entries_checked: 0,
+            entries_to_check: 0,
+            bytes_checked: 0,
+            bytes_to_check: 0,
+            current_path: None,
+        }
+    }
+
+    pub fn checking_method(mut self, checking_method: CheckingMethod) -> Self {
+        self.checking_method = checking_method;
+        self
+    }
+
+    pub fn entries_to_check(mut self, entries_to_check: usize) -> Self {
+        self.entries_to_check = entries_to_check;
+        self
+    }
+
+    pub fn bytes_to_check(mut self, bytes_to_check: u64) -> Self {
+        self.bytes_to_check = bytes_to_check;
+        self
+    }
+}
+
+struct ProgressCounters {
+    entries_checked: AtomicUsize,
+    bytes_checked: AtomicU64,
+    current_path: Mutex<Option<PathBuf>>,
+}
+
+/// Thread which sends progress of current stage every LOOP_DURATION ms
+/// Counters may be updated from multiple threads, thread is stopped when reporter is ended or dropped
+pub struct ProgressReporter {
+    enabled: bool,
+    counters: Arc<ProgressCounters>,
+    progress_thread_run: Arc<AtomicBool>,
+    progress_thread_handle: Option<JoinHandle<()>>,
+}
+
+impl ProgressReporter {
+    pub fn new(progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>, progress_data: ProgressData) -> Self {
+        let counters = Arc::new(ProgressCounters {
+            entries_checked: AtomicUsize::new(0),
+            bytes_checked: AtomicU64::new(0),
+            current_path: Mutex::new(None),
+        });
+        let progress_thread_run = Arc::new(AtomicBool::new(true));
+
+        let progress_thread_handle = progress_sender.map(|progress_sender| {
+            let progress_send = progress_sender.clone();
+            let progress_thread_run = progress_thread_run.clone();
+            let counters = counters.clone();
+            thread::spawn(move || loop {
+                let mut progress_data = progress_data.clone();
+                progress_data.entries_checked = counters.entries_checked.load(Ordering::Relaxed);
+                progress_data.bytes_checked = counters.bytes_checked.load(Ordering::Relaxed);
+                progress_data.current_path = counters.current_path.lock().unwrap().clone();
+                // Receiver may be already dropped, then there is nobody to inform
+                let _ = progress_send.unbounded_send(progress_data);
+                if !progress_thread_run.load(Ordering::Relaxed) {
+                    break;
+                }
+                sleep(Duration::from_millis(LOOP_DURATION as u64));
+            })
+        });
+
+        Self {
+            enabled: progress_thread_handle.is_some(),
+            counters,
+            progress_thread_run,
+            progress_thread_handle,
+        }
+    }
+
+    pub fn add_entries(&self, entries: usize) {
+        self.counters.entries_checked.fetch_add(entries, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.counters.bytes_checked.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Path is only informational, so it is not stored when nobody listens for progress
+    pub fn set_current_path(&self, path: &Path) {
+        if self.enabled {
+            *self.counters.current_path.lock().unwrap() = Some(path.to_path_buf());
+        }
+    }
+
+    pub fn entries_checked(&self) -> usize {
+        self.counters.entries_checked.load(Ordering::Relaxed)
+    }
+
+    /// Sends last progress info and waits for end of thread
+    pub fn end(mut self) {
+        self.stop();
+    }
+
+    /// Thread sends progress once more after noticing that it should stop, so last sent counters are always current
+    fn stop(&mut self) {
+        self.progress_thread_run.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.progress_thread_handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn test_progress_reporter_sends_counters() {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let reporter = ProgressReporter::new(
+            Some(&sender),
+            ProgressData::new(ProgressStage::CalculatingHashes, 2, 2).entries_to_check(3).bytes_to_check(30),
+        );
+        reporter.add_entries(3);
+        reporter.add_bytes(30);
+        reporter.set_current_path(Path::new("/tmp/a"));
+        reporter.end();
+        drop(sender);
+
+        let last = futures::executor::block_on(receiver.collect::<Vec<_>>()).pop().unwrap();
+        assert_eq!(last.stage, ProgressStage::CalculatingHashes);
+        assert_eq!(last.entries_checked, 3);
+        assert_eq!(last.bytes_checked, 30);
+        assert_eq!(last.bytes_to_check, 30);
+        assert_eq!(last.current_path, Some(PathBuf::from("/tmp/a")));
+    }
+
+    #[test]
+    fn test_progress_reporter_without_sender() {
+        let reporter = ProgressReporter::new(None, ProgressData::new(ProgressStage::CollectingFiles, 0, 0));
+        reporter.add_entries(2);
+        reporter.set_current_path(Path::new("/tmp/a"));
+        assert_eq!(reporter.entries_checked(), 2);
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+use crate::common_archive::ensure_not_archive_entry;
+use crate::common_journal::{record_operation, Journal, JournalAction, JournalEntry};
+use crate::common_messages::Diagnostic;
+
+/// Decides what happens with files removed by tools
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemovalMethod {
+    Permanent,
+    /// Uses freedesktop.org Trash specification on Linux and system trash on other platforms
+    Trash,
+    /// Moves files into given folder, full original path is recreated inside it
+    Quarantine(PathBuf),
+}
+
+impl Default for RemovalMethod {
+    fn default() -> Self {
+        RemovalMethod::Permanent
+    }
+}
+
+impl RemovalMethod {
+    /// Removes file or folder with its content, operation is saved to journal when it is given
+    /// Returned warning means that file was removed, but saving it to journal failed
+    pub fn remove(&self, path: &Path, journal: Option<&Journal>) -> io::Result<Option<Diagnostic>> {
+        self.remove_with_hash(path, None, journal)
+    }
+
+    /// Same as `remove`, but also saves known hash of file to journal
+    pub fn remove_with_hash(&self, path: &Path, hash: Option<&str>, journal: Option<&Journal>) -> io::Result<Option<Diagnostic>> {
+        ensure_not_archive_entry(path)?;
+        let metadata = fs::symlink_metadata(path)?;
+        let size = if metadata.is_dir() { 0 } else { metadata.len() };
+        let entry = match self {
+            RemovalMethod::Permanent => {
+                if metadata.is_dir() {
+                    fs::remove_dir_all(path)?;
+                } else {
+                    fs::remove_file(path)?;
+                }
+                JournalEntry::new(JournalAction::Delete, path, size)
+            }
+            RemovalMethod::Trash => {
+                let entry = JournalEntry::new(JournalAction::Trash, path, size);
+                match move_to_trash(path)? {
+                    Some(trashed_file) => entry.target(&trashed_file),
+                    None => entry,
+                }
+            }
+            RemovalMethod::Quarantine(quarantine_folder) => {
+                let destination = quarantine_path(quarantine_folder, path);
+                if let Some(parent) = destination.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                move_path(path, &destination)?;
+                JournalEntry::new(JournalAction::Quarantine, path, size).target(&destination)
+            }
+        };
+        Ok(record_operation(journal, &entry.hash(hash)))
+    }
+
+    /// Text used in dry run instead of removing file
+    pub fn describe(&self, path: &Path) -> String {
+        match self {
+            RemovalMethod::Permanent => format!("Delete {}", path.display()),
+            RemovalMethod::Trash => format!("Move {} to trash", path.display()),
+            RemovalMethod::Quarantine(quarantine_folder) => format!("Move {} to quarantine {}", path.display(), quarantine_folder.display()),
+        }
+    }
+}
+
+/// Place of file inside quarantine folder, e.g. /home/a/b.txt is moved to <quarantine>/home/a/b.txt
+pub fn quarantine_path(quarantine_folder: &Path, path: &Path) -> PathBuf {
+    let mut destination = quarantine_folder.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => destination.push(prefix.as_os_str().to_string_lossy().replace(':', "")),
+            Component::Normal(part) => destination.push(part),
+            Component::RootDir | Component::CurDir | Component::ParentDir => {}
+        }
+    }
+    destination
+}
+
+/// Moves file or folder without overwriting existing one, content is copied when rename between filesystems is not possible
+pub fn move_path(path: &Path, destination: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(destination).is_ok() {
+        return Err(Error::new(ErrorKind::AlreadyExists, format!("{} already exists", destination.display())));
+    }
+    if fs::rename(path, destination).is_ok() {
+        return Ok(());
+    }
+
+    let result = if path.is_dir() {
+        let options = fs_extra::dir::CopyOptions {
+            copy_inside: true,
+            ..fs_extra::dir::CopyOptions::new()
+        };
+        fs_extra::dir::move_dir(path, destination, &options).map(|_| ())
+    } else {
+        fs_extra::file::move_file(path, destination, &fs_extra::file::CopyOptions::new()).map(|_| ())
+    };
+    result.map_err(|e| Error::new(ErrorKind::Other, e.to_string()))
+}
+
+/// Returns location of file inside trash, when it is known
+#[cfg(not(target_os = "linux"))]
+fn move_to_trash(path: &Path) -> io::Result<Option<PathBuf>> {
+    trash::delete(path).map(|_| None).map_err(|e| Error::new(ErrorKind::Other, e.to_string()))
+}
+
+#[cfg(target_os = "linux")]
+fn move_to_trash(path: &Path) -> io::Result<Option<PathBuf>> {
+    freedesktop_trash::move_to_trash(path).map(Some)
+}
+
+/// https://specifications.freedesktop.org/trash-spec/trashspec-latest.html
+#[cfg(target_os = "linux")]
+mod freedesktop_trash {
+    use std::fs::{self, DirBuilder, OpenOptions};
+    use std::io::{self, Error, ErrorKind, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::path::{Path, PathBuf};
+
+    use directories_next::BaseDirs;
+
+    pub fn move_to_trash(path: &Path) -> io::Result<PathBuf> {
+        let path = if path.is_absolute() { path.to_path_buf() } else { std::env::current_dir()?.join(path) };
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Cannot move to trash path without file name"))?;
+        let device = fs::symlink_metadata(&path)?.dev();
+        let trash_folder = find_trash_folder(&path, device)?;
+
+        let files_folder = trash_folder.join("files");
+        let info_folder = trash_folder.join("info");
+        fs::create_dir_all(&files_folder)?;
+        fs::create_dir_all(&info_folder)?;
+
+        // Info file is created first to atomically reserve name in trash
+        let base_name = file_name.to_string_lossy().to_string();
+        for index in 1.. {
+            let trashed_name = if index == 1 { base_name.clone() } else { format!("{}.{}", base_name, index) };
+            let info_file = info_folder.join(format!("{}.trashinfo", trashed_name));
+            let trashed_file = files_folder.join(&trashed_name);
+            if fs::symlink_metadata(&trashed_file).is_ok() {
+                continue;
+            }
+            let mut info_handler = match OpenOptions::new().write(true).create_new(true).open(&info_file) {
+                Ok(t) => t,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            let content = format!(
+                "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                encode_path(&path),
+                chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+            );
+            if let Err(e) = info_handler.write_all(content.as_bytes()).and_then(|_| fs::rename(&path, &trashed_file)) {
+                let _ = fs::remove_file(&info_file);
+                return Err(e);
+            }
+            return Ok(trashed_file);
+        }
+        unreachable!()
+    }
+
+    /// Home trash is used for files on same device, otherwise $topdir/.Trash-$uid is used to avoid copying files
+    fn find_trash_folder(path: &Path, device: u64) -> io::Result<PathBuf> {
+        let data_folder = BaseDirs::new()
+            .map(|e| e.data_dir().to_path_buf())
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "Cannot find home trash folder"))?;
+        let home_trash = data_folder.join("Trash");
+        if existing_ancestor_device(&home_trash)? == device {
+            return Ok(home_trash);
+        }
+
+        let uid = fs::metadata("/proc/self")?.uid();
+        let top_folder = find_mount_point(path, device);
+        let shared_trash = top_folder.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared_trash) {
+            // Shared trash must have sticky bit and cannot be symlink
+            if metadata.is_dir() && metadata.mode() & 0o1000 != 0 {
+                let user_trash = shared_trash.join(uid.to_string());
+                if fs::symlink_metadata(&user_trash).is_ok() || DirBuilder::new().mode(0o700).create(&user_trash).is_ok() {
+                    return Ok(user_trash);
+                }
+            }
+        }
+
+        let user_trash = top_folder.join(format!(".Trash-{}", uid));
+        if fs::symlink_metadata(&user_trash).is_err() {
+            DirBuilder::new().mode(0o700).create(&user_trash)?;
+        }
+        Ok(user_trash)
+    }
+
+    fn existing_ancestor_device(path: &Path) -> io::Result<u64> {
+        let mut current = Some(path);
+        while let Some(folder) = current {
+            if let Ok(metadata) = fs::metadata(folder) {
+                return Ok(metadata.dev());
+            }
+            current = folder.parent();
+        }
+        Err(Error::new(ErrorKind::NotFound, format!("None of parents of {} exists", path.display())))
+    }
+
+    fn find_mount_point(path: &Path, device: u64) -> PathBuf {
+        let mut top_folder = path.parent().unwrap_or(path).to_path_buf();
+        while let Some(parent) = top_folder.parent() {
+            match fs::metadata(parent) {
+                Ok(metadata) if metadata.dev() == device => top_folder = parent.to_path_buf(),
+                _ => break,
+            }
+        }
+        top_folder
+    }
+
+    /// Path must be saved in URL-like form with escaped special characters
+    fn encode_path(path: &Path) -> String {
+        let mut encoded = String::new();
+        for byte in path.as_os_str().as_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(*byte as char),
+                _ => encoded.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        encoded
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::Path;
+
+        use super::encode_path;
+
+        #[test]
+        fn test_encode_path() {
+            assert_eq!(encode_path(Path::new("/home/a b/zażółć.txt")), "/home/a%20b/za%C5%BC%C3%B3%C5%82%C4%87.txt");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::common_remove::{quarantine_path, RemovalMethod};
+
+    #[test]
+    fn test_quarantine_keeps_original_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a").join("b.txt");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "abc").unwrap();
+
+        let quarantine = dir.path().join("quarantine");
+        RemovalMethod::Quarantine(quarantine.clone()).remove(&file, None).unwrap();
+        assert!(!file.exists());
+        assert_eq!(fs::read_to_string(quarantine_path(&quarantine, &file)).unwrap(), "abc");
+        assert_eq!(quarantine_path(Path::new("/q"), Path::new("/home/a.txt")), Path::new("/q/home/a.txt"));
+    }
+}
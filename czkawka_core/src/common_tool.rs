@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use crossbeam_channel::Receiver;
+
+use crate::common_export::ExportedResults;
+use crate::common_messages::Messages;
+use crate::common_progress::ProgressData;
+use crate::common_traits::{DebugPrint, ExportResults, PrintResults, SaveResults};
+
+/// Search settings which are used by every tool
+/// Settings which are not supported by tool(e.g. allowed extensions in empty folders) are silently ignored
+#[derive(Clone, Debug)]
+pub struct SearchSettings {
+    pub included_directories: Vec<PathBuf>,
+    pub excluded_directories: Vec<PathBuf>,
+    pub reference_directories: Vec<PathBuf>,
+    pub excluded_items: Vec<String>,
+    pub allowed_extensions: String,
+    pub recursive_search: bool,
+    #[cfg(target_family = "unix")]
+    pub exclude_other_filesystems: bool,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            included_directories: vec![],
+            excluded_directories: vec![],
+            reference_directories: vec![],
+            excluded_items: vec![],
+            allowed_extensions: "".to_string(),
+            recursive_search: true,
+            #[cfg(target_family = "unix")]
+            exclude_other_filesystems: false,
+        }
+    }
+}
+
+impl SearchSettings {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// Interface shared by all finders, which allows to drive any of them without knowing its concrete type
+pub trait Tool: DebugPrint + PrintResults + SaveResults + ExportResults {
+    /// Applies common settings, must be used before `run`
+    fn set_search_settings(&mut self, search_settings: SearchSettings);
+
+    /// Starts search, results are kept inside tool
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>);
+
+    /// Uses results saved earlier instead of searching, entries changed since saving are skipped
+    /// Configured delete method is applied to loaded results, like after normal search
+    fn load_results(&mut self, results: ExportedResults);
+
+    fn get_stopped_search(&self) -> bool;
+
+    fn get_text_messages(&self) -> &Messages;
+
+    /// Number of found entries - in grouped results only files which are not first in group are counted
+    fn get_number_of_found_entries(&self) -> usize;
+}
@@ -1,3 +1,5 @@
+use crate::common_export::ExportedResults;
+
 pub trait DebugPrint {
     fn debug_print(&self);
 }
@@ -9,3 +11,7 @@ pub trait SaveResults {
 pub trait PrintResults {
     fn print_results(&self);
 }
+
+pub trait ExportResults {
+    fn get_exported_results(&self) -> ExportedResults;
+}
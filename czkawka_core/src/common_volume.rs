@@ -0,0 +1,353 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File in root folder of filesystem, which gives it identifier when it has no UUID or when it should store own caches
+/// First line contains identifier, line `cache` means that caches of files from this filesystem are saved on it
+pub const VOLUME_MARKER_FILE: &str = ".czkawka_volume";
+/// Folder in root folder of filesystem, with caches of its files
+pub const VOLUME_CACHE_FOLDER: &str = ".czkawka_cache";
+/// Paths saved relative to filesystem start with this prefix and identifier of filesystem
+pub const VOLUME_PATH_PREFIX: &str = "czkawka-volume:";
+
+/// Mounted filesystem
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Volume {
+    /// UUID of filesystem or identifier from marker file, None when filesystem cannot be identified
+    pub id: Option<String>,
+    pub mount_point: PathBuf,
+    /// Folder of filesystem mounted in mount point, different than `/` for bind mounts and subvolumes
+    pub root: PathBuf,
+    /// Caches of files from this filesystem are saved in its root folder
+    pub cache_on_volume: bool,
+}
+
+/// Mounted filesystems, used to save paths in caches relative to filesystem, so they are still valid after mounting it in other place
+/// Paths on root filesystem are always saved as they are
+#[derive(Clone, Debug, Default)]
+pub struct Volumes {
+    volumes: Vec<Volume>,
+}
+
+impl Volumes {
+    /// Volumes with longer mount point are checked first, because they may be mounted inside other volumes
+    pub fn new(mut volumes: Vec<Volume>) -> Self {
+        volumes.sort_by_key(|volume| std::cmp::Reverse(volume.mount_point.components().count()));
+        Self { volumes }
+    }
+
+    /// Currently mounted filesystems, empty on systems other than Linux
+    /// Marker files on network and temporary filesystems are not read, see `mounted_for`
+    pub fn mounted() -> Self {
+        Self::mounted_for(&[])
+    }
+
+    /// Currently mounted filesystems, marker files on network and temporary filesystems are read only when they contain given folders or are mounted inside them
+    pub fn mounted_for(folders: &[PathBuf]) -> Self {
+        Self::new(read_mounted_volumes(folders))
+    }
+
+    pub fn get_volumes(&self) -> &[Volume] {
+        &self.volumes
+    }
+
+    /// Volume which contains path, None also for files on root filesystem
+    pub fn volume_of(&self, path: &Path) -> Option<&Volume> {
+        let volume = self.volumes.iter().find(|volume| path.starts_with(&volume.mount_point))?;
+        if volume.mount_point == Path::new("/") {
+            None
+        } else {
+            Some(volume)
+        }
+    }
+
+    /// Folder with caches of files from given path, None when they are saved in default cache folder
+    pub fn cache_folder_of(&self, path: &Path) -> Option<PathBuf> {
+        self.volume_of(path)
+            .filter(|volume| volume.cache_on_volume)
+            .map(|volume| volume.mount_point.join(VOLUME_CACHE_FOLDER))
+    }
+
+    /// Changes path of file on identified volume to path relative to its filesystem, other paths are not changed
+    pub fn to_cache_path(&self, path: &Path) -> PathBuf {
+        if let Some(volume) = self.volume_of(path) {
+            if let (Some(id), Ok(relative_path)) = (&volume.id, path.strip_prefix(&volume.mount_point)) {
+                let path_inside_volume = volume.root.join(relative_path);
+                return PathBuf::from(format!("{}{}", VOLUME_PATH_PREFIX, id)).join(path_inside_volume.strip_prefix("/").unwrap_or(&path_inside_volume));
+            }
+        }
+        path.to_path_buf()
+    }
+
+    /// Changes path relative to filesystem into absolute path, None when filesystem is not mounted now
+    pub fn from_cache_path(&self, path: &Path) -> Option<PathBuf> {
+        let (id, path_inside_volume) = match split_volume_path(path) {
+            Some(t) => t,
+            None => return Some(path.to_path_buf()),
+        };
+        self.volumes.iter().filter(|volume| volume.id.as_deref() == Some(id)).find_map(|volume| {
+            let relative_path = path_inside_volume.strip_prefix(&volume.root).ok()?;
+            Some(volume.mount_point.join(relative_path))
+        })
+    }
+}
+
+/// True when path was saved relative to filesystem
+pub fn is_volume_path(path: &Path) -> bool {
+    split_volume_path(path).is_some()
+}
+
+/// Splits path into identifier of filesystem and absolute path inside it
+fn split_volume_path(path: &Path) -> Option<(&str, PathBuf)> {
+    let mut components = path.components();
+    let id = match components.next()? {
+        Component::Normal(first) => first.to_str()?.strip_prefix(VOLUME_PATH_PREFIX)?,
+        _ => return None,
+    };
+    Some((id, Path::new("/").join(components.as_path())))
+}
+
+/// Creates marker file in root folder of filesystem which contains path and returns path of marker
+/// Already known identifier of filesystem is kept, so saved caches are still valid
+pub fn mark_volume(path: &Path, cache_on_volume: bool) -> io::Result<PathBuf> {
+    let path = path.canonicalize()?;
+    let volumes = Volumes::mounted_for(std::slice::from_ref(&path));
+    let volume = volumes
+        .volume_of(&path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} is on root filesystem", path.display())))?;
+    let id = match &volume.id {
+        Some(id) => id.clone(),
+        None => generate_volume_id(),
+    };
+    let marker_file = volume.mount_point.join(VOLUME_MARKER_FILE);
+    let mut content = format!("{}\n", id);
+    if cache_on_volume {
+        content.push_str("cache\n");
+    }
+    fs::write(&marker_file, content)?;
+    Ok(marker_file)
+}
+
+fn generate_volume_id() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|e| e.as_nanos()).unwrap_or_default();
+    let hash = blake3::hash(format!("{}-{}", nanos, std::process::id()).as_bytes());
+    hash.to_hex()[..32].to_string()
+}
+
+/// Reads identifier and cache setting from marker file
+fn read_marker_file(mount_point: &Path) -> Option<(String, bool)> {
+    let content = fs::read_to_string(mount_point.join(VOLUME_MARKER_FILE)).ok()?;
+    let mut lines = content.lines().map(str::trim);
+    let id = lines.next().filter(|id| !id.is_empty() && !id.contains('/'))?.to_string();
+    Some((id, lines.any(|line| line == "cache")))
+}
+
+/// Filesystems which never contain user files
+#[cfg(target_os = "linux")]
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Marker files on these filesystems are read only when they are scanned, FUSE filesystems are treated the same way
+/// Reading from network filesystem hangs when its server is unavailable, temporary and container filesystems are often mounted in big numbers
+#[cfg(target_os = "linux")]
+const PROBED_WHEN_SCANNED_FILESYSTEMS: &[&str] = &[
+    "9p",
+    "afs",
+    "ceph",
+    "cifs",
+    "davfs",
+    "fuse",
+    "glusterfs",
+    "ncpfs",
+    "nfs",
+    "nfs4",
+    "overlay",
+    "ramfs",
+    "smb3",
+    "smbfs",
+    "sshfs",
+    "tmpfs",
+];
+
+#[cfg(target_os = "linux")]
+fn is_probed_when_scanned(fs_type: &str) -> bool {
+    PROBED_WHEN_SCANNED_FILESYSTEMS.contains(&fs_type) || fs_type.starts_with("fuse.")
+}
+
+/// Reads mounted filesystems from /proc/self/mountinfo, filesystems are identified by UUID from /dev/disk/by-uuid or by marker file
+#[cfg(target_os = "linux")]
+fn read_mounted_volumes(folders: &[PathBuf]) -> Vec<Volume> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mountinfo = match fs::read_to_string("/proc/self/mountinfo") {
+        Ok(t) => t,
+        Err(_) => return Vec::new(),
+    };
+    let uuids: Vec<(String, Option<u64>, Option<PathBuf>)> = match fs::read_dir("/dev/disk/by-uuid") {
+        Ok(read_dir) => read_dir
+            .flatten()
+            .map(|entry| {
+                let path = entry.path();
+                (
+                    entry.file_name().to_string_lossy().to_string(),
+                    fs::metadata(&path).ok().map(|e| e.rdev()),
+                    path.canonicalize().ok(),
+                )
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            // Format is described in proc(5), optional fields end with separator `-`
+            let (mount_fields, fs_fields) = line.split_once(" - ")?;
+            let mount_fields: Vec<&str> = mount_fields.split(' ').collect();
+            let fs_fields: Vec<&str> = fs_fields.split(' ').collect();
+            let (major, minor) = mount_fields.get(2)?.split_once(':')?;
+            let device = make_device(major.parse().ok()?, minor.parse().ok()?);
+            let root = PathBuf::from(unescape_mountinfo(mount_fields.get(3)?));
+            let mount_point = PathBuf::from(unescape_mountinfo(mount_fields.get(4)?));
+            let fs_type = *fs_fields.first()?;
+            let source = fs_fields.get(1).map(|e| PathBuf::from(unescape_mountinfo(e)));
+
+            let is_scanned = folders.iter().any(|folder| folder.starts_with(&mount_point) || mount_point.starts_with(folder));
+            let marker = if !is_probed_when_scanned(fs_type) || is_scanned {
+                read_marker_file(&mount_point)
+            } else {
+                None
+            };
+
+            let (id, cache_on_volume) = if PSEUDO_FILESYSTEMS.contains(&fs_type) {
+                (None, false)
+            } else if let Some((id, cache_on_volume)) = marker {
+                (Some(id), cache_on_volume)
+            } else {
+                let source = source.and_then(|e| e.canonicalize().ok());
+                let uuid = uuids
+                    .iter()
+                    .find(|(_uuid, uuid_device, uuid_source)| *uuid_device == Some(device) || (source.is_some() && *uuid_source == source))
+                    .map(|(uuid, _uuid_device, _uuid_source)| uuid.clone());
+                (uuid, false)
+            };
+            Some(Volume {
+                id,
+                mount_point,
+                root,
+                cache_on_volume,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_mounted_volumes(_folders: &[PathBuf]) -> Vec<Volume> {
+    Vec::new()
+}
+
+/// Same encoding of device number as used by glibc
+#[cfg(target_os = "linux")]
+fn make_device(major: u64, minor: u64) -> u64 {
+    ((major & 0xffff_f000) << 32) | ((major & 0xfff) << 8) | ((minor & 0xffff_ff00) << 12) | (minor & 0xff)
+}
+
+/// Spaces, tabs, new lines and backslashes in paths are saved as octal escapes
+#[cfg(target_os = "linux")]
+fn unescape_mountinfo(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 4).and_then(|e| u8::from_str_radix(e, 8).ok());
+        match escape {
+            Some(character) => {
+                result.push(character as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    #[cfg(target_os = "linux")]
+    use crate::common_volume::is_probed_when_scanned;
+    use crate::common_volume::{is_volume_path, Volume, Volumes};
+
+    fn volume(id: Option<&str>, mount_point: &str, root: &str) -> Volume {
+        Volume {
+            id: id.map(str::to_string),
+            mount_point: PathBuf::from(mount_point),
+            root: PathBuf::from(root),
+            cache_on_volume: false,
+        }
+    }
+
+    #[test]
+    fn test_volume_paths() {
+        let volumes = Volumes::new(vec![
+            volume(Some("root"), "/", "/"),
+            volume(Some("usb"), "/media/alice/USB", "/"),
+            volume(None, "/media/alice/USB/network", "/"),
+        ]);
+        assert_eq!(volumes.to_cache_path(Path::new("/home/alice/a.txt")), PathBuf::from("/home/alice/a.txt"));
+        assert_eq!(
+            volumes.to_cache_path(Path::new("/media/alice/USB/network/a.txt")),
+            PathBuf::from("/media/alice/USB/network/a.txt")
+        );
+        let cache_path = volumes.to_cache_path(Path::new("/media/alice/USB/photos/a.jpg"));
+        assert_eq!(cache_path, PathBuf::from("czkawka-volume:usb/photos/a.jpg"));
+        assert!(is_volume_path(&cache_path));
+        assert_eq!(volumes.from_cache_path(Path::new("/home/alice/a.txt")), Some(PathBuf::from("/home/alice/a.txt")));
+
+        // Same filesystem mounted in other place, with only one of its folders mounted by bind mount
+        let remounted = Volumes::new(vec![volume(Some("usb"), "/run/media/alice/USB", "/"), volume(Some("usb"), "/srv/photos", "/photos")]);
+        assert_eq!(remounted.from_cache_path(&cache_path), Some(PathBuf::from("/run/media/alice/USB/photos/a.jpg")));
+        assert_eq!(remounted.to_cache_path(Path::new("/srv/photos/a.jpg")), cache_path);
+        assert_eq!(
+            Volumes::new(vec![volume(Some("usb"), "/srv/photos", "/photos")]).from_cache_path(&cache_path),
+            Some(PathBuf::from("/srv/photos/a.jpg"))
+        );
+        assert_eq!(Volumes::new(vec![volume(Some("usb"), "/srv/music", "/music")]).from_cache_path(&cache_path), None);
+        assert_eq!(Volumes::default().from_cache_path(&cache_path), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_probed_when_scanned() {
+        assert!(is_probed_when_scanned("nfs4"));
+        assert!(is_probed_when_scanned("fuse.sshfs"));
+        assert!(!is_probed_when_scanned("ext4"));
+        assert!(!is_probed_when_scanned("fuseblk"));
+    }
+}
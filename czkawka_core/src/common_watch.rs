@@ -0,0 +1,652 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, Metadata};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use notify::{RecursiveMode, Watcher};
+use rayon::prelude::*;
+
+use crate::broken_files::{self, check_broken_file, check_extension_avaibility, TypeOfFile};
+use crate::common::Common;
+use crate::common_dir_traversal::{DirTraversalBuilder, DirTraversalResult, FileEntry};
+use crate::common_directory::Directories;
+use crate::common_extensions::Extensions;
+use crate::common_items::ExcludedItems;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::ProgressData;
+use crate::common_tool::SearchSettings;
+use crate::common_volume::Volumes;
+use crate::duplicate::{hash_calculation, load_hashes_from_file, save_hashes_to_file, HashType, HASH_BUFFER_SIZE};
+
+/// Tools which results are updated when watched files change
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WatchTool {
+    Duplicates,
+    EmptyFiles,
+    BrokenFiles,
+}
+
+impl WatchTool {
+    pub const ALL: [WatchTool; 3] = [WatchTool::Duplicates, WatchTool::EmptyFiles, WatchTool::BrokenFiles];
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// Initial scan finished, its findings were already sent as events
+    InitialScanFinished {
+        files: usize,
+    },
+    /// File has the same content as other files, group contains all of them
+    Duplicate {
+        path: PathBuf,
+        group: Vec<PathBuf>,
+    },
+    EmptyFile {
+        path: PathBuf,
+    },
+    BrokenFile {
+        path: PathBuf,
+        error_string: String,
+    },
+    /// File is not reported anymore by tool, because it was removed, changed or has no duplicates anymore
+    Resolved {
+        tool: WatchTool,
+        path: PathBuf,
+    },
+    Warning(Diagnostic),
+}
+
+/// How often stop request is checked when nothing changes
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Changes are processed at least once per this many delays, also when files change all the time
+const MAX_DELAYS_WITHOUT_UPDATE: u32 = 10;
+
+/// Watched file with results of checks, hash is empty when file has no other file with the same size
+struct WatchedFile {
+    file_entry: FileEntry,
+    broken_error: Option<String>,
+}
+
+/// Scans folders once and later updates results of chosen tools when files are created, modified, renamed or removed
+pub struct DirectoryWatcher {
+    text_messages: Messages,
+    directories: Directories,
+    allowed_extensions: Extensions,
+    excluded_items: ExcludedItems,
+    recursive_search: bool,
+    minimal_file_size: u64,
+    maximal_file_size: u64,
+    hash_type: HashType,
+    tools: Vec<WatchTool>,
+    delay: Duration,
+    use_cache: bool,
+    minimal_cache_file_size: u64,
+    stopped_search: bool,
+    files: BTreeMap<PathBuf, WatchedFile>,
+    files_by_size: HashMap<u64, BTreeSet<PathBuf>>,
+    files_by_hash: HashMap<(u64, String), BTreeSet<PathBuf>>,
+    cached_hashes: HashMap<PathBuf, FileEntry>,
+}
+
+impl DirectoryWatcher {
+    pub fn new() -> Self {
+        Self {
+            text_messages: Messages::new(),
+            directories: Directories::new(),
+            allowed_extensions: Extensions::new(),
+            excluded_items: ExcludedItems::new(),
+            recursive_search: true,
+            minimal_file_size: 8192,
+            maximal_file_size: u64::MAX,
+            hash_type: HashType::Blake3,
+            tools: WatchTool::ALL.to_vec(),
+            delay: Duration::from_secs(2),
+            use_cache: true,
+            minimal_cache_file_size: 1024 * 256,
+            stopped_search: false,
+            files: Default::default(),
+            files_by_size: Default::default(),
+            files_by_hash: Default::default(),
+            cached_hashes: Default::default(),
+        }
+    }
+
+    /// Scans folders and watches them for changes, until stop is requested or receiver of events is dropped
+    /// Returns false when watching couldn't be started or was stopped during initial scan
+    pub fn watch(
+        &mut self,
+        stop_receiver: Option<&Receiver<()>>,
+        progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>,
+        event_sender: &Sender<WatchEvent>,
+    ) -> bool {
+        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
+
+        // Folders are watched before initial scan, so changes made during scan are not lost
+        let (notify_sender, notify_receiver) = crossbeam_channel::unbounded();
+        let mut watcher = match notify::recommended_watcher(notify_sender) {
+            Ok(t) => t,
+            Err(e) => {
+                self.text_messages
+                    .add_diagnostic(Diagnostic::error(DiagnosticKind::Other, format!("Failed to start watching folders, reason {}", e)));
+                return false;
+            }
+        };
+        let recursive_mode = if self.recursive_search { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        for directory in &self.directories.included_directories {
+            if let Err(e) = watcher.watch(directory, recursive_mode) {
+                self.text_messages
+                    .add_diagnostic(Diagnostic::error(DiagnosticKind::CannotOpenDir, format!("Failed to watch folder {}, reason {}", directory.display(), e)).path(directory));
+                return false;
+            }
+        }
+
+        if self.use_cache {
+            let volumes = Volumes::mounted_for(&self.directories.included_directories);
+            let loaded_hashes = load_hashes_from_file(&mut self.text_messages, &volumes, false, &self.hash_type, false).unwrap_or_default();
+            self.cached_hashes = loaded_hashes.into_values().flatten().map(|file_entry| (file_entry.path.clone(), file_entry)).collect();
+        }
+        let events = match self.initial_scan(stop_receiver, progress_sender) {
+            Some(t) => t,
+            None => {
+                self.stopped_search = true;
+                return false;
+            }
+        };
+        self.save_cache();
+        if !send_events(event_sender, events) {
+            return true;
+        }
+
+        let mut changed_paths: BTreeSet<PathBuf> = BTreeSet::new();
+        let mut first_change = Instant::now();
+        let mut last_change = Instant::now();
+        loop {
+            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                break;
+            }
+            match notify_receiver.recv_timeout(STOP_CHECK_INTERVAL.min(self.delay)) {
+                Ok(Ok(event)) => {
+                    if changed_paths.is_empty() {
+                        first_change = Instant::now();
+                    }
+                    last_change = Instant::now();
+                    // Some events were lost, so everything is checked again
+                    if event.need_rescan() {
+                        changed_paths.extend(self.directories.included_directories.iter().cloned());
+                    }
+                    changed_paths.extend(event.paths);
+                }
+                Ok(Err(e)) => {
+                    if !send_events(
+                        event_sender,
+                        vec![WatchEvent::Warning(Diagnostic::warning(
+                            DiagnosticKind::Other,
+                            format!("Failed to watch folders, reason {}", e),
+                        ))],
+                    ) {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            // Files are checked when they stop changing, e.g. after copying them
+            if !changed_paths.is_empty() && (last_change.elapsed() >= self.delay || first_change.elapsed() >= self.delay * MAX_DELAYS_WITHOUT_UPDATE) {
+                let events = self.update_paths(mem::take(&mut changed_paths));
+                if !send_events(event_sender, events) {
+                    break;
+                }
+            }
+        }
+
+        self.save_cache();
+        true
+    }
+
+    /// Finds all files in watched folders and returns findings of all tools, None when stopped
+    fn initial_scan(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> Option<Vec<WatchEvent>> {
+        let start_time: SystemTime = SystemTime::now();
+        let mut events = Vec::new();
+        let file_entries = self.collect_files(self.directories.included_directories.clone(), stop_receiver, progress_sender, &mut events)?;
+        self.add_files(file_entries, &mut events);
+        events.push(WatchEvent::InitialScanFinished { files: self.files.len() });
+        Common::print_time(start_time, SystemTime::now(), "initial_scan".to_string());
+        Some(events)
+    }
+
+    /// Checks again given files and folders, removed ones are also removed from results
+    pub fn update_paths(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        let mut file_entries = Vec::new();
+        for path in paths {
+            if !self.is_watched_path(&path) {
+                continue;
+            }
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    if !self.recursive_search && !self.directories.included_directories.contains(&path) {
+                        continue;
+                    }
+                    let found_entries = match self.collect_files(vec![path.clone()], None, None, &mut events) {
+                        Some(t) => t,
+                        None => continue,
+                    };
+                    let found_paths: BTreeSet<&Path> = found_entries.iter().map(|file_entry| file_entry.path.as_path()).collect();
+                    let removed_paths: Vec<PathBuf> = self.files_inside(&path).filter(|file| !found_paths.contains(file.as_path())).collect();
+                    for removed_path in removed_paths {
+                        self.remove_file(&removed_path, &mut events);
+                    }
+                    file_entries.extend(found_entries);
+                }
+                Ok(metadata) if metadata.is_file() => match self.file_entry_of(&path, &metadata) {
+                    Some(file_entry) => file_entries.push(file_entry),
+                    None => self.remove_file(&path, &mut events),
+                },
+                _ => {
+                    let removed_paths: Vec<PathBuf> = self.files_inside(&path).collect();
+                    for removed_path in removed_paths {
+                        self.remove_file(&removed_path, &mut events);
+                    }
+                }
+            }
+        }
+        self.add_files(file_entries, &mut events);
+        events
+    }
+
+    fn collect_files(
+        &self,
+        root_dirs: Vec<PathBuf>,
+        stop_receiver: Option<&Receiver<()>>,
+        progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>,
+        events: &mut Vec<WatchEvent>,
+    ) -> Option<Vec<FileEntry>> {
+        let result = DirTraversalBuilder::new()
+            .root_dirs(root_dirs)
+            .group_by(|_fe| ())
+            .stop_receiver(stop_receiver)
+            .progress_sender(progress_sender)
+            .directories(self.directories.clone())
+            .allowed_extensions(self.allowed_extensions.clone())
+            .excluded_items(self.excluded_items.clone())
+            .recursive_search(self.recursive_search)
+            .build()
+            .run();
+        match result {
+            DirTraversalResult::SuccessFiles {
+                mut grouped_file_entries,
+                warnings,
+                ..
+            } => {
+                events.extend(warnings.into_iter().map(WatchEvent::Warning));
+                Some(grouped_file_entries.remove(&()).unwrap_or_default())
+            }
+            DirTraversalResult::SuccessFolders { .. } => {
+                unreachable!()
+            }
+            DirTraversalResult::Stopped => None,
+        }
+    }
+
+    /// Path is inside included folders and neither it nor any of its parents are excluded
+    fn is_watched_path(&self, path: &Path) -> bool {
+        let included_directory = match self.directories.included_directories.iter().find(|directory| path.starts_with(directory)) {
+            Some(t) => t,
+            None => return false,
+        };
+        if !self.recursive_search && path != included_directory && path.parent() != Some(included_directory.as_path()) {
+            return false;
+        }
+        !path
+            .ancestors()
+            .take_while(|ancestor| *ancestor != included_directory.as_path())
+            .any(|ancestor| self.directories.is_excluded(ancestor) || self.excluded_items.is_excluded(ancestor))
+    }
+
+    fn file_entry_of(&self, path: &Path, metadata: &Metadata) -> Option<FileEntry> {
+        let file_name_lowercase = path.file_name()?.to_str()?.to_lowercase();
+        if !self.allowed_extensions.matches_filename(&file_name_lowercase) {
+            return None;
+        }
+        Some(FileEntry {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified_date: modified_date_of(metadata),
+            ..Default::default()
+        })
+    }
+
+    fn files_inside<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        self.files
+            .range(path.to_path_buf()..)
+            .map(|(file, _)| file)
+            .take_while(move |file| file.starts_with(path))
+            .cloned()
+    }
+
+    fn is_duplicate_candidate(&self, file_entry: &FileEntry) -> bool {
+        self.tools.contains(&WatchTool::Duplicates) && file_entry.size > 0 && (self.minimal_file_size..=self.maximal_file_size).contains(&file_entry.size)
+    }
+
+    /// Adds new or changed files, files which are already known with the same size and modification date are skipped
+    fn add_files(&mut self, file_entries: Vec<FileEntry>, events: &mut Vec<WatchEvent>) {
+        let mut new_entries = Vec::new();
+        for file_entry in file_entries {
+            match self.files.get(&file_entry.path) {
+                Some(watched) if watched.file_entry.size == file_entry.size && watched.file_entry.modified_date == file_entry.modified_date => continue,
+                Some(_) => self.remove_file(&file_entry.path, events),
+                None => {}
+            }
+            new_entries.push(file_entry);
+        }
+
+        let broken_errors: HashMap<PathBuf, String> = if self.tools.contains(&WatchTool::BrokenFiles) {
+            new_entries.par_iter().filter_map(check_broken_watched_file).collect()
+        } else {
+            HashMap::new()
+        };
+
+        for file_entry in new_entries {
+            if self.tools.contains(&WatchTool::EmptyFiles) && file_entry.size == 0 {
+                events.push(WatchEvent::EmptyFile { path: file_entry.path.clone() });
+            }
+            let broken_error = broken_errors.get(&file_entry.path).cloned();
+            if let Some(error_string) = &broken_error {
+                events.push(WatchEvent::BrokenFile {
+                    path: file_entry.path.clone(),
+                    error_string: error_string.clone(),
+                });
+            }
+            if self.is_duplicate_candidate(&file_entry) {
+                self.files_by_size.entry(file_entry.size).or_default().insert(file_entry.path.clone());
+            }
+            self.files.insert(file_entry.path.clone(), WatchedFile { file_entry, broken_error });
+        }
+
+        // Only files which have other files with the same size must be hashed
+        let files_to_hash: Vec<FileEntry> = self
+            .files_by_size
+            .values()
+            .filter(|paths| paths.len() >= 2)
+            .flatten()
+            .map(|path| &self.files[path].file_entry)
+            .filter(|file_entry| file_entry.hash.is_empty())
+            .cloned()
+            .collect();
+        let hashes: Vec<(FileEntry, Result<String, String>)> = files_to_hash
+            .into_par_iter()
+            .map_init(
+                || vec![0u8; HASH_BUFFER_SIZE],
+                |buffer, file_entry| {
+                    let hash = match self.cached_hashes.get(&file_entry.path) {
+                        Some(cached) if cached.size == file_entry.size && cached.modified_date == file_entry.modified_date => Ok(cached.hash.clone()),
+                        _ => hash_calculation(buffer, &file_entry, &self.hash_type, u64::MAX),
+                    };
+                    (file_entry, hash)
+                },
+            )
+            .collect();
+        for (file_entry, hash) in hashes {
+            match hash {
+                Ok(hash) => self.set_hash(&file_entry.path, file_entry.size, hash, events),
+                Err(e) => events.push(WatchEvent::Warning(Diagnostic::warning(DiagnosticKind::CannotReadFile, e).path(&file_entry.path))),
+            }
+        }
+    }
+
+    fn set_hash(&mut self, path: &Path, size: u64, hash: String, events: &mut Vec<WatchEvent>) {
+        if let Some(watched) = self.files.get_mut(path) {
+            watched.file_entry.hash = hash.clone();
+        }
+        let group = self.files_by_hash.entry((size, hash)).or_default();
+        group.insert(path.to_path_buf());
+        let group_paths: Vec<PathBuf> = group.iter().cloned().collect();
+        // Second file with the same content makes also first one a duplicate
+        let new_duplicates: Vec<PathBuf> = match group_paths.len() {
+            0 | 1 => Vec::new(),
+            2 => group_paths.clone(),
+            _ => vec![path.to_path_buf()],
+        };
+        events.extend(new_duplicates.into_iter().map(|path| WatchEvent::Duplicate { path, group: group_paths.clone() }));
+    }
+
+    fn remove_file(&mut self, path: &Path, events: &mut Vec<WatchEvent>) {
+        let watched = match self.files.remove(path) {
+            Some(t) => t,
+            None => return,
+        };
+        let file_entry = watched.file_entry;
+        if self.tools.contains(&WatchTool::EmptyFiles) && file_entry.size == 0 {
+            events.push(WatchEvent::Resolved {
+                tool: WatchTool::EmptyFiles,
+                path: path.to_path_buf(),
+            });
+        }
+        if watched.broken_error.is_some() {
+            events.push(WatchEvent::Resolved {
+                tool: WatchTool::BrokenFiles,
+                path: path.to_path_buf(),
+            });
+        }
+
+        if let Some(paths) = self.files_by_size.get_mut(&file_entry.size) {
+            paths.remove(path);
+            if paths.is_empty() {
+                self.files_by_size.remove(&file_entry.size);
+            }
+        }
+        if file_entry.hash.is_empty() {
+            return;
+        }
+        let key = (file_entry.size, file_entry.hash);
+        if let Some(group) = self.files_by_hash.get_mut(&key) {
+            let was_duplicate = group.len() >= 2;
+            group.remove(path);
+            if was_duplicate {
+                events.push(WatchEvent::Resolved {
+                    tool: WatchTool::Duplicates,
+                    path: path.to_path_buf(),
+                });
+                // Last file with this content has no duplicates anymore
+                if group.len() == 1 {
+                    events.extend(group.iter().map(|path| WatchEvent::Resolved {
+                        tool: WatchTool::Duplicates,
+                        path: path.clone(),
+                    }));
+                }
+            }
+            if group.is_empty() {
+                self.files_by_hash.remove(&key);
+            }
+        }
+    }
+
+    /// Saves computed hashes together with hashes loaded from cache, so entries of other folders are not lost
+    fn save_cache(&mut self) {
+        if !self.use_cache {
+            return;
+        }
+        let mut hashmap: BTreeMap<PathBuf, FileEntry> = mem::take(&mut self.cached_hashes).into_iter().collect();
+        for watched in self.files.values() {
+            if !watched.file_entry.hash.is_empty() {
+                hashmap.insert(watched.file_entry.path.clone(), watched.file_entry.clone());
+            }
+        }
+        // Volumes may be mounted or unmounted while folders are watched, so they are read again before every saving
+        let volumes = Volumes::mounted_for(&self.directories.included_directories);
+        save_hashes_to_file(&hashmap, &volumes, &mut self.text_messages, &self.hash_type, false, self.minimal_cache_file_size);
+        self.cached_hashes = hashmap.into_iter().collect();
+    }
+
+    pub fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    pub const fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    /// Applies common settings, must be used before `watch`
+    pub fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.directories.set_excluded_directory(search_settings.excluded_directories, &mut self.text_messages);
+        self.excluded_items.set_excluded_items(search_settings.excluded_items, &mut self.text_messages);
+        self.allowed_extensions.set_allowed_extensions(search_settings.allowed_extensions, &mut self.text_messages);
+        self.recursive_search = search_settings.recursive_search;
+        #[cfg(target_family = "unix")]
+        self.directories.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
+
+    pub fn set_included_directory(&mut self, included_directory: Vec<PathBuf>) -> bool {
+        self.directories.set_included_directory(included_directory, &mut self.text_messages)
+    }
+
+    /// Minimal and maximal size of files checked for duplicates
+    pub fn set_minimal_file_size(&mut self, minimal_file_size: u64) {
+        self.minimal_file_size = minimal_file_size.max(1);
+    }
+
+    pub fn set_maximal_file_size(&mut self, maximal_file_size: u64) {
+        self.maximal_file_size = maximal_file_size.max(1);
+    }
+
+    pub fn set_hash_type(&mut self, hash_type: HashType) {
+        self.hash_type = hash_type;
+    }
+
+    pub fn set_tools(&mut self, tools: Vec<WatchTool>) {
+        self.tools = tools;
+    }
+
+    /// Time without changes after which changed files are checked
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    pub fn set_use_cache(&mut self, use_cache: bool) {
+        self.use_cache = use_cache;
+    }
+
+    pub fn set_minimal_cache_file_size(&mut self, minimal_cache_file_size: u64) {
+        self.minimal_cache_file_size = minimal_cache_file_size;
+    }
+}
+
+impl Default for DirectoryWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// False when receiver of events was dropped
+fn send_events(event_sender: &Sender<WatchEvent>, events: Vec<WatchEvent>) -> bool {
+    events.into_iter().all(|event| event_sender.send(event).is_ok())
+}
+
+fn modified_date_of(metadata: &Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Error of file which type is supported by broken files finder and which cannot be opened
+fn check_broken_watched_file(file_entry: &FileEntry) -> Option<(PathBuf, String)> {
+    let type_of_file = check_extension_avaibility(&file_entry.path.file_name()?.to_string_lossy().to_lowercase());
+    if type_of_file == TypeOfFile::Unknown {
+        return None;
+    }
+    let broken_entry = check_broken_file(broken_files::FileEntry {
+        path: file_entry.path.clone(),
+        modified_date: file_entry.modified_date,
+        size: file_entry.size,
+        type_of_file,
+        error_string: String::new(),
+    })?;
+    if broken_entry.error_string.is_empty() {
+        None
+    } else {
+        Some((file_entry.path.clone(), broken_entry.error_string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::common_tool::SearchSettings;
+    use crate::common_watch::{DirectoryWatcher, WatchEvent, WatchTool};
+
+    #[test]
+    fn test_watch_updates() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("a"), "content").unwrap();
+        fs::write(path("b"), "other").unwrap();
+        fs::write(path("empty"), "").unwrap();
+        fs::write(path("broken.zip"), "not zip").unwrap();
+
+        let mut watcher = DirectoryWatcher::new();
+        watcher.set_use_cache(false);
+        watcher.set_minimal_file_size(1);
+        watcher.set_search_settings(SearchSettings {
+            included_directories: vec![dir.path().to_path_buf()],
+            ..Default::default()
+        });
+        watcher.directories.optimize_directories(true, &mut watcher.text_messages);
+        let mut events = watcher.initial_scan(None, None).unwrap();
+        events.sort_by_key(|event| format!("{:?}", event));
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], WatchEvent::BrokenFile { path: broken, .. } if *broken == path("broken.zip")));
+        assert_eq!(events[1], WatchEvent::EmptyFile { path: path("empty") });
+        assert_eq!(events[2], WatchEvent::InitialScanFinished { files: 4 });
+
+        // New copy of file makes both files duplicates
+        fs::create_dir(path("folder")).unwrap();
+        fs::write(path("folder/c"), "content").unwrap();
+        let group = vec![path("a"), path("folder/c")];
+        let events = watcher.update_paths([path("folder")]);
+        assert_eq!(
+            events,
+            vec![
+                WatchEvent::Duplicate {
+                    path: path("a"),
+                    group: group.clone()
+                },
+                WatchEvent::Duplicate { path: path("folder/c"), group }
+            ]
+        );
+        assert!(watcher.update_paths([path("folder/c")]).is_empty());
+
+        // Renaming is seen as removing old file and creating new one
+        fs::rename(path("empty"), path("empty_renamed")).unwrap();
+        let events = watcher.update_paths([path("empty"), path("empty_renamed")]);
+        assert_eq!(
+            events,
+            vec![
+                WatchEvent::Resolved {
+                    tool: WatchTool::EmptyFiles,
+                    path: path("empty")
+                },
+                WatchEvent::EmptyFile { path: path("empty_renamed") }
+            ]
+        );
+
+        fs::remove_dir_all(path("folder")).unwrap();
+        let mut events = watcher.update_paths([path("folder")]);
+        events.sort_by_key(|event| format!("{:?}", event));
+        let resolved = |path: PathBuf| WatchEvent::Resolved {
+            tool: WatchTool::Duplicates,
+            path,
+        };
+        assert_eq!(events, vec![resolved(path("a")), resolved(path("folder/c"))]);
+        assert!(watcher.update_paths([PathBuf::from("/not_watched")]).is_empty());
+    }
+}
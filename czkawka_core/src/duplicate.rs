@@ -1,5 +1,4 @@
 use std::collections::BTreeMap;
-#[cfg(target_family = "unix")]
 use std::collections::HashSet;
 use std::fs::File;
 use std::hash::Hasher;
@@ -9,33 +8,65 @@ use std::io::{BufReader, BufWriter};
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime};
-use std::{fs, mem, thread};
+use std::time::SystemTime;
+use std::{fs, mem};
 
 use crossbeam_channel::Receiver;
 use humansize::{file_size_opts as options, FileSize};
 use rayon::prelude::*;
-
-use crate::common::{open_cache_folder, Common, LOOP_DURATION};
-use crate::common_dir_traversal::{CheckingMethod, DirTraversalBuilder, DirTraversalResult, FileEntry, ProgressData};
+use serde::{Deserialize, Serialize};
+
+use crate::common::{get_cache_folder, open_cache_folder, Common};
+use crate::common_apply::ApplyAction;
+use crate::common_archive;
+use crate::common_archive::{is_archive_entry, ArchiveType, ARCHIVE_EXTENSIONS};
+use crate::common_cache::{
+    decode_cache_entry, identity_of_entry, load_volume_cache_entries, path_from_bytes, path_to_bytes, read_cache_content, read_versioned_cache_header, save_cache_entries,
+    write_versioned_cache, CacheEntries, CacheFormat, FileIdentity, MovedFiles,
+};
+use crate::common_dir_traversal::{CheckingMethod, DirTraversalBuilder, DirTraversalResult, FileEntry};
 use crate::common_directory::Directories;
+use crate::common_export::{bytes_to_hex, ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
+use crate::common_io_scheduler::IoScheduler;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_journal::Journal;
+use crate::common_keep_policy::{KeepCandidate, KeepPolicy, KeepRule};
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
+use crate::common_remove::RemovalMethod;
+use crate::common_tool::{SearchSettings, Tool};
 use crate::common_traits::*;
+use crate::common_volume::Volumes;
+use crate::duplicate_directories;
+use crate::duplicate_directories::DuplicatedDirectories;
 use crate::flc;
 use crate::localizer_core::generate_translation_hashmap;
 
 const TEMP_HARDLINK_FILE: &str = "rzeczek.rxrxrxl";
+const PREHASH_BUFFER_SIZE: usize = 1024 * 2;
+pub(crate) const HASH_BUFFER_SIZE: usize = 1024 * 16;
+
+/// Stages used when user doesn't choose other, together they read at most few MB of every file
+pub const DEFAULT_PARTIAL_HASH_STAGES: [PartialHashStage; 3] = [
+    PartialHashStage::Head(PREHASH_BUFFER_SIZE as u64),
+    PartialHashStage::Tail(64 * 1024),
+    PartialHashStage::Sampled {
+        chunks: 4,
+        chunk_size: 1024 * 1024,
+    },
+];
 
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 pub enum HashType {
     Blake3,
     Crc32,
     Xxh3,
+    Sha256,
+    Sha1,
+    Md5,
 }
 
 impl HashType {
@@ -44,6 +75,9 @@ impl HashType {
             HashType::Blake3 => Box::new(blake3::Hasher::new()),
             HashType::Crc32 => Box::new(crc32fast::Hasher::new()),
             HashType::Xxh3 => Box::new(xxhash_rust::xxh3::Xxh3::new()),
+            HashType::Sha256 => Box::new(sha2::Sha256::default()),
+            HashType::Sha1 => Box::new(sha1::Sha1::default()),
+            HashType::Md5 => Box::new(md5::Md5::default()),
         }
     }
 }
@@ -56,6 +90,93 @@ pub enum DeleteMethod {
     OneOldest,
     OneNewest,
     HardLink,
+    Symlink(SymlinkType),
+    /// Shares content of files using copy-on-write, supported only by some filesystems(e.g. Btrfs, XFS)
+    Reflink,
+}
+
+impl DeleteMethod {
+    /// Rules used to choose kept file when no custom keep rules are set
+    pub fn keep_policy(&self) -> KeepPolicy {
+        match self {
+            DeleteMethod::OneOldest | DeleteMethod::AllExceptNewest => KeepPolicy::new(vec![KeepRule::Newest]),
+            DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::HardLink | DeleteMethod::Symlink(_) | DeleteMethod::Reflink => {
+                KeepPolicy::new(vec![KeepRule::Oldest])
+            }
+            DeleteMethod::None => KeepPolicy::default(),
+        }
+    }
+}
+
+/// Part of file hashed before full hash, groups of files with different partial hash are not checked further
+#[derive(PartialEq, Eq, Clone, Debug, Copy)]
+pub enum PartialHashStage {
+    /// First bytes of file
+    Head(u64),
+    /// Last bytes of file
+    Tail(u64),
+    /// Chunks spread evenly over whole file
+    Sampled { chunks: u64, chunk_size: u64 },
+}
+
+impl PartialHashStage {
+    /// Offsets and lengths of parts of file with given size which are hashed
+    fn ranges(&self, size: u64) -> Vec<(u64, u64)> {
+        match *self {
+            PartialHashStage::Head(length) => vec![(0, length.min(size))],
+            PartialHashStage::Tail(length) => vec![(size - length.min(size), length.min(size))],
+            PartialHashStage::Sampled { chunks, chunk_size } => {
+                let chunk_size = chunk_size.min(size);
+                (1..=chunks)
+                    .map(|index| ((size / (chunks + 1) * index).saturating_sub(chunk_size / 2).min(size - chunk_size), chunk_size))
+                    .collect()
+            }
+        }
+    }
+
+    pub fn bytes_to_read(&self, size: u64) -> u64 {
+        self.ranges(size).iter().map(|(_, length)| length).sum()
+    }
+
+    /// Reading bigger part of file is slower than going straight to full hash
+    /// Stage which reads nothing(e.g. sampled with 0 chunks) would give the same hash to every file
+    fn is_worth_checking(&self, size: u64) -> bool {
+        let bytes_to_read = self.bytes_to_read(size);
+        bytes_to_read > 0 && bytes_to_read * 2 <= size
+    }
+
+    /// Every stage has its own cache file, first 2 KiB of file are saved to the same file as in older versions
+    pub fn get_cache_file_name(&self, type_of_hash: &HashType) -> String {
+        match *self {
+            PartialHashStage::Head(length) if length == PREHASH_BUFFER_SIZE as u64 => get_file_hash_name(type_of_hash, true),
+            PartialHashStage::Head(length) => format!("cache_duplicates_{:?}_prehash_head_{}.bin", type_of_hash, length),
+            PartialHashStage::Tail(length) => format!("cache_duplicates_{:?}_prehash_tail_{}.bin", type_of_hash, length),
+            PartialHashStage::Sampled { chunks, chunk_size } => format!("cache_duplicates_{:?}_prehash_sampled_{}x{}.bin", type_of_hash, chunks, chunk_size),
+        }
+    }
+}
+
+/// Checks done before removing or linking file, to be sure that it is really identical to file which is kept
+#[derive(PartialEq, Eq, Clone, Debug, Copy)]
+pub enum VerificationMethod {
+    None,
+    ByteByByte,
+    /// Independent of hash used to find duplicates, so it also catches collisions of weaker hashes like CRC32
+    Blake3,
+}
+
+impl Default for VerificationMethod {
+    fn default() -> Self {
+        VerificationMethod::None
+    }
+}
+
+/// Decides how path of file to which symbolic link points is saved
+#[derive(PartialEq, Eq, Clone, Debug, Copy)]
+pub enum SymlinkType {
+    Absolute,
+    /// Relative to folder with link, so link still works after moving whole folder tree
+    Relative,
 }
 
 #[derive(Default)]
@@ -68,6 +189,9 @@ pub struct Info {
     pub number_of_duplicated_files_by_name: usize,
     pub lost_space_by_size: u64,
     pub lost_space_by_hash: u64,
+    pub number_of_groups_by_directories: usize,
+    pub number_of_duplicated_directories: usize,
+    pub lost_space_by_directories: u64,
 }
 
 impl Info {
@@ -85,6 +209,9 @@ pub struct DuplicateFinder {
     files_with_identical_names_referenced: BTreeMap<String, (FileEntry, Vec<FileEntry>)>,    // File Size, File Entry
     files_with_identical_size_referenced: BTreeMap<u64, (FileEntry, Vec<FileEntry>)>,        // File Size, File Entry
     files_with_identical_hashes_referenced: BTreeMap<u64, Vec<(FileEntry, Vec<FileEntry>)>>, // File Size, next grouped by file size, next grouped by hash
+    duplicated_directories: Vec<DuplicatedDirectories>,
+    identical_directories: HashSet<PathBuf>, // Folders from groups of identical folders, files inside them are not shown separately
+    scanned_files: Vec<FileEntry>,           // All found files, needed only to find duplicated directories
     directories: Directories,
     allowed_extensions: Extensions,
     excluded_items: ExcludedItems,
@@ -93,7 +220,12 @@ pub struct DuplicateFinder {
     maximal_file_size: u64,
     check_method: CheckingMethod,
     delete_method: DeleteMethod,
+    keep_policy: Option<KeepPolicy>,
+    removal_method: RemovalMethod,
+    journal: Option<Journal>,
     hash_type: HashType,
+    partial_hash_stages: Vec<PartialHashStage>,
+    verification_method: VerificationMethod,
     ignore_hard_links: bool,
     dryrun: bool,
     stopped_search: bool,
@@ -104,6 +236,11 @@ pub struct DuplicateFinder {
     delete_outdated_cache: bool,
     use_reference_folders: bool,
     case_sensitive_name_comparison: bool,
+    find_duplicated_directories: bool,
+    minimal_directory_similarity: u8,
+    look_inside_archives: bool,
+    keep_scanned_files: bool,
+    io_scheduler: IoScheduler,
 }
 
 impl DuplicateFinder {
@@ -117,10 +254,16 @@ impl DuplicateFinder {
             files_with_identical_names_referenced: Default::default(),
             files_with_identical_size_referenced: Default::default(),
             files_with_identical_hashes_referenced: Default::default(),
+            duplicated_directories: Vec::new(),
+            identical_directories: HashSet::new(),
+            scanned_files: Vec::new(),
             recursive_search: true,
             allowed_extensions: Extensions::new(),
             check_method: CheckingMethod::None,
             delete_method: DeleteMethod::None,
+            keep_policy: None,
+            removal_method: RemovalMethod::Permanent,
+            journal: None,
             minimal_file_size: 8192,
             maximal_file_size: u64::MAX,
             directories: Directories::new(),
@@ -128,6 +271,8 @@ impl DuplicateFinder {
             stopped_search: false,
             ignore_hard_links: true,
             hash_type: HashType::Blake3,
+            partial_hash_stages: DEFAULT_PARTIAL_HASH_STAGES.to_vec(),
+            verification_method: VerificationMethod::None,
             dryrun: false,
             use_cache: true,
             use_prehash_cache: true,
@@ -136,6 +281,11 @@ impl DuplicateFinder {
             delete_outdated_cache: true,
             use_reference_folders: false,
             case_sensitive_name_comparison: false,
+            find_duplicated_directories: false,
+            minimal_directory_similarity: 100,
+            look_inside_archives: false,
+            keep_scanned_files: false,
+            io_scheduler: IoScheduler::new(),
         }
     }
 
@@ -236,6 +386,55 @@ impl DuplicateFinder {
         self.hash_type = hash_type;
     }
 
+    /// Stages are checked in given order before calculating full hash, empty list means that only full hash is used
+    pub fn set_partial_hash_stages(&mut self, partial_hash_stages: Vec<PartialHashStage>) {
+        self.partial_hash_stages = partial_hash_stages;
+    }
+
+    pub fn set_verification_method(&mut self, verification_method: VerificationMethod) {
+        self.verification_method = verification_method;
+    }
+
+    /// Works only with hash checking method and without reference folders
+    pub fn set_find_duplicated_directories(&mut self, find_duplicated_directories: bool) {
+        self.find_duplicated_directories = find_duplicated_directories;
+    }
+
+    /// Folders which share at least this percentage of files are reported as similar, 100 means that only identical folders are reported
+    pub fn set_minimal_directory_similarity(&mut self, minimal_directory_similarity: u8) {
+        assert!((1..=100).contains(&minimal_directory_similarity));
+        self.minimal_directory_similarity = minimal_directory_similarity;
+    }
+
+    /// Files inside zip and tar archives are compared too, but they are never removed or linked
+    /// Works only with size and hash checking methods
+    pub fn set_look_inside_archives(&mut self, look_inside_archives: bool) {
+        self.look_inside_archives = look_inside_archives;
+    }
+
+    /// Limits number of files hashed at once from every device
+    pub fn set_io_scheduler(&mut self, io_scheduler: IoScheduler) {
+        self.io_scheduler = io_scheduler;
+    }
+
+    /// Keeps all found files, also without duplicates, so they can be taken after search
+    pub(crate) fn set_keep_scanned_files(&mut self, keep_scanned_files: bool) {
+        self.keep_scanned_files = keep_scanned_files;
+    }
+
+    pub(crate) fn take_scanned_files(&mut self) -> Vec<FileEntry> {
+        mem::take(&mut self.scanned_files)
+    }
+
+    pub const fn get_duplicated_directories(&self) -> &Vec<DuplicatedDirectories> {
+        &self.duplicated_directories
+    }
+
+    /// Checks if all files from group are inside identical folders, so group doesn't need to be shown separately
+    pub fn is_group_in_duplicated_directories(&self, vector: &[FileEntry]) -> bool {
+        !self.identical_directories.is_empty() && vector.iter().all(|e| e.path.ancestors().skip(1).any(|folder| self.identical_directories.contains(folder)))
+    }
+
     pub fn set_ignore_hard_links(&mut self, ignore_hard_links: bool) {
         self.ignore_hard_links = ignore_hard_links;
     }
@@ -252,6 +451,21 @@ impl DuplicateFinder {
         self.delete_method = delete_method;
     }
 
+    /// Rules choosing file which is kept from every group and file used as reference, None uses rules matching delete method
+    pub fn set_keep_policy(&mut self, keep_policy: Option<KeepPolicy>) {
+        self.keep_policy = keep_policy;
+    }
+
+    /// Decides if removed files are deleted permanently, moved to trash or to quarantine
+    pub fn set_removal_method(&mut self, removal_method: RemovalMethod) {
+        self.removal_method = removal_method;
+    }
+
+    /// Removed files are saved to journal, so they can be restored later
+    pub fn set_journal(&mut self, journal: Option<Journal>) {
+        self.journal = journal;
+    }
+
     pub fn set_minimal_file_size(&mut self, minimal_file_size: u64) {
         self.minimal_file_size = match minimal_file_size {
             0 => 1,
@@ -331,7 +545,7 @@ impl DuplicateFinder {
                 warnings,
             } => {
                 self.files_with_identical_names = grouped_file_entries;
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages.extend_diagnostics(warnings);
 
                 // Create new BTreeMap without single size entries(files have not duplicates)
                 let mut new_map: BTreeMap<String, Vec<FileEntry>> = Default::default();
@@ -348,25 +562,10 @@ impl DuplicateFinder {
                     let mut btree_map = Default::default();
                     mem::swap(&mut self.files_with_identical_names, &mut btree_map);
                     let reference_directories = self.directories.reference_directories.clone();
+                    let keep_policy = self.keep_policy.as_ref();
                     let vec = btree_map
                         .into_iter()
-                        .filter_map(|(_size, vec_file_entry)| {
-                            let mut files_from_referenced_folders = Vec::new();
-                            let mut normal_files = Vec::new();
-                            for file_entry in vec_file_entry {
-                                if reference_directories.iter().any(|e| file_entry.path.starts_with(&e)) {
-                                    files_from_referenced_folders.push(file_entry);
-                                } else {
-                                    normal_files.push(file_entry);
-                                }
-                            }
-
-                            if files_from_referenced_folders.is_empty() || normal_files.is_empty() {
-                                None
-                            } else {
-                                Some((files_from_referenced_folders.pop().unwrap(), normal_files))
-                            }
-                        })
+                        .filter_map(|(_size, vec_file_entry)| split_reference_files(vec_file_entry, &reference_directories, keep_policy))
                         .collect::<Vec<(FileEntry, Vec<FileEntry>)>>();
                     for (fe, vec_fe) in vec {
                         self.files_with_identical_names_referenced.insert(fe.path.to_string_lossy().to_string(), (fe, vec_fe));
@@ -395,6 +594,48 @@ impl DuplicateFinder {
         }
     }
 
+    /// Adds files from found archives as virtual entries and removes archives which were found only because of them
+    fn add_archive_entries(&mut self, grouped_file_entries: &mut BTreeMap<u64, Vec<FileEntry>>) {
+        let archives: Vec<FileEntry> = grouped_file_entries
+            .values()
+            .flatten()
+            .filter(|file_entry| ArchiveType::from_path(&file_entry.path).is_some())
+            .cloned()
+            .collect();
+        let listed_archives: Vec<(FileEntry, io::Result<Vec<FileEntry>>)> = archives
+            .into_par_iter()
+            .map(|archive| {
+                let entries = common_archive::list_archive_entries(&archive);
+                (archive, entries)
+            })
+            .collect();
+
+        for (archive, entries) in listed_archives {
+            match entries {
+                Ok(entries) => {
+                    for file_entry in entries {
+                        let file_name_lowercase = file_entry.path.to_string_lossy().to_lowercase();
+                        if file_entry.size >= self.minimal_file_size && file_entry.size <= self.maximal_file_size && self.allowed_extensions.matches_filename(&file_name_lowercase)
+                        {
+                            grouped_file_entries.entry(file_entry.size).or_default().push(file_entry);
+                        }
+                    }
+                }
+                Err(e) => self.text_messages.add_diagnostic(
+                    Diagnostic::warning(DiagnosticKind::CannotReadFile, "Failed to read content of archive".to_string())
+                        .path(&archive.path)
+                        .io_error(&e)
+                        .stage(ProgressStage::CollectingFiles),
+                ),
+            }
+            if !self.allowed_extensions.matches_filename(&archive.path.to_string_lossy().to_lowercase()) {
+                if let Some(vector) = grouped_file_entries.get_mut(&archive.size) {
+                    vector.retain(|e| e.path != archive.path);
+                }
+            }
+        }
+    }
+
     /// Read file length and puts it to different boxes(each for different lengths)
     /// If in box is only 1 result, then it is removed
     fn check_files_size(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
@@ -403,6 +644,11 @@ impl DuplicateFinder {
             CheckingMethod::Hash => 2,
             _ => panic!(),
         };
+        // Archives must be found even if they don't match allowed extensions, they are removed from results later
+        let mut allowed_extensions = self.allowed_extensions.clone();
+        if self.look_inside_archives && allowed_extensions.using_custom_extensions() {
+            allowed_extensions.extend_allowed_extensions(ARCHIVE_EXTENSIONS);
+        }
         let result = DirTraversalBuilder::new()
             .root_dirs(self.directories.included_directories.clone())
             .group_by(|fe| fe.size)
@@ -411,7 +657,7 @@ impl DuplicateFinder {
             .checking_method(self.check_method)
             .max_stage(max_stage)
             .directories(self.directories.clone())
-            .allowed_extensions(self.allowed_extensions.clone())
+            .allowed_extensions(allowed_extensions)
             .excluded_items(self.excluded_items.clone())
             .recursive_search(self.recursive_search)
             .minimal_file_size(self.minimal_file_size)
@@ -421,11 +667,18 @@ impl DuplicateFinder {
         match result {
             DirTraversalResult::SuccessFiles {
                 start_time,
-                grouped_file_entries,
+                mut grouped_file_entries,
                 warnings,
             } => {
+                if self.look_inside_archives {
+                    self.add_archive_entries(&mut grouped_file_entries);
+                }
+                if self.keep_scanned_files || (self.find_duplicated_directories && self.check_method == CheckingMethod::Hash && !self.use_reference_folders) {
+                    // Folders inside archives can't be removed, so they are not compared
+                    self.scanned_files = grouped_file_entries.values().flatten().filter(|e| !is_archive_entry(&e.path)).cloned().collect();
+                }
                 self.files_with_identical_size = grouped_file_entries;
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages.extend_diagnostics(warnings);
 
                 // Create new BTreeMap without single size entries(files have not duplicates)
                 let mut old_map: BTreeMap<u64, Vec<FileEntry>> = Default::default();
@@ -443,30 +696,22 @@ impl DuplicateFinder {
                     }
                 }
 
+                // Groups without files from reference folders would be removed after hashing anyway
+                if self.use_reference_folders && self.check_method == CheckingMethod::Hash {
+                    let reference_directories = &self.directories.reference_directories;
+                    self.files_with_identical_size
+                        .retain(|_size, vector| vector.iter().any(|e| reference_directories.iter().any(|r| e.path.starts_with(r))));
+                }
+
                 // Reference - only use in size, because later hash will be counted differently
                 if self.use_reference_folders && self.check_method == CheckingMethod::Size {
                     let mut btree_map = Default::default();
                     mem::swap(&mut self.files_with_identical_size, &mut btree_map);
                     let reference_directories = self.directories.reference_directories.clone();
+                    let keep_policy = self.keep_policy.as_ref();
                     let vec = btree_map
                         .into_iter()
-                        .filter_map(|(_size, vec_file_entry)| {
-                            let mut files_from_referenced_folders = Vec::new();
-                            let mut normal_files = Vec::new();
-                            for file_entry in vec_file_entry {
-                                if reference_directories.iter().any(|e| file_entry.path.starts_with(&e)) {
-                                    files_from_referenced_folders.push(file_entry);
-                                } else {
-                                    normal_files.push(file_entry);
-                                }
-                            }
-
-                            if files_from_referenced_folders.is_empty() || normal_files.is_empty() {
-                                None
-                            } else {
-                                Some((files_from_referenced_folders.pop().unwrap(), normal_files))
-                            }
-                        })
+                        .filter_map(|(_size, vec_file_entry)| split_reference_files(vec_file_entry, &reference_directories, keep_policy))
                         .collect::<Vec<(FileEntry, Vec<FileEntry>)>>();
                     for (fe, vec_fe) in vec {
                         self.files_with_identical_size_referenced.insert(fe.size, (fe, vec_fe));
@@ -502,281 +747,126 @@ impl DuplicateFinder {
         assert_eq!(self.check_method, CheckingMethod::Hash);
 
         let check_type = Arc::new(self.hash_type);
-
-        let start_time: SystemTime = SystemTime::now();
         let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
-        let mut pre_checked_map: BTreeMap<u64, Vec<FileEntry>> = Default::default();
-
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let files_to_check = self.files_with_identical_size.values().map(|e| e.len()).sum();
-            let checking_method = self.check_method;
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method,
-                        current_stage: 1,
-                        max_stage: 2,
-                        entries_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        entries_to_check: files_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
+
+        ///////////////////////////////////////////////////////////////////////////// PARTIAL HASHING START
+        let stages_number = self.partial_hash_stages.len() as u8 + 1;
+        // Mounted filesystems are read once and used by caches of all stages
+        let volumes = if self.use_cache || self.use_prehash_cache {
+            Volumes::mounted_for(&self.directories.included_directories)
         } else {
-            thread::spawn(|| {})
+            Volumes::default()
         };
-
-        //// PROGRESS THREAD END
-
-        ///////////////////////////////////////////////////////////////////////////// PREHASHING START
-        {
-            let loaded_hash_map;
-            let mut records_already_cached: BTreeMap<u64, Vec<FileEntry>> = Default::default();
-            let mut non_cached_files_to_check: BTreeMap<u64, Vec<FileEntry>> = Default::default();
-
-            // Cache algorithm
-            // - Load data from cache
-            // - Convert from BT<u64,Vec<FileEntry>> to BT<String,FileEntry>
-            // - Save to proper values
-            if self.use_prehash_cache {
-                loaded_hash_map = match load_hashes_from_file(&mut self.text_messages, self.delete_outdated_cache, &self.hash_type, true) {
-                    Some(t) => t,
-                    None => Default::default(),
-                };
-
-                let mut loaded_hash_map2: BTreeMap<String, FileEntry> = Default::default();
-                for vec_file_entry in loaded_hash_map.values() {
-                    for file_entry in vec_file_entry {
-                        loaded_hash_map2.insert(file_entry.path.to_string_lossy().to_string(), file_entry.clone());
-                    }
-                }
-
-                #[allow(clippy::if_same_then_else)]
-                for vec_file_entry in self.files_with_identical_size.values() {
-                    for file_entry in vec_file_entry {
-                        let name = file_entry.path.to_string_lossy().to_string();
-                        if !loaded_hash_map2.contains_key(&name) {
-                            // If loaded data doesn't contains current image info
-                            non_cached_files_to_check.entry(file_entry.size).or_insert_with(Vec::new);
-                            non_cached_files_to_check.get_mut(&file_entry.size).unwrap().push(file_entry.clone());
-                        } else if file_entry.size != loaded_hash_map2.get(&name).unwrap().size || file_entry.modified_date != loaded_hash_map2.get(&name).unwrap().modified_date {
-                            // When size or modification date of image changed, then it is clear that is different image
-                            non_cached_files_to_check.entry(file_entry.size).or_insert_with(Vec::new);
-                            non_cached_files_to_check.get_mut(&file_entry.size).unwrap().push(file_entry.clone());
-                        } else {
-                            // Checking may be omitted when already there is entry with same size and modification date
-                            records_already_cached.entry(file_entry.size).or_insert_with(Vec::new);
-                            records_already_cached.get_mut(&file_entry.size).unwrap().push(file_entry.clone());
-                        }
-                    }
-                }
-            } else {
-                loaded_hash_map = Default::default();
-                mem::swap(&mut self.files_with_identical_size, &mut non_cached_files_to_check);
-            }
-
-            #[allow(clippy::type_complexity)]
-            let pre_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<String>)> = non_cached_files_to_check
-                .par_iter()
-                .map(|(size, vec_file_entry)| {
-                    let mut hashmap_with_hash: BTreeMap<String, Vec<FileEntry>> = Default::default();
-                    let mut errors: Vec<String> = Vec::new();
-                    let mut buffer = [0u8; 1024 * 2];
-
-                    atomic_file_counter.fetch_add(vec_file_entry.len(), Ordering::Relaxed);
-                    for file_entry in vec_file_entry {
-                        if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                            check_was_breaked.store(true, Ordering::Relaxed);
-                            return None;
-                        }
-                        match hash_calculation(&mut buffer, file_entry, &check_type, 0) {
-                            Ok(hash_string) => {
-                                hashmap_with_hash.entry(hash_string.clone()).or_insert_with(Vec::new);
-                                hashmap_with_hash.get_mut(hash_string.as_str()).unwrap().push(file_entry.clone());
-                            }
-                            Err(s) => errors.push(s),
-                        }
-                    }
-                    Some((*size, hashmap_with_hash, errors))
-                })
-                .while_some()
-                .collect();
-
-            // End thread which send info to gui
-            progress_thread_run.store(false, Ordering::Relaxed);
-            progress_thread_handle.join().unwrap();
-
-            // Check if user aborted search(only from GUI)
-            if check_was_breaked.load(Ordering::Relaxed) {
-                return false;
-            }
-
-            // Add data from cache
-            for (size, vec_file_entry) in &records_already_cached {
-                pre_checked_map.entry(*size).or_insert_with(Vec::new);
-                pre_checked_map.get_mut(size).unwrap().append(&mut vec_file_entry.clone());
-            }
-
-            // Check results
-            for (size, hash_map, errors) in &pre_hash_results {
-                self.text_messages.warnings.append(&mut errors.clone());
-                for vec_file_entry in hash_map.values() {
-                    if vec_file_entry.len() > 1 {
-                        pre_checked_map.entry(*size).or_insert_with(Vec::new);
-                        pre_checked_map.get_mut(size).unwrap().append(&mut vec_file_entry.clone());
-                    }
-                }
-            }
-
-            if self.use_prehash_cache {
-                // All results = records already cached + computed results
-                let mut save_cache_to_hashmap: BTreeMap<String, FileEntry> = Default::default();
-
-                for (size, vec_file_entry) in loaded_hash_map {
-                    if size >= self.minimal_prehash_cache_file_size {
-                        for file_entry in vec_file_entry {
-                            save_cache_to_hashmap.insert(file_entry.path.to_string_lossy().to_string(), file_entry.clone());
-                        }
-                    }
-                }
-
-                for (size, hash_map, _errors) in &pre_hash_results {
-                    if *size >= self.minimal_prehash_cache_file_size {
-                        for vec_file_entry in hash_map.values() {
-                            for file_entry in vec_file_entry {
-                                save_cache_to_hashmap.insert(file_entry.path.to_string_lossy().to_string(), file_entry.clone());
-                            }
-                        }
-                    }
-                }
-
-                save_hashes_to_file(&save_cache_to_hashmap, &mut self.text_messages, &self.hash_type, true, self.minimal_prehash_cache_file_size);
-            }
+        let mut candidate_groups: BTreeMap<u64, Vec<Vec<FileEntry>>> = mem::take(&mut self.files_with_identical_size)
+            .into_iter()
+            .map(|(size, vec_file_entry)| (size, vec![vec_file_entry]))
+            .collect();
+        for (index, stage) in self.partial_hash_stages.clone().into_iter().enumerate() {
+            candidate_groups = match self.check_partial_hash_stage(stage, index as u8 + 1, candidate_groups, &volumes, stop_receiver, progress_sender) {
+                Some(t) => t,
+                None => return false,
+            };
         }
+        // Full hash groups files again, so groups with same size may be merged
+        let mut pre_checked_map: BTreeMap<u64, Vec<FileEntry>> = candidate_groups.into_iter().map(|(size, groups)| (size, groups.into_iter().flatten().collect())).collect();
+        ///////////////////////////////////////////////////////////////////////////// PARTIAL HASHING END
 
-        ///////////////////////////////////////////////////////////////////////////// PREHASHING END
-
-        Common::print_time(start_time, SystemTime::now(), "check_files_hash - prehash".to_string());
         let start_time: SystemTime = SystemTime::now();
 
         /////////////////////////
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let files_to_check = pre_checked_map.iter().map(|(_size, vec_file_entry)| vec_file_entry.len()).sum();
-            let checking_method = self.check_method;
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method,
-                        current_stage: 2,
-                        max_stage: 2,
-                        entries_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        entries_to_check: files_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::CalculatingHashes, stages_number, stages_number)
+                .checking_method(self.check_method)
+                .entries_to_check(pre_checked_map.values().map(|e| e.len()).sum())
+                .bytes_to_check(pre_checked_map.iter().map(|(size, e)| size * e.len() as u64).sum()),
+        );
 
         ///////////////////////////////////////////////////////////////////////////// HASHING START
         {
             #[allow(clippy::type_complexity)]
-            let mut full_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<String>)>;
+            let mut full_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<Diagnostic>)>;
 
-            let loaded_hash_map;
+            let loaded_hash_map: BTreeMap<u64, Vec<FileEntry>>;
+            let moved_files: MovedFiles<FileEntry>;
 
             let mut records_already_cached: BTreeMap<u64, Vec<FileEntry>> = Default::default();
             let mut non_cached_files_to_check: BTreeMap<u64, Vec<FileEntry>> = Default::default();
 
             if self.use_cache {
-                loaded_hash_map = match load_hashes_from_file(&mut self.text_messages, self.delete_outdated_cache, &self.hash_type, false) {
-                    Some(t) => t,
-                    None => Default::default(),
-                };
+                let (cached_hash_map, cached_moved_files) =
+                    load_hashes_with_moved_files(&mut self.text_messages, &volumes, self.delete_outdated_cache, &get_file_hash_name(&self.hash_type, false)).unwrap_or_default();
+                loaded_hash_map = cached_hash_map;
+                moved_files = cached_moved_files;
 
                 for (size, vec_file_entry) in pre_checked_map {
-                    #[allow(clippy::collapsible_if)]
-                    if !loaded_hash_map.contains_key(&size) {
-                        // If loaded data doesn't contains current info
-                        non_cached_files_to_check.insert(size, vec_file_entry);
-                    } else {
-                        let loaded_vec_file_entry = loaded_hash_map.get(&size).unwrap();
-
-                        for file_entry in vec_file_entry {
-                            let mut found: bool = false;
-                            for loaded_file_entry in loaded_vec_file_entry {
-                                if file_entry.path == loaded_file_entry.path && file_entry.modified_date == loaded_file_entry.modified_date {
-                                    records_already_cached.entry(file_entry.size).or_insert_with(Vec::new);
-                                    records_already_cached.get_mut(&file_entry.size).unwrap().push(loaded_file_entry.clone());
-                                    found = true;
-                                    break;
-                                }
-                            }
+                    let loaded_vec_file_entry = loaded_hash_map.get(&size);
 
-                            if !found {
-                                non_cached_files_to_check.entry(file_entry.size).or_insert_with(Vec::new);
-                                non_cached_files_to_check.get_mut(&file_entry.size).unwrap().push(file_entry);
-                            }
+                    for file_entry in vec_file_entry {
+                        let cached_file_entry = loaded_vec_file_entry
+                            .and_then(|e| {
+                                e.iter()
+                                    .find(|loaded_file_entry| file_entry.path == loaded_file_entry.path && file_entry.modified_date == loaded_file_entry.modified_date)
+                            })
+                            .cloned()
+                            // File may be moved or renamed since previous scan
+                            .or_else(|| moved_files.find(&file_entry.path));
+
+                        match cached_file_entry {
+                            Some(cached_file_entry) => records_already_cached.entry(size).or_default().push(cached_file_entry),
+                            None => non_cached_files_to_check.entry(size).or_default().push(file_entry),
                         }
                     }
                 }
             } else {
                 loaded_hash_map = Default::default();
+                moved_files = Default::default();
                 mem::swap(&mut pre_checked_map, &mut non_cached_files_to_check);
             }
 
-            full_hash_results = non_cached_files_to_check
-                .into_par_iter()
-                .map(|(size, vec_file_entry)| {
-                    let mut hashmap_with_hash: BTreeMap<String, Vec<FileEntry>> = Default::default();
-                    let mut errors: Vec<String> = Vec::new();
-                    let mut buffer = [0u8; 1024 * 16];
-
-                    atomic_file_counter.fetch_add(vec_file_entry.len(), Ordering::Relaxed);
-                    for mut file_entry in vec_file_entry {
-                        if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                            check_was_breaked.store(true, Ordering::Relaxed);
-                            return None;
-                        }
-
-                        match hash_calculation(&mut buffer, &file_entry, &check_type, u64::MAX) {
-                            Ok(hash_string) => {
-                                file_entry.hash = hash_string.clone();
-                                hashmap_with_hash.entry(hash_string.clone()).or_insert_with(Vec::new);
-                                hashmap_with_hash.get_mut(hash_string.as_str()).unwrap().push(file_entry);
-                            }
-                            Err(s) => errors.push(s),
-                        }
+            let files_to_check: Vec<(u64, FileEntry)> = non_cached_files_to_check
+                .into_iter()
+                .flat_map(|(size, vec_file_entry)| vec_file_entry.into_iter().map(move |file_entry| (size, file_entry)))
+                .collect();
+            let hashed_files: Vec<(u64, FileEntry, Result<String, Diagnostic>)> = self.io_scheduler.process_by_device(
+                files_to_check,
+                |(_size, file_entry)| file_entry.path.as_path(),
+                |(_size, file_entry)| file_entry.device,
+                |(size, mut file_entry)| {
+                    if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                        check_was_breaked.store(true, Ordering::Relaxed);
+                        return None;
                     }
-                    Some((size, hashmap_with_hash, errors))
-                })
-                .while_some()
+                    progress_reporter.add_entries(1);
+                    progress_reporter.set_current_path(&file_entry.path);
+                    progress_reporter.add_bytes(size);
+
+                    let mut buffer = [0u8; HASH_BUFFER_SIZE];
+                    let result = match hash_calculation(&mut buffer, &file_entry, &check_type, u64::MAX) {
+                        Ok(hash_string) => {
+                            file_entry.hash = hash_string.clone();
+                            Ok(hash_string)
+                        }
+                        Err(s) => Err(Diagnostic::warning(DiagnosticKind::CannotReadFile, s)
+                            .path(&file_entry.path)
+                            .stage(ProgressStage::CalculatingHashes)),
+                    };
+                    Some((size, file_entry, result))
+                },
+            );
+
+            let mut results_by_size: BTreeMap<u64, (BTreeMap<String, Vec<FileEntry>>, Vec<Diagnostic>)> = Default::default();
+            for (size, file_entry, result) in hashed_files {
+                let (hashmap_with_hash, errors) = results_by_size.entry(size).or_default();
+                match result {
+                    Ok(hash_string) => hashmap_with_hash.entry(hash_string).or_default().push(file_entry),
+                    Err(diagnostic) => errors.push(diagnostic),
+                }
+            }
+            full_hash_results = results_by_size
+                .into_iter()
+                .map(|(size, (hashmap_with_hash, errors))| (size, hashmap_with_hash, errors))
                 .collect();
 
             if self.use_cache {
@@ -801,33 +891,38 @@ impl DuplicateFinder {
                 }
 
                 // Must save all results to file, old loaded from file with all currently counted results
-                let mut all_results: BTreeMap<String, FileEntry> = Default::default();
+                let mut all_results: BTreeMap<PathBuf, FileEntry> = Default::default();
                 for (_size, vec_file_entry) in loaded_hash_map {
                     for file_entry in vec_file_entry {
-                        all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
+                        all_results.insert(file_entry.path.clone(), file_entry);
                     }
                 }
                 for (_size, hashmap, _errors) in &full_hash_results {
                     for vec_file_entry in hashmap.values() {
                         for file_entry in vec_file_entry {
-                            all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry.clone());
+                            all_results.insert(file_entry.path.clone(), file_entry.clone());
                         }
                     }
                 }
-                save_hashes_to_file(&all_results, &mut self.text_messages, &self.hash_type, false, self.minimal_cache_file_size);
+                save_hashes_to_cache_file(
+                    &all_results,
+                    Some(&moved_files),
+                    &volumes,
+                    &mut self.text_messages,
+                    &get_file_hash_name(&self.hash_type, false),
+                    self.minimal_cache_file_size,
+                );
             }
 
-            // End thread which send info to gui
-            progress_thread_run.store(false, Ordering::Relaxed);
-            progress_thread_handle.join().unwrap();
+            progress_reporter.end();
 
             // Check if user aborted search(only from GUI)
             if check_was_breaked.load(Ordering::Relaxed) {
                 return false;
             }
 
-            for (size, hash_map, mut errors) in full_hash_results {
-                self.text_messages.warnings.append(&mut errors);
+            for (size, hash_map, errors) in full_hash_results {
+                self.text_messages.extend_diagnostics(errors);
                 for (_hash, vec_file_entry) in hash_map {
                     if vec_file_entry.len() > 1 {
                         self.files_with_identical_hashes.entry(size).or_insert_with(Vec::new);
@@ -839,32 +934,23 @@ impl DuplicateFinder {
 
         ///////////////////////////////////////////////////////////////////////////// HASHING END
 
+        if self.find_duplicated_directories && !self.use_reference_folders {
+            self.check_duplicated_directories();
+        }
+
         // Reference - only use in size, because later hash will be counted differently
         if self.use_reference_folders {
             let mut btree_map = Default::default();
             mem::swap(&mut self.files_with_identical_hashes, &mut btree_map);
             let reference_directories = self.directories.reference_directories.clone();
+            let keep_policy = self.keep_policy.as_ref();
             let vec = btree_map
                 .into_iter()
                 .filter_map(|(_size, vec_vec_file_entry)| {
-                    let mut all_results_with_same_size = Vec::new();
-                    for vec_file_entry in vec_vec_file_entry {
-                        let mut files_from_referenced_folders = Vec::new();
-                        let mut normal_files = Vec::new();
-                        for file_entry in vec_file_entry {
-                            if reference_directories.iter().any(|e| file_entry.path.starts_with(&e)) {
-                                files_from_referenced_folders.push(file_entry);
-                            } else {
-                                normal_files.push(file_entry);
-                            }
-                        }
-
-                        if files_from_referenced_folders.is_empty() || normal_files.is_empty() {
-                            continue;
-                        } else {
-                            all_results_with_same_size.push((files_from_referenced_folders.pop().unwrap(), normal_files))
-                        }
-                    }
+                    let all_results_with_same_size: Vec<(FileEntry, Vec<FileEntry>)> = vec_vec_file_entry
+                        .into_iter()
+                        .filter_map(|vec_file_entry| split_reference_files(vec_file_entry, &reference_directories, keep_policy))
+                        .collect();
                     if all_results_with_same_size.is_empty() {
                         None
                     } else {
@@ -903,6 +989,204 @@ impl DuplicateFinder {
         true
     }
 
+    /// Groups of files inside identical folders are skipped later, so number of hidden groups is printed here
+    fn write_duplicated_directories<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.duplicated_directories.is_empty() {
+            return Ok(());
+        }
+        writeln!(
+            writer,
+            "Found {} groups of duplicated folders, {} identical folders takes {}.",
+            self.information.number_of_groups_by_directories,
+            self.information.number_of_duplicated_directories,
+            self.information.lost_space_by_directories.file_size(options::BINARY).unwrap()
+        )?;
+        for group in &self.duplicated_directories {
+            let first = &group.directories[0];
+            match group.similarity {
+                None => writeln!(
+                    writer,
+                    "\n---- Identical folders - {} files, {} ({}) each",
+                    first.number_of_files,
+                    first.size.file_size(options::BINARY).unwrap(),
+                    first.size
+                )?,
+                Some(similarity) => writeln!(writer, "\n---- Similar folders - {}% of files are duplicated", similarity)?,
+            }
+            for directory in &group.directories {
+                writeln!(writer, "{}", directory.path.display())?;
+            }
+        }
+        let hidden_groups = self
+            .files_with_identical_hashes
+            .values()
+            .flatten()
+            .filter(|e| self.is_group_in_duplicated_directories(e))
+            .count();
+        writeln!(writer, "\n{} groups of files inside identical folders are not shown below.", hidden_groups)?;
+        Ok(())
+    }
+
+    /// Aggregates groups of files with same hash into identical(and optionally similar) folders
+    fn check_duplicated_directories(&mut self) {
+        let start_time: SystemTime = SystemTime::now();
+        let scanned_files = mem::take(&mut self.scanned_files);
+        let duplicate_groups: Vec<&Vec<FileEntry>> = self.files_with_identical_hashes.values().flatten().collect();
+        self.duplicated_directories =
+            duplicate_directories::find_duplicated_directories(&scanned_files, &duplicate_groups, &self.directories.included_directories, self.minimal_directory_similarity);
+
+        for group in &self.duplicated_directories {
+            self.information.number_of_groups_by_directories += 1;
+            if group.similarity.is_none() {
+                self.information.number_of_duplicated_directories += group.directories.len() - 1;
+                self.information.lost_space_by_directories += (group.directories.len() as u64 - 1) * group.directories[0].size;
+                self.identical_directories.extend(group.directories.iter().map(|e| e.path.clone()));
+            }
+        }
+
+        Common::print_time(start_time, SystemTime::now(), "check_duplicated_directories".to_string());
+    }
+
+    /// Splits every group of files by hash of part of file chosen by stage, groups with only one file are dropped
+    /// Returns None when search was stopped
+    #[allow(clippy::type_complexity)]
+    fn check_partial_hash_stage(
+        &mut self,
+        stage: PartialHashStage,
+        stage_number: u8,
+        groups: BTreeMap<u64, Vec<Vec<FileEntry>>>,
+        volumes: &Volumes,
+        stop_receiver: Option<&Receiver<()>>,
+        progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>,
+    ) -> Option<BTreeMap<u64, Vec<Vec<FileEntry>>>> {
+        let start_time: SystemTime = SystemTime::now();
+        let check_type = self.hash_type;
+        let check_was_breaked = AtomicBool::new(false);
+        let stages_number = self.partial_hash_stages.len() as u8 + 1;
+
+        let (groups_to_check, mut checked_groups): (BTreeMap<u64, Vec<Vec<FileEntry>>>, BTreeMap<u64, Vec<Vec<FileEntry>>>) =
+            groups.into_iter().partition(|(size, _)| stage.is_worth_checking(*size));
+
+        let cache_file_name = stage.get_cache_file_name(&self.hash_type);
+        let (loaded_hash_map, moved_files): (BTreeMap<PathBuf, FileEntry>, MovedFiles<FileEntry>) = if self.use_prehash_cache {
+            let (loaded_hash_map, moved_files) = load_hashes_with_moved_files(&mut self.text_messages, volumes, self.delete_outdated_cache, &cache_file_name).unwrap_or_default();
+            let loaded_hash_map = loaded_hash_map.into_values().flatten().map(|file_entry| (file_entry.path.clone(), file_entry)).collect();
+            (loaded_hash_map, moved_files)
+        } else {
+            Default::default()
+        };
+
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::CalculatingPrehashes, stage_number, stages_number)
+                .checking_method(self.check_method)
+                .entries_to_check(groups_to_check.values().flatten().map(|e| e.len()).sum())
+                .bytes_to_check(
+                    groups_to_check
+                        .iter()
+                        .map(|(size, groups)| stage.bytes_to_read(*size) * groups.iter().map(|e| e.len() as u64).sum::<u64>())
+                        .sum(),
+                ),
+        );
+
+        // Files are hashed one by one, so files from one group can be read from different devices at the same time
+        let files_to_check: Vec<(u64, usize, FileEntry)> = groups_to_check
+            .into_iter()
+            .flat_map(|(size, groups)| {
+                groups
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(move |(group_index, group)| group.into_iter().map(move |file_entry| (size, group_index, file_entry)))
+            })
+            .collect();
+
+        // Size, index of group, file, partial hash and information if it must be saved in cache under current path or error
+        #[allow(clippy::type_complexity)]
+        let partial_hash_results: Vec<(u64, usize, FileEntry, Result<(String, bool), Diagnostic>)> = self.io_scheduler.process_by_device(
+            files_to_check,
+            |(_size, _group_index, file_entry)| file_entry.path.as_path(),
+            |(_size, _group_index, file_entry)| file_entry.device,
+            |(size, group_index, file_entry)| {
+                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                    check_was_breaked.store(true, Ordering::Relaxed);
+                    return None;
+                }
+                progress_reporter.add_entries(1);
+                progress_reporter.set_current_path(&file_entry.path);
+                progress_reporter.add_bytes(stage.bytes_to_read(size));
+
+                let cached_hash = loaded_hash_map
+                    .get(&file_entry.path)
+                    .filter(|e| e.size == file_entry.size && e.modified_date == file_entry.modified_date)
+                    .map(|e| (e.hash.clone(), false))
+                    // Hash of moved or renamed file is reused, but must be saved again under new path
+                    .or_else(|| moved_files.find(&file_entry.path).map(|e| (e.hash, true)));
+                let result = match cached_hash {
+                    Some(cached_hash) => Ok(cached_hash),
+                    None => {
+                        let mut buffer = [0u8; HASH_BUFFER_SIZE];
+                        partial_hash_calculation(&mut buffer, &file_entry, &check_type, &stage)
+                            .map(|hash| (hash, true))
+                            .map_err(|s| {
+                                Diagnostic::warning(DiagnosticKind::CannotReadFile, s)
+                                    .path(&file_entry.path)
+                                    .stage(ProgressStage::CalculatingPrehashes)
+                            })
+                    }
+                };
+                Some((size, group_index, file_entry, result))
+            },
+        );
+
+        progress_reporter.end();
+
+        // Check if user aborted search(only from GUI)
+        if check_was_breaked.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let mut hashed_files: Vec<FileEntry> = Vec::new();
+        let mut groups_by_hash: BTreeMap<(u64, usize), BTreeMap<String, Vec<FileEntry>>> = BTreeMap::new();
+        for (size, group_index, file_entry, result) in partial_hash_results {
+            match result {
+                Ok((hash, newly_hashed)) => {
+                    if newly_hashed {
+                        hashed_files.push(FileEntry {
+                            hash: hash.clone(),
+                            ..file_entry.clone()
+                        });
+                    }
+                    groups_by_hash.entry((size, group_index)).or_default().entry(hash).or_default().push(file_entry);
+                }
+                Err(diagnostic) => self.text_messages.add_diagnostic(diagnostic),
+            }
+        }
+
+        if self.use_prehash_cache {
+            let mut save_cache_to_hashmap = loaded_hash_map;
+            for file_entry in hashed_files {
+                save_cache_to_hashmap.insert(file_entry.path.clone(), file_entry);
+            }
+            save_hashes_to_cache_file(
+                &save_cache_to_hashmap,
+                Some(&moved_files),
+                volumes,
+                &mut self.text_messages,
+                &cache_file_name,
+                self.minimal_prehash_cache_file_size,
+            );
+        }
+
+        for ((size, _group_index), hashmap_with_hash) in groups_by_hash {
+            let new_groups = hashmap_with_hash.into_values().filter(|e| e.len() > 1);
+            checked_groups.entry(size).or_default().extend(new_groups);
+        }
+        checked_groups.retain(|_size, groups| !groups.is_empty());
+
+        Common::print_time(start_time, SystemTime::now(), format!("check_files_hash - partial hash stage {:?}", stage));
+        Some(checked_groups)
+    }
+
     /// Function to delete files, from filed before BTreeMap
     /// Using another function to delete files to avoid duplicates data
     fn delete_files(&mut self) {
@@ -910,23 +1194,32 @@ impl DuplicateFinder {
         if self.delete_method == DeleteMethod::None {
             return;
         }
+        let keep_policy = self.keep_policy.clone().unwrap_or_else(|| self.delete_method.keep_policy());
+        let options = DeleteOptions {
+            delete_method: &self.delete_method,
+            keep_policy: &keep_policy,
+            removal_method: &self.removal_method,
+            verification_method: self.verification_method,
+            journal: self.journal.as_ref(),
+            dryrun: self.dryrun,
+        };
 
         match self.check_method {
             CheckingMethod::Name => {
                 for vector in self.files_with_identical_names.values() {
-                    let _tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &mut self.text_messages, self.dryrun);
+                    let _tuple: (u64, usize, usize) = delete_files(vector, &options, &mut self.text_messages);
                 }
             }
             CheckingMethod::Hash => {
                 for vector_vectors in self.files_with_identical_hashes.values() {
                     for vector in vector_vectors.iter() {
-                        let _tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &mut self.text_messages, self.dryrun);
+                        let _tuple: (u64, usize, usize) = delete_files(vector, &options, &mut self.text_messages);
                     }
                 }
             }
             CheckingMethod::Size => {
                 for vector in self.files_with_identical_size.values() {
-                    let _tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &mut self.text_messages, self.dryrun);
+                    let _tuple: (u64, usize, usize) = delete_files(vector, &options, &mut self.text_messages);
                 }
             }
             CheckingMethod::None => {
@@ -945,35 +1238,154 @@ impl Default for DuplicateFinder {
     }
 }
 
-impl DebugPrint for DuplicateFinder {
-    #[allow(dead_code)]
-    #[allow(unreachable_code)]
-    /// Debugging printing - only available on debug build
-    fn debug_print(&self) {
-        #[cfg(not(debug_assertions))]
-        {
-            return;
-        }
-        println!("---------------DEBUG PRINT---------------");
-        println!("### Information's");
+impl Tool for DuplicateFinder {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_reference_directory(search_settings.reference_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
 
-        println!("Errors size - {}", self.text_messages.errors.len());
-        println!("Warnings size - {}", self.text_messages.warnings.len());
-        println!("Messages size - {}", self.text_messages.messages.len());
-        println!(
-            "Number of duplicated files by size(in groups) - {} ({})",
-            self.information.number_of_duplicated_files_by_size, self.information.number_of_groups_by_size
-        );
-        println!(
-            "Number of duplicated files by hash(in groups) - {} ({})",
-            self.information.number_of_duplicated_files_by_hash, self.information.number_of_groups_by_hash
-        );
-        println!(
-            "Number of duplicated files by name(in groups) - {} ({})",
-            self.information.number_of_duplicated_files_by_name, self.information.number_of_groups_by_name
-        );
-        println!(
-            "Lost space by size - {} ({} bytes)",
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_duplicates(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("duplicate", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        self.check_method = match results.checking_method.as_deref() {
+            Some("name") => CheckingMethod::Name,
+            Some("size") => CheckingMethod::Size,
+            Some("hash") => CheckingMethod::Hash,
+            _ => {
+                self.text_messages.add_diagnostic(Diagnostic::error(
+                    DiagnosticKind::InvalidFile,
+                    "Loaded results don't contain valid checking method".to_string(),
+                ));
+                return;
+            }
+        };
+        let to_file_entry = |entry: ExportedEntry| FileEntry {
+            path: entry.path,
+            size: entry.size,
+            modified_date: entry.modified_date,
+            hash: entry.hash.unwrap_or_default(),
+            symlink_info: None,
+            device: None,
+        };
+
+        self.use_reference_folders = results.has_reference_groups();
+        for (reference, group) in results.into_split_groups() {
+            let group: Vec<FileEntry> = group.into_iter().map(to_file_entry).collect();
+            if self.use_reference_folders {
+                let reference = match reference {
+                    Some(reference) => to_file_entry(reference),
+                    None => continue,
+                };
+                let size = reference.size;
+                match self.check_method {
+                    CheckingMethod::Name => {
+                        self.information.number_of_duplicated_files_by_name += group.len();
+                        self.information.number_of_groups_by_name += 1;
+                        self.files_with_identical_names_referenced
+                            .insert(reference.path.to_string_lossy().to_string(), (reference, group));
+                    }
+                    CheckingMethod::Size => {
+                        self.information.number_of_duplicated_files_by_size += group.len();
+                        self.information.number_of_groups_by_size += 1;
+                        self.information.lost_space_by_size += (group.len() as u64) * size;
+                        self.files_with_identical_size_referenced.insert(size, (reference, group));
+                    }
+                    CheckingMethod::Hash => {
+                        self.information.number_of_duplicated_files_by_hash += group.len();
+                        self.information.number_of_groups_by_hash += 1;
+                        self.information.lost_space_by_hash += (group.len() as u64) * size;
+                        self.files_with_identical_hashes_referenced.entry(size).or_default().push((reference, group));
+                    }
+                    CheckingMethod::None => unreachable!(),
+                }
+            } else if reference.is_none() {
+                let size = group[0].size;
+                match self.check_method {
+                    CheckingMethod::Name => {
+                        self.information.number_of_duplicated_files_by_name += group.len() - 1;
+                        self.information.number_of_groups_by_name += 1;
+                        let name = group[0].path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                        self.files_with_identical_names.insert(name, group);
+                    }
+                    CheckingMethod::Size => {
+                        self.information.number_of_duplicated_files_by_size += group.len() - 1;
+                        self.information.number_of_groups_by_size += 1;
+                        self.information.lost_space_by_size += (group.len() as u64 - 1) * size;
+                        self.files_with_identical_size.insert(size, group);
+                    }
+                    CheckingMethod::Hash => {
+                        self.information.number_of_duplicated_files_by_hash += group.len() - 1;
+                        self.information.number_of_groups_by_hash += 1;
+                        self.information.lost_space_by_hash += (group.len() as u64 - 1) * size;
+                        self.files_with_identical_hashes.entry(size).or_default().push(group);
+                    }
+                    CheckingMethod::None => unreachable!(),
+                }
+            }
+        }
+        self.delete_files();
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        match self.check_method {
+            CheckingMethod::Name => self.information.number_of_duplicated_files_by_name,
+            CheckingMethod::Size => self.information.number_of_duplicated_files_by_size,
+            CheckingMethod::Hash => self.information.number_of_duplicated_files_by_hash,
+            CheckingMethod::None => 0,
+        }
+    }
+}
+
+impl DebugPrint for DuplicateFinder {
+    #[allow(dead_code)]
+    #[allow(unreachable_code)]
+    /// Debugging printing - only available on debug build
+    fn debug_print(&self) {
+        #[cfg(not(debug_assertions))]
+        {
+            return;
+        }
+        println!("---------------DEBUG PRINT---------------");
+        println!("### Information's");
+
+        println!("Errors size - {}", self.text_messages.errors.len());
+        println!("Warnings size - {}", self.text_messages.warnings.len());
+        println!("Messages size - {}", self.text_messages.messages.len());
+        println!(
+            "Number of duplicated files by size(in groups) - {} ({})",
+            self.information.number_of_duplicated_files_by_size, self.information.number_of_groups_by_size
+        );
+        println!(
+            "Number of duplicated files by hash(in groups) - {} ({})",
+            self.information.number_of_duplicated_files_by_hash, self.information.number_of_groups_by_hash
+        );
+        println!(
+            "Number of duplicated files by name(in groups) - {} ({})",
+            self.information.number_of_duplicated_files_by_name, self.information.number_of_groups_by_name
+        );
+        println!(
+            "Lost space by size - {} ({} bytes)",
             self.information.lost_space_by_size.file_size(options::BINARY).unwrap(),
             self.information.lost_space_by_size
         );
@@ -1011,7 +1423,11 @@ impl SaveResults for DuplicateFinder {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -1022,7 +1438,11 @@ impl SaveResults for DuplicateFinder {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
         match self.check_method {
@@ -1090,8 +1510,9 @@ impl SaveResults for DuplicateFinder {
                         self.information.lost_space_by_hash.file_size(options::BINARY).unwrap()
                     )
                     .unwrap();
+                    self.write_duplicated_directories(&mut writer).unwrap();
                     for (size, vectors_vector) in self.files_with_identical_hashes.iter().rev() {
-                        for vector in vectors_vector {
+                        for vector in vectors_vector.iter().filter(|vector| !self.is_group_in_duplicated_directories(vector)) {
                             writeln!(writer, "\n---- Size {} ({}) - {} files", size.file_size(options::BINARY).unwrap(), size, vector.len()).unwrap();
                             for file_entry in vector {
                                 writeln!(writer, "{}", file_entry.path.display()).unwrap();
@@ -1111,6 +1532,57 @@ impl SaveResults for DuplicateFinder {
     }
 }
 
+impl ExportResults for DuplicateFinder {
+    fn get_exported_results(&self) -> ExportedResults {
+        let to_exported = |file_entry: &FileEntry| {
+            let entry = ExportedEntry::new(&file_entry.path, file_entry.size, file_entry.modified_date);
+            if file_entry.hash.is_empty() {
+                entry
+            } else {
+                entry.hash(file_entry.hash.clone())
+            }
+        };
+        let mut results = ExportedResults::new("duplicate", true).checking_method(&format!("{:?}", self.check_method).to_lowercase());
+        match self.check_method {
+            CheckingMethod::Name => {
+                if self.use_reference_folders {
+                    for (reference, vector) in self.files_with_identical_names_referenced.values() {
+                        results.add_referenced_group(to_exported(reference), vector.iter().map(to_exported).collect());
+                    }
+                } else {
+                    for vector in self.files_with_identical_names.values() {
+                        results.add_group(vector.iter().map(to_exported).collect());
+                    }
+                }
+            }
+            CheckingMethod::Size => {
+                if self.use_reference_folders {
+                    for (reference, vector) in self.files_with_identical_size_referenced.values().rev() {
+                        results.add_referenced_group(to_exported(reference), vector.iter().map(to_exported).collect());
+                    }
+                } else {
+                    for vector in self.files_with_identical_size.values().rev() {
+                        results.add_group(vector.iter().map(to_exported).collect());
+                    }
+                }
+            }
+            CheckingMethod::Hash => {
+                if self.use_reference_folders {
+                    for (reference, vector) in self.files_with_identical_hashes_referenced.values().rev().flatten() {
+                        results.add_referenced_group(to_exported(reference), vector.iter().map(to_exported).collect());
+                    }
+                } else {
+                    for vector in self.files_with_identical_hashes.values().rev().flatten() {
+                        results.add_group(vector.iter().map(to_exported).collect());
+                    }
+                }
+            }
+            CheckingMethod::None => {}
+        }
+        results
+    }
+}
+
 impl PrintResults for DuplicateFinder {
     /// Print information's about duplicated entries
     /// Only needed for CLI
@@ -1147,8 +1619,9 @@ impl PrintResults for DuplicateFinder {
                     number_of_groups,
                     self.information.lost_space_by_size.file_size(options::BINARY).unwrap()
                 );
+                self.write_duplicated_directories(&mut io::stdout().lock()).unwrap();
                 for (size, vector) in self.files_with_identical_hashes.iter().rev() {
-                    for j in vector {
+                    for j in vector.iter().filter(|j| !self.is_group_in_duplicated_directories(j)) {
                         println!("Size - {} ({}) - {} files ", size.file_size(options::BINARY).unwrap(), size, j.len());
                         for k in j {
                             println!("{}", k.path.display());
@@ -1185,23 +1658,51 @@ impl PrintResults for DuplicateFinder {
     }
 }
 
+/// Settings of `delete_files` which are same for every group of duplicates
+struct DeleteOptions<'a> {
+    delete_method: &'a DeleteMethod,
+    keep_policy: &'a KeepPolicy,
+    removal_method: &'a RemovalMethod,
+    verification_method: VerificationMethod,
+    journal: Option<&'a Journal>,
+    dryrun: bool,
+}
+
 /// Functions to remove slice(vector) of files with provided method
 /// Returns size of removed elements, number of deleted and failed to delete files and modified warning list
-fn delete_files(vector: &[FileEntry], delete_method: &DeleteMethod, text_messages: &mut Messages, dryrun: bool) -> (u64, usize, usize) {
+fn delete_files(vector: &[FileEntry], options: &DeleteOptions, text_messages: &mut Messages) -> (u64, usize, usize) {
+    let DeleteOptions {
+        delete_method,
+        keep_policy,
+        removal_method,
+        verification_method,
+        journal,
+        dryrun,
+    } = *options;
     assert!(vector.len() > 1, "Vector length must be bigger than 1(This should be done in previous steps).");
     let mut gained_space: u64 = 0;
     let mut removed_files: usize = 0;
     let mut failed_to_remove_files: usize = 0;
-    let mut values = vector.iter().enumerate();
-    let q_index = match delete_method {
-        DeleteMethod::OneOldest | DeleteMethod::AllExceptNewest => values.max_by(|(_, l), (_, r)| l.modified_date.cmp(&r.modified_date)),
-        DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::HardLink => values.min_by(|(_, l), (_, r)| l.modified_date.cmp(&r.modified_date)),
-        DeleteMethod::None => values.next(),
+    let q_index = match keep_policy.file_to_keep(&vector.iter().map(KeepCandidate::from).collect::<Vec<_>>()) {
+        Some(q_index) => q_index,
+        None => {
+            text_messages
+                .messages
+                .push(format!("Skipped group with {} files, because none of them matches keep rules", vector.len()));
+            return (0, 0, 0);
+        }
     };
-    let q_index = q_index.map(|t| t.0).unwrap_or(0);
     let n = match delete_method {
         DeleteMethod::OneNewest | DeleteMethod::OneOldest => 1,
-        DeleteMethod::AllExceptNewest | DeleteMethod::AllExceptOldest | DeleteMethod::None | DeleteMethod::HardLink => usize::MAX,
+        DeleteMethod::AllExceptNewest | DeleteMethod::AllExceptOldest | DeleteMethod::None | DeleteMethod::HardLink | DeleteMethod::Symlink(_) | DeleteMethod::Reflink => {
+            usize::MAX
+        }
+    };
+    let action = match delete_method {
+        DeleteMethod::HardLink => ApplyAction::HardLink,
+        DeleteMethod::Symlink(symlink_type) => ApplyAction::Symlink(*symlink_type),
+        DeleteMethod::Reflink => ApplyAction::Reflink,
+        _ => ApplyAction::Remove(removal_method.clone()),
     };
     for (index, file) in vector.iter().enumerate() {
         if q_index == index {
@@ -1209,37 +1710,66 @@ fn delete_files(vector: &[FileEntry], delete_method: &DeleteMethod, text_message
         } else if removed_files + failed_to_remove_files >= n {
             break;
         }
+        // Files inside archives are only compared, they are never removed or replaced with links
+        if is_archive_entry(&file.path) {
+            continue;
+        }
 
-        let r = match delete_method {
-            DeleteMethod::OneOldest | DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::AllExceptNewest => {
-                if dryrun {
-                    Ok(Some(format!("Delete {}", file.path.display())))
-                } else {
-                    fs::remove_file(&file.path).map(|_| None)
+        if *delete_method != DeleteMethod::None && verification_method != VerificationMethod::None {
+            let kept_file = &vector[q_index].path;
+            match files_are_identical(kept_file, &file.path, verification_method) {
+                Ok(true) => {}
+                Ok(false) => {
+                    failed_to_remove_files += 1;
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::HashCollision,
+                            format!(
+                                "Content of {} differs from {} despite same hash(possible hash collision), so it was not processed",
+                                file.path.display(),
+                                kept_file.display()
+                            ),
+                        )
+                        .path(&file.path),
+                    );
+                    continue;
                 }
-            }
-            DeleteMethod::HardLink => {
-                let src = &vector[q_index].path;
-                if dryrun {
-                    Ok(Some(format!("Replace file {} with hard link to {}", file.path.display(), src.display())))
-                } else {
-                    make_hard_link(src, &file.path).map(|_| None)
+                Err(e) => {
+                    failed_to_remove_files += 1;
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::CannotReadFile,
+                            format!("Failed to verify {} against {}, so it was not processed ({})", file.path.display(), kept_file.display(), e),
+                        )
+                        .path(&file.path)
+                        .io_error(&e),
+                    );
+                    continue;
                 }
             }
+        }
+
+        let hash = Some(file.hash.as_str()).filter(|e| !e.is_empty());
+        let r = match delete_method {
             DeleteMethod::None => Ok(None),
+            _ if dryrun => {
+                text_messages.messages.push(action.describe(&file.path, Some(&vector[q_index].path)));
+                Ok(None)
+            }
+            _ => action.perform(&file.path, hash, Some(&vector[q_index].path), journal),
         };
 
         match r {
             Err(e) => {
                 failed_to_remove_files += 1;
-                text_messages.warnings.push(format!("Failed to remove {} ({})", file.path.display(), e));
-            }
-            Ok(Some(msg)) => {
-                text_messages.messages.push(msg);
-                removed_files += 1;
-                gained_space += file.size;
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(action.diagnostic_kind(), format!("Failed to process {} ({})", file.path.display(), e))
+                        .path(&file.path)
+                        .io_error(&e),
+                );
             }
-            Ok(None) => {
+            Ok(journal_warning) => {
+                text_messages.extend_diagnostics(journal_warning);
                 removed_files += 1;
                 gained_space += file.size;
             }
@@ -1248,6 +1778,72 @@ fn delete_files(vector: &[FileEntry], delete_method: &DeleteMethod, text_message
     (gained_space, removed_files, failed_to_remove_files)
 }
 
+/// Splits group into file from reference folders, chosen by keep rules or last found, and files outside them
+/// Groups without files in reference folders or without normal files are skipped
+fn split_reference_files(vec_file_entry: Vec<FileEntry>, reference_directories: &[PathBuf], keep_policy: Option<&KeepPolicy>) -> Option<(FileEntry, Vec<FileEntry>)> {
+    let (mut files_from_referenced_folders, normal_files): (Vec<FileEntry>, Vec<FileEntry>) = vec_file_entry
+        .into_iter()
+        .partition(|file_entry| reference_directories.iter().any(|e| file_entry.path.starts_with(e)));
+    if files_from_referenced_folders.is_empty() || normal_files.is_empty() {
+        return None;
+    }
+    let index = match keep_policy {
+        Some(keep_policy) => keep_policy.file_to_keep(&files_from_referenced_folders.iter().map(KeepCandidate::from).collect::<Vec<_>>())?,
+        None => files_from_referenced_folders.len() - 1,
+    };
+    Some((files_from_referenced_folders.swap_remove(index), normal_files))
+}
+
+/// Compares content of files with chosen method, files with different size are never identical
+/// Files inside archives are also supported, but their size is known only after reading them
+pub fn files_are_identical(first: &Path, second: &Path, verification_method: VerificationMethod) -> io::Result<bool> {
+    if !is_archive_entry(first) && !is_archive_entry(second) && fs::metadata(first)?.len() != fs::metadata(second)?.len() {
+        return Ok(false);
+    }
+    match verification_method {
+        VerificationMethod::None => Ok(true),
+        VerificationMethod::ByteByByte => common_archive::open_file(first, |first_file| {
+            common_archive::open_file(second, |second_file| {
+                let (mut first_buffer, mut second_buffer) = ([0u8; HASH_BUFFER_SIZE], [0u8; HASH_BUFFER_SIZE]);
+                loop {
+                    let first_read = read_to_fill(first_file, &mut first_buffer)?;
+                    let second_read = read_to_fill(second_file, &mut second_buffer)?;
+                    if first_buffer[..first_read] != second_buffer[..second_read] {
+                        return Ok(false);
+                    }
+                    if first_read == 0 {
+                        return Ok(true);
+                    }
+                }
+            })
+        }),
+        VerificationMethod::Blake3 => {
+            let blake3_hash = |path: &Path| -> io::Result<blake3::Hash> {
+                common_archive::open_file(path, |file| {
+                    let mut hasher = blake3::Hasher::new();
+                    io::copy(file, &mut hasher)?;
+                    Ok(hasher.finalize())
+                })
+            };
+            Ok(blake3_hash(first)? == blake3_hash(second)?)
+        }
+    }
+}
+
+/// Unlike single read, fills whole buffer unless end of file is reached
+fn read_to_fill(file: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
 #[cfg(target_family = "windows")]
 fn filter_hard_links(vec_file_entry: &[FileEntry]) -> Vec<FileEntry> {
     vec_file_entry.to_vec()
@@ -1269,6 +1865,8 @@ fn filter_hard_links(vec_file_entry: &[FileEntry]) -> Vec<FileEntry> {
 }
 
 pub fn make_hard_link(src: &Path, dst: &Path) -> io::Result<()> {
+    common_archive::ensure_not_archive_entry(src)?;
+    common_archive::ensure_not_archive_entry(dst)?;
     let dst_dir = dst.parent().ok_or_else(|| Error::new(ErrorKind::Other, "No parent"))?;
     let temp = dst_dir.join(TEMP_HARDLINK_FILE);
     fs::rename(dst, temp.as_path())?;
@@ -1280,112 +1878,606 @@ pub fn make_hard_link(src: &Path, dst: &Path) -> io::Result<()> {
     result
 }
 
-pub fn save_hashes_to_file(hashmap: &BTreeMap<String, FileEntry>, text_messages: &mut Messages, type_of_hash: &HashType, is_prehash: bool, minimal_cache_file_size: u64) {
-    if let Some(((file_handler, cache_file), (_json_file, _json_name))) = open_cache_folder(&get_file_hash_name(type_of_hash, is_prehash), true, false, &mut text_messages.warnings)
+/// Replaces dst with symbolic link to src
+/// Link is created under temporary name and renamed over dst, so dst is never missing
+pub fn make_symlink(src: &Path, dst: &Path, symlink_type: SymlinkType) -> io::Result<()> {
+    common_archive::ensure_not_archive_entry(src)?;
+    common_archive::ensure_not_archive_entry(dst)?;
+    let dst_dir = dst.parent().ok_or_else(|| Error::new(ErrorKind::Other, "No parent"))?;
+    let src = fs::canonicalize(src)?;
+    let link_target = match symlink_type {
+        SymlinkType::Absolute => src,
+        SymlinkType::Relative => relative_path(&fs::canonicalize(dst_dir)?, &src),
+    };
+
+    let temp = dst_dir.join(TEMP_HARDLINK_FILE);
+    #[cfg(target_family = "unix")]
+    let result = std::os::unix::fs::symlink(&link_target, &temp);
+    #[cfg(target_family = "windows")]
+    let result = std::os::windows::fs::symlink_file(&link_target, &temp);
+    result?;
+    fs::rename(&temp, dst).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        e
+    })
+}
+
+/// Path to `to` relative to folder `from`, both paths must be absolute
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from_components: Vec<_> = from.components().collect();
+    let to_components: Vec<_> = to.components().collect();
+    let common = from_components.iter().zip(&to_components).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from_components.len() {
+        relative.push("..");
+    }
+    for component in &to_components[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Makes dst share extents with src(copy-on-write), both files stay independent inodes
+/// File is never modified when filesystem doesn't support reflinks, error with `ErrorKind::Unsupported` is returned instead
+pub fn make_reflink(src: &Path, dst: &Path) -> io::Result<()> {
+    common_archive::ensure_not_archive_entry(src)?;
+    common_archive::ensure_not_archive_entry(dst)?;
+    #[cfg(target_os = "linux")]
+    {
+        reflink::make_reflink(src, dst)
+    }
+    #[cfg(not(target_os = "linux"))]
     {
-        let mut writer = BufWriter::new(file_handler.unwrap()); // Unwrap cannot fail
+        let _ = (src, dst);
+        Err(Error::new(ErrorKind::Unsupported, "Reflinks are supported only on Linux"))
+    }
+}
 
-        let mut how_much = 0;
-        for file_entry in hashmap.values() {
-            if file_entry.size >= minimal_cache_file_size {
-                let string: String = format!("{}//{}//{}//{}", file_entry.path.display(), file_entry.size, file_entry.modified_date, file_entry.hash);
+#[cfg(target_os = "linux")]
+mod reflink {
+    use std::ffi::CString;
+    use std::fs::{self, File, Metadata, OpenOptions};
+    use std::io::{self, Error, ErrorKind};
+    use std::os::raw::{c_char, c_ulong, c_void};
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    use super::{files_are_identical, VerificationMethod};
+
+    const FICLONE: c_ulong = 0x4004_9409;
+    const FIDEDUPERANGE: c_ulong = 0xC018_9436;
+    const FILE_DEDUPE_RANGE_DIFFERS: i32 = 1;
+    // Kernel may dedupe less than requested in one call, so bigger files are processed in chunks
+    const DEDUPE_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
+    #[repr(C)]
+    struct FileDedupeRangeInfo {
+        dest_fd: i64,
+        dest_offset: u64,
+        bytes_deduped: u64,
+        status: i32,
+        reserved: u32,
+    }
+
+    #[repr(C)]
+    struct FileDedupeRange {
+        src_offset: u64,
+        src_length: u64,
+        dest_count: u16,
+        reserved1: u16,
+        reserved2: u32,
+        info: [FileDedupeRangeInfo; 1],
+    }
+
+    /// FIDEDUPERANGE is used first, because kernel itself checks that content is identical and keeps metadata of dst
+    /// FICLONE into temporary file is used only when dedupe is not implemented, other dedupe errors(e.g. file changed in meantime) are returned
+    pub fn make_reflink(src: &Path, dst: &Path) -> io::Result<()> {
+        let src_file = File::open(src)?;
+        let length = src_file.metadata()?.len();
+        if fs::metadata(dst)?.len() != length {
+            return Err(Error::new(ErrorKind::InvalidData, "Files have different size"));
+        }
 
-                if let Err(e) = writeln!(writer, "{}", string) {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to save some data to cache file {}, reason {}", cache_file.display(), e));
-                    return;
+        match dedupe(&src_file, dst, length) {
+            Err(e) if is_not_implemented(&e) => clone(&src_file, dst).map_err(|e| {
+                if is_not_implemented(&e) || matches!(e.raw_os_error(), Some(libc::EINVAL | libc::EXDEV)) {
+                    Error::new(ErrorKind::Unsupported, format!("Filesystem doesn't support reflinks ({})", e))
                 } else {
-                    how_much += 1;
+                    e
                 }
+            }),
+            result => result,
+        }
+    }
+
+    fn is_not_implemented(error: &io::Error) -> bool {
+        matches!(error.raw_os_error(), Some(libc::EOPNOTSUPP | libc::ENOTTY | libc::ENOSYS))
+    }
+
+    fn dedupe(src_file: &File, dst: &Path, length: u64) -> io::Result<()> {
+        // Owner of file may dedupe it also when it is opened only for reading
+        let dst_file = match OpenOptions::new().write(true).open(dst) {
+            Ok(t) => t,
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => File::open(dst)?,
+            Err(e) => return Err(e),
+        };
+
+        let mut offset = 0;
+        while offset < length {
+            let mut range = FileDedupeRange {
+                src_offset: offset,
+                src_length: (length - offset).min(DEDUPE_CHUNK_SIZE),
+                dest_count: 1,
+                reserved1: 0,
+                reserved2: 0,
+                info: [FileDedupeRangeInfo {
+                    dest_fd: dst_file.as_raw_fd() as i64,
+                    dest_offset: offset,
+                    bytes_deduped: 0,
+                    status: 0,
+                    reserved: 0,
+                }],
+            };
+            // SAFETY: range is valid file_dedupe_range structure with exactly dest_count entries
+            if unsafe { libc::ioctl(src_file.as_raw_fd(), FIDEDUPERANGE as _, &mut range) } < 0 {
+                return Err(Error::last_os_error());
+            }
+
+            let info = &range.info[0];
+            if info.status < 0 {
+                return Err(Error::from_raw_os_error(-info.status));
+            } else if info.status == FILE_DEDUPE_RANGE_DIFFERS {
+                return Err(Error::new(ErrorKind::InvalidData, "Content of files differs"));
+            } else if info.bytes_deduped == 0 {
+                return Err(Error::new(ErrorKind::Other, "Kernel didn't dedupe any data"));
+            }
+            offset += info.bytes_deduped;
+        }
+        Ok(())
+    }
+
+    /// Clone replaces dst, so it is done only when dst still has the same content and its metadata can be copied to clone
+    fn clone(src_file: &File, dst: &Path) -> io::Result<()> {
+        let dst_dir = dst.parent().ok_or_else(|| Error::new(ErrorKind::Other, "No parent"))?;
+        let dst_file = File::open(dst)?;
+        let dst_metadata = dst_file.metadata()?;
+        let temp = tempfile::Builder::new().prefix(".czkawka_reflink").tempfile_in(dst_dir)?;
+
+        // SAFETY: both descriptors are valid for whole call
+        if unsafe { libc::ioctl(temp.as_file().as_raw_fd(), FICLONE as _, src_file.as_raw_fd()) } < 0 {
+            return Err(Error::last_os_error());
+        }
+        // dst may be changed after it was hashed
+        if !files_are_identical(temp.path(), dst, VerificationMethod::ByteByByte)? {
+            return Err(Error::new(ErrorKind::InvalidData, "Content of files differs"));
+        }
+        copy_metadata(&dst_file, &dst_metadata, temp.as_file())?;
+
+        temp.persist(dst).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Owner is set before permissions, because changing it clears setuid bits, times are set at the end because they change with content
+    pub(super) fn copy_metadata(from: &File, metadata: &Metadata, to: &File) -> io::Result<()> {
+        // SAFETY: descriptor is valid for whole call
+        if unsafe { libc::fchown(to.as_raw_fd(), metadata.uid(), metadata.gid()) } < 0 {
+            return Err(Error::last_os_error());
+        }
+        to.set_permissions(metadata.permissions())?;
+        copy_xattrs(from, to)?;
+
+        let times = [
+            libc::timespec {
+                tv_sec: metadata.atime() as _,
+                tv_nsec: metadata.atime_nsec() as _,
+            },
+            libc::timespec {
+                tv_sec: metadata.mtime() as _,
+                tv_nsec: metadata.mtime_nsec() as _,
+            },
+        ];
+        // SAFETY: times contains exactly two timespec structures
+        if unsafe { libc::futimens(to.as_raw_fd(), times.as_ptr()) } < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Attributes which new file already got with the same value(e.g. security labels) are not set again
+    fn copy_xattrs(from: &File, to: &File) -> io::Result<()> {
+        // SAFETY: in all calls below descriptors are valid, names are NUL terminated and buffers have given size
+        let names = match read_xattr_buffer(|buffer, size| unsafe { libc::flistxattr(from.as_raw_fd(), buffer as *mut c_char, size) }) {
+            Ok(t) => t,
+            Err(e) if is_not_implemented(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for name in names.split(|byte| *byte == 0).filter(|name| !name.is_empty()) {
+            let name = CString::new(name).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            let value = read_xattr_buffer(|buffer, size| unsafe { libc::fgetxattr(from.as_raw_fd(), name.as_ptr(), buffer, size) })?;
+            let current_value = read_xattr_buffer(|buffer, size| unsafe { libc::fgetxattr(to.as_raw_fd(), name.as_ptr(), buffer, size) });
+            if current_value.ok().as_ref() == Some(&value) {
+                continue;
+            }
+            // SAFETY: name is NUL terminated and value is valid for its length
+            if unsafe { libc::fsetxattr(to.as_raw_fd(), name.as_ptr(), value.as_ptr() as *const c_void, value.len(), 0) } < 0 {
+                return Err(Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Size of attribute is read first, it may grow before second call, so then it is read again
+    fn read_xattr_buffer(read: impl Fn(*mut c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+        loop {
+            let size = read(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(Error::last_os_error());
+            }
+            let mut buffer = vec![0u8; size as usize];
+            let read_size = read(buffer.as_mut_ptr() as *mut c_void, buffer.len());
+            if read_size >= 0 {
+                buffer.truncate(read_size as usize);
+                return Ok(buffer);
+            }
+            let e = Error::last_os_error();
+            if e.raw_os_error() != Some(libc::ERANGE) {
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Version of binary cache of duplicate hashes, older versions are migrated when loading
+const DUPLICATE_CACHE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct DuplicateCacheEntry {
+    path: Vec<u8>,
+    size: u64,
+    modified_date: u64,
+    hash: String,
+    identity: Option<FileIdentity>,
+}
+
+/// Entry of cache saved before identities of files were recorded
+#[derive(Serialize, Deserialize)]
+struct DuplicateCacheEntryV1 {
+    path: Vec<u8>,
+    size: u64,
+    modified_date: u64,
+    hash: String,
+}
+
+impl From<DuplicateCacheEntryV1> for DuplicateCacheEntry {
+    fn from(entry: DuplicateCacheEntryV1) -> Self {
+        Self {
+            path: entry.path,
+            size: entry.size,
+            modified_date: entry.modified_date,
+            hash: entry.hash,
+            identity: None,
+        }
+    }
+}
+
+impl DuplicateCacheEntry {
+    fn new(file_entry: &FileEntry, identity: Option<FileIdentity>) -> Self {
+        Self {
+            path: path_to_bytes(&file_entry.path),
+            size: file_entry.size,
+            modified_date: file_entry.modified_date,
+            hash: file_entry.hash.clone(),
+            identity,
+        }
+    }
+}
+
+impl From<DuplicateCacheEntry> for FileEntry {
+    fn from(entry: DuplicateCacheEntry) -> Self {
+        Self {
+            path: path_from_bytes(entry.path),
+            size: entry.size,
+            modified_date: entry.modified_date,
+            hash: entry.hash,
+            symlink_info: None,
+            device: None,
+        }
+    }
+}
+
+/// Writes entries with already known identities of files
+pub(crate) fn write_duplicate_cache<W: Write>(writer: W, file_entries: &[(&FileEntry, Option<FileIdentity>)]) -> io::Result<()> {
+    let entries: Vec<DuplicateCacheEntry> = file_entries.iter().map(|(file_entry, identity)| DuplicateCacheEntry::new(file_entry, *identity)).collect();
+    write_versioned_cache(writer, DUPLICATE_CACHE_VERSION, &entries)
+}
+
+pub(crate) fn read_duplicate_cache<R: Read>(mut reader: R) -> io::Result<Vec<(FileEntry, Option<FileIdentity>)>> {
+    let entries: Vec<DuplicateCacheEntry> = match read_versioned_cache_header(&mut reader)? {
+        1 => read_cache_content::<_, Vec<DuplicateCacheEntryV1>>(reader)?
+            .into_iter()
+            .map(DuplicateCacheEntry::from)
+            .collect(),
+        2 => read_cache_content(reader)?,
+        version => return Err(Error::new(ErrorKind::InvalidData, format!("Unsupported cache version {}", version))),
+    };
+    Ok(entries
+        .into_iter()
+        .map(|entry| {
+            let identity = entry.identity;
+            (FileEntry::from(entry), identity)
+        })
+        .collect())
+}
+
+pub(crate) struct DuplicateCacheFormat;
+
+impl CacheFormat for DuplicateCacheFormat {
+    type Entry = FileEntry;
+
+    fn read<R: Read>(reader: R) -> io::Result<CacheEntries<FileEntry>> {
+        read_duplicate_cache(reader)
+    }
+
+    fn write(entries: &[(FileEntry, Option<FileIdentity>)]) -> io::Result<Vec<u8>> {
+        let entries: Vec<(&FileEntry, Option<FileIdentity>)> = entries.iter().map(|(file_entry, identity)| (file_entry, *identity)).collect();
+        let mut data = Vec::new();
+        write_duplicate_cache(&mut data, &entries)?;
+        Ok(data)
+    }
+}
+
+pub fn save_hashes_to_file(
+    hashmap: &BTreeMap<PathBuf, FileEntry>,
+    volumes: &Volumes,
+    text_messages: &mut Messages,
+    type_of_hash: &HashType,
+    is_prehash: bool,
+    minimal_cache_file_size: u64,
+) {
+    save_hashes_to_cache_file(
+        hashmap,
+        None,
+        volumes,
+        text_messages,
+        &get_file_hash_name(type_of_hash, is_prehash),
+        minimal_cache_file_size,
+    );
+}
+
+fn save_hashes_to_cache_file(
+    hashmap: &BTreeMap<PathBuf, FileEntry>,
+    moved_files: Option<&MovedFiles<FileEntry>>,
+    volumes: &Volumes,
+    text_messages: &mut Messages,
+    cache_file_name: &str,
+    minimal_cache_file_size: u64,
+) {
+    if let Some(((_file_handler, cache_file), (_json_file, _json_name))) = open_cache_folder(cache_file_name, true, false, text_messages) {
+        let own_file_entries: Vec<&FileEntry> = hashmap.values().filter(|e| e.size >= minimal_cache_file_size).collect();
+        let file_entries: CacheEntries<FileEntry> = own_file_entries
+            .par_iter()
+            .map(|file_entry| ((*file_entry).clone(), identity_of_entry(*file_entry, moved_files)))
+            .collect();
+
+        let mut saved_entries = 0;
+        let results = save_cache_entries::<DuplicateCacheFormat>(&cache_file, file_entries, volumes, |file_entry| {
+            file_entry.size >= minimal_cache_file_size && !hashmap.contains_key(&file_entry.path)
+        });
+        for (file, result) in results {
+            match result {
+                Ok(file_entries) => saved_entries += file_entries.len(),
+                Err(e) => text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::CannotCreateFile,
+                        format!("Failed to save some data to cache file {}, reason {}", file.display(), e),
+                    )
+                    .path(&file)
+                    .io_error(&e),
+                ),
             }
         }
 
         text_messages
             .messages
-            .push(flc!("core_saving_to_cache", generate_translation_hashmap(vec![("number", how_much.to_string())])));
+            .push(flc!("core_saving_to_cache", generate_translation_hashmap(vec![("number", saved_entries.to_string())])));
     }
 }
-pub fn load_hashes_from_file(text_messages: &mut Messages, delete_outdated_cache: bool, type_of_hash: &HashType, is_prehash: bool) -> Option<BTreeMap<u64, Vec<FileEntry>>> {
-    if let Some(((file_handler, cache_file), (_json_file, _json_name))) =
-        open_cache_folder(&get_file_hash_name(type_of_hash, is_prehash), false, false, &mut text_messages.warnings)
-    {
+
+pub fn load_hashes_from_file(
+    text_messages: &mut Messages,
+    volumes: &Volumes,
+    delete_outdated_cache: bool,
+    type_of_hash: &HashType,
+    is_prehash: bool,
+) -> Option<BTreeMap<u64, Vec<FileEntry>>> {
+    load_hashes_with_moved_files(text_messages, volumes, delete_outdated_cache, &get_file_hash_name(type_of_hash, is_prehash)).map(|(hashmap, _moved_files)| hashmap)
+}
+
+/// Loads cache grouped by size, entries of not existing files with known identity are returned also as moved files
+fn load_hashes_with_moved_files(
+    text_messages: &mut Messages,
+    volumes: &Volumes,
+    delete_outdated_cache: bool,
+    cache_file_name: &str,
+) -> Option<(BTreeMap<u64, Vec<FileEntry>>, MovedFiles<FileEntry>)> {
+    migrate_legacy_cache_file(text_messages, volumes, cache_file_name);
+
+    if let Some(((file_handler, cache_file), (_json_file, _json_name))) = open_cache_folder(cache_file_name, false, false, text_messages) {
         // Unwrap could fail when failed to open cache file, but json would exists
         let file_handler = match file_handler {
             Some(t) => t,
             _ => return Default::default(),
         };
-        let reader = BufReader::new(file_handler);
+        let file_entries = match read_duplicate_cache(BufReader::new(file_handler)) {
+            Ok(t) => t,
+            Err(e) => {
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidCache,
+                        format!("Failed to load data from cache file {}, reason {}", cache_file.display(), e),
+                    )
+                    .path(&cache_file)
+                    .io_error(&e),
+                );
+                return None;
+            }
+        };
+
+        let mut file_entries = file_entries;
+        for (file, result) in load_volume_cache_entries::<DuplicateCacheFormat>(cache_file_name, volumes) {
+            match result {
+                Ok(t) => file_entries.extend(t),
+                Err(e) => text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidCache,
+                        format!("Failed to load data from cache file {}, reason {}", file.display(), e),
+                    )
+                    .path(&file)
+                    .io_error(&e),
+                ),
+            }
+        }
 
         let mut hashmap_loaded_entries: BTreeMap<u64, Vec<FileEntry>> = Default::default();
+        let mut moved_files = MovedFiles::new();
+        for (mut file_entry, identity) in file_entries {
+            // Files on not mounted volumes are kept for later
+            if !decode_cache_entry(volumes, &mut file_entry) {
+                hashmap_loaded_entries.entry(file_entry.size).or_default().push(file_entry);
+                continue;
+            }
+            if (identity.is_some() || delete_outdated_cache) && !file_entry.path.exists() && !is_archive_entry(&file_entry.path) {
+                if let Some(identity) = identity {
+                    moved_files.add(identity, file_entry.clone());
+                }
+                // Don't load cache data if destination file not exists
+                if delete_outdated_cache {
+                    continue;
+                }
+            }
+            hashmap_loaded_entries.entry(file_entry.size).or_default().push(file_entry);
+        }
+
+        text_messages.messages.push(flc!(
+            "core_loading_from_cache",
+            generate_translation_hashmap(vec![("number", hashmap_loaded_entries.values().map(|e| e.len()).sum::<usize>().to_string())])
+        ));
 
-        // Read the file line by line using the lines() iterator from std::io::BufRead.
-        for (index, line) in reader.lines().enumerate() {
-            let line = match line {
+        return Some((hashmap_loaded_entries, moved_files));
+    }
+    None
+}
+
+/// Older versions saved cache as text file with `path//size//modified_date//hash` lines
+/// When only such file exists, it is converted to binary cache and removed
+fn migrate_legacy_cache_file(text_messages: &mut Messages, volumes: &Volumes, cache_file_name: &str) {
+    let cache_folder = match get_cache_folder() {
+        Some(t) => t,
+        None => return,
+    };
+    let legacy_cache_file = cache_folder.join(cache_file_name.replace(".bin", ".txt"));
+    if cache_folder.join(cache_file_name).exists() || !legacy_cache_file.is_file() {
+        return;
+    }
+    let file_entries = match File::open(&legacy_cache_file) {
+        Ok(file_handler) => load_legacy_cache(BufReader::new(file_handler), &legacy_cache_file, text_messages),
+        Err(_) => return,
+    };
+    if let Some(file_entries) = file_entries {
+        let hashmap: BTreeMap<PathBuf, FileEntry> = file_entries.into_iter().map(|e| (e.path.clone(), e)).collect();
+        save_hashes_to_cache_file(&hashmap, None, volumes, text_messages, cache_file_name, 0);
+        if cache_folder.join(cache_file_name).exists() {
+            let _ = fs::remove_file(&legacy_cache_file);
+            text_messages.messages.push(format!("Migrated cache file {} to new format", legacy_cache_file.display()));
+        }
+    }
+}
+
+fn load_legacy_cache<R: BufRead>(reader: R, cache_file: &Path, text_messages: &mut Messages) -> Option<Vec<FileEntry>> {
+    let mut file_entries = Vec::new();
+
+    // Read the file line by line using the lines() iterator from std::io::BufRead.
+    for (index, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(t) => t,
+            Err(e) => {
+                text_messages.add_diagnostic(
+                    Diagnostic::warning(
+                        DiagnosticKind::InvalidCache,
+                        format!("Failed to load line number {} from cache file {}, reason {}", index + 1, cache_file.display(), e),
+                    )
+                    .path(cache_file)
+                    .io_error(&e),
+                );
+                return None;
+            }
+        };
+        let uuu = line.split("//").collect::<Vec<&str>>();
+        if uuu.len() != 4 {
+            text_messages.add_diagnostic(
+                Diagnostic::warning(
+                    DiagnosticKind::InvalidCache,
+                    format!(
+                        "Found invalid data(too much or too low amount of data) in line {} - ({}) in cache file {}",
+                        index + 1,
+                        line,
+                        cache_file.display()
+                    ),
+                )
+                .path(cache_file),
+            );
+            continue;
+        }
+        let file_entry = FileEntry {
+            path: PathBuf::from(uuu[0]),
+            size: match uuu[1].parse::<u64>() {
                 Ok(t) => t,
                 Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load line number {} from cache file {}, reason {}", index + 1, cache_file.display(), e));
-                    return None;
-                }
-            };
-            let uuu = line.split("//").collect::<Vec<&str>>();
-            if uuu.len() != 4 {
-                text_messages.warnings.push(format!(
-                    "Found invalid data(too much or too low amount of data) in line {} - ({}) in cache file {}",
-                    index + 1,
-                    line,
-                    cache_file.display()
-                ));
-                continue;
-            }
-            // Don't load cache data if destination file not exists
-            if !delete_outdated_cache || Path::new(uuu[0]).exists() {
-                let file_entry = FileEntry {
-                    path: PathBuf::from(uuu[0]),
-                    size: match uuu[1].parse::<u64>() {
-                        Ok(t) => t,
-                        Err(e) => {
-                            text_messages.warnings.push(format!(
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::InvalidCache,
+                            format!(
                                 "Found invalid size value in line {} - ({}) in cache file {}, reason {}",
                                 index + 1,
                                 line,
                                 cache_file.display(),
                                 e
-                            ));
-                            continue;
-                        }
-                    },
-                    modified_date: match uuu[2].parse::<u64>() {
-                        Ok(t) => t,
-                        Err(e) => {
-                            text_messages.warnings.push(format!(
+                            ),
+                        )
+                        .path(cache_file),
+                    );
+                    continue;
+                }
+            },
+            modified_date: match uuu[2].parse::<u64>() {
+                Ok(t) => t,
+                Err(e) => {
+                    text_messages.add_diagnostic(
+                        Diagnostic::warning(
+                            DiagnosticKind::InvalidCache,
+                            format!(
                                 "Found invalid modified date value in line {} - ({}) in cache file {}, reason {}",
                                 index + 1,
                                 line,
                                 cache_file.display(),
                                 e
-                            ));
-                            continue;
-                        }
-                    },
-                    hash: uuu[3].to_string(),
-                    symlink_info: None,
-                };
-                hashmap_loaded_entries.entry(file_entry.size).or_insert_with(Vec::new);
-                hashmap_loaded_entries.get_mut(&file_entry.size).unwrap().push(file_entry);
-            }
-        }
-
-        text_messages.messages.push(flc!(
-            "core_loading_from_cache",
-            generate_translation_hashmap(vec![("number", hashmap_loaded_entries.values().map(|e| e.len()).sum::<usize>().to_string())])
-        ));
-
-        return Some(hashmap_loaded_entries);
+                            ),
+                        )
+                        .path(cache_file),
+                    );
+                    continue;
+                }
+            },
+            hash: uuu[3].to_string(),
+            symlink_info: None,
+            device: None,
+        };
+        file_entries.push(file_entry);
     }
-    None
+
+    Some(file_entries)
 }
 
 pub trait MyHasher {
@@ -1393,33 +2485,59 @@ pub trait MyHasher {
     fn finalize(&self) -> String;
 }
 
-fn hash_calculation(buffer: &mut [u8], file_entry: &FileEntry, hash_type: &HashType, limit: u64) -> Result<String, String> {
-    let mut file_handler = match File::open(&file_entry.path) {
-        Ok(t) => t,
-        Err(e) => return Err(format!("Unable to check hash of file {}, reason {}", file_entry.path.display(), e)),
-    };
+pub(crate) fn hash_calculation(buffer: &mut [u8], file_entry: &FileEntry, hash_type: &HashType, limit: u64) -> Result<String, String> {
     let hasher = &mut *hash_type.hasher();
-    let mut current_file_read_bytes: u64 = 0;
-    loop {
-        let n = match file_handler.read(buffer) {
-            Ok(0) => break,
-            Ok(t) => t,
-            Err(e) => return Err(format!("Error happened when checking hash of file {}, reason {}", file_entry.path.display(), e)),
-        };
+    let result = common_archive::open_file(&file_entry.path, |file_handler| {
+        let mut current_file_read_bytes: u64 = 0;
+        loop {
+            let n = match file_handler.read(buffer)? {
+                0 => break,
+                t => t,
+            };
 
-        current_file_read_bytes += n as u64;
-        hasher.update(&buffer[..n]);
+            current_file_read_bytes += n as u64;
+            hasher.update(&buffer[..n]);
 
-        if current_file_read_bytes >= limit {
-            break;
+            if current_file_read_bytes >= limit {
+                break;
+            }
         }
+        Ok(())
+    });
+    match result {
+        Ok(()) => Ok(hasher.finalize()),
+        Err(e) => Err(format!("Error happened when checking hash of file {}, reason {}", file_entry.path.display(), e)),
+    }
+}
+
+/// Hashes only parts of file chosen by stage
+fn partial_hash_calculation(buffer: &mut [u8], file_entry: &FileEntry, hash_type: &HashType, stage: &PartialHashStage) -> Result<String, String> {
+    let hasher = &mut *hash_type.hasher();
+    let result = common_archive::open_file(&file_entry.path, |file_handler| {
+        for (offset, length) in stage.ranges(file_entry.size) {
+            file_handler.seek_to(offset)?;
+            let mut left_bytes = length;
+            while left_bytes > 0 {
+                let to_read = left_bytes.min(buffer.len() as u64) as usize;
+                let n = match file_handler.read(&mut buffer[..to_read])? {
+                    0 => break,
+                    t => t,
+                };
+                hasher.update(&buffer[..n]);
+                left_bytes -= n as u64;
+            }
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => Ok(hasher.finalize()),
+        Err(e) => Err(format!("Error happened when checking hash of file {}, reason {}", file_entry.path.display(), e)),
     }
-    Ok(hasher.finalize())
 }
 
 fn get_file_hash_name(type_of_hash: &HashType, is_prehash: bool) -> String {
     let prehash_str = if is_prehash { "_prehash" } else { "" };
-    format!("cache_duplicates_{:?}{}.txt", type_of_hash, prehash_str)
+    format!("cache_duplicates_{:?}{}.bin", type_of_hash, prehash_str)
 }
 
 impl MyHasher for blake3::Hasher {
@@ -1449,6 +2567,34 @@ impl MyHasher for xxhash_rust::xxh3::Xxh3 {
     }
 }
 
+// Hex digests are same as printed by sha256sum, sha1sum and md5sum
+impl MyHasher for sha2::Sha256 {
+    fn update(&mut self, bytes: &[u8]) {
+        sha2::Digest::update(self, bytes);
+    }
+    fn finalize(&self) -> String {
+        bytes_to_hex(&sha2::Digest::finalize(self.clone()))
+    }
+}
+
+impl MyHasher for sha1::Sha1 {
+    fn update(&mut self, bytes: &[u8]) {
+        sha1::Digest::update(self, bytes);
+    }
+    fn finalize(&self) -> String {
+        bytes_to_hex(&sha1::Digest::finalize(self.clone()))
+    }
+}
+
+impl MyHasher for md5::Md5 {
+    fn update(&mut self, bytes: &[u8]) {
+        md5::Digest::update(self, bytes);
+    }
+    fn finalize(&self) -> String {
+        bytes_to_hex(&md5::Digest::finalize(self.clone()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::{read_dir, File, Metadata};
@@ -1508,6 +2654,189 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_make_relative_symlink() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (src, dst) = (dir.path().join("a"), dir.path().join("b").join("c"));
+        fs::create_dir(dir.path().join("b"))?;
+        fs::write(&src, b"abcd")?;
+        fs::write(&dst, b"abcd")?;
+
+        make_symlink(&src, &dst, SymlinkType::Relative)?;
+
+        assert_eq!(fs::read_link(&dst)?, Path::new("../a"));
+        assert_eq!(fs::read(&dst)?, b"abcd");
+        assert_eq!(read_dir(dir.path().join("b"))?.count(), 1);
+        assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/c/d")), Path::new("../c/d"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_reflink_keeps_content() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (src, dst, other) = (dir.path().join("a"), dir.path().join("b"), dir.path().join("c"));
+        fs::write(&src, b"abcd")?;
+        fs::write(&dst, b"abcd")?;
+        fs::write(&other, b"abc")?;
+
+        // Filesystem used by tests may not support reflinks, but file must stay untouched anyway
+        match make_reflink(&src, &dst) {
+            Ok(()) => {}
+            Err(e) => assert_eq!(e.kind(), ErrorKind::Unsupported),
+        }
+        assert_eq!(fs::read(&dst)?, b"abcd");
+        assert!(make_reflink(&src, &other).is_err());
+        assert_eq!(fs::read(&other)?, b"abc");
+
+        let mut actual = read_dir(&dir)?.map(|e| e.unwrap().path()).collect::<Vec<PathBuf>>();
+        actual.sort();
+        assert_eq!(vec![src, dst, other], actual);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_reflink_copies_metadata() -> io::Result<()> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::io::AsRawFd;
+
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (src, dst) = (dir.path().join("a"), dir.path().join("b"));
+        fs::write(&src, b"abcd")?;
+        fs::write(&dst, b"abcd")?;
+        fs::set_permissions(&src, fs::Permissions::from_mode(0o640))?;
+        let src_file = File::open(&src)?;
+        let times = [libc::timespec { tv_sec: 1_000_000, tv_nsec: 500 }; 2];
+        assert_eq!(unsafe { libc::futimens(src_file.as_raw_fd(), times.as_ptr()) }, 0);
+        // Filesystem used by tests may not support user attributes
+        let (src_c, dst_c, name) = (
+            CString::new(src.as_os_str().as_bytes())?,
+            CString::new(dst.as_os_str().as_bytes())?,
+            CString::new("user.czkawka")?,
+        );
+        let has_xattr = unsafe { libc::setxattr(src_c.as_ptr(), name.as_ptr(), b"value".as_ptr() as *const libc::c_void, 5, 0) } == 0;
+
+        reflink::copy_metadata(&src_file, &src_file.metadata()?, &File::open(&dst)?)?;
+        let (src_metadata, dst_metadata) = (fs::metadata(&src)?, fs::metadata(&dst)?);
+        assert_eq!(src_metadata.permissions(), dst_metadata.permissions());
+        assert_eq!((src_metadata.mtime(), src_metadata.mtime_nsec()), (dst_metadata.mtime(), dst_metadata.mtime_nsec()));
+        assert_eq!((src_metadata.uid(), src_metadata.gid()), (dst_metadata.uid(), dst_metadata.gid()));
+        if has_xattr {
+            let mut value = [0u8; 16];
+            let size = unsafe { libc::getxattr(dst_c.as_ptr(), name.as_ptr(), value.as_mut_ptr() as *mut libc::c_void, value.len()) };
+            assert_eq!(&value[..size.max(0) as usize], b"value");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_files_are_identical() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (a, b, c) = (dir.path().join("a"), dir.path().join("b"), dir.path().join("c"));
+        let content = vec![7u8; HASH_BUFFER_SIZE * 2 + 5];
+        fs::write(&a, &content)?;
+        fs::write(&b, &content)?;
+        let mut different = content.clone();
+        different[HASH_BUFFER_SIZE + 1] = 8;
+        fs::write(&c, &different)?;
+
+        for method in [VerificationMethod::ByteByByte, VerificationMethod::Blake3] {
+            assert!(files_are_identical(&a, &b, method)?);
+            assert!(!files_are_identical(&a, &c, method)?);
+        }
+
+        let mut messages = Messages::new();
+        let entries = [&a, &c].map(|path| FileEntry {
+            path: path.clone(),
+            ..Default::default()
+        });
+        let options = DeleteOptions {
+            delete_method: &DeleteMethod::AllExceptOldest,
+            keep_policy: &DeleteMethod::AllExceptOldest.keep_policy(),
+            removal_method: &RemovalMethod::Permanent,
+            verification_method: VerificationMethod::ByteByByte,
+            journal: None,
+            dryrun: false,
+        };
+        let (_, removed, failed) = delete_files(&entries, &options, &mut messages);
+        assert_eq!((removed, failed), (0, 1));
+        assert!(c.exists());
+        assert_eq!(messages.get_diagnostics_of_kind(DiagnosticKind::HashCollision).count(), 1);
+        Ok(())
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_duplicate_cache_format() -> io::Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let file_entries: Vec<FileEntry> = [OsStr::new("/a//b"), OsStr::new("/new\nline"), OsStr::from_bytes(b"/not_utf8_\xff")]
+            .iter()
+            .enumerate()
+            .map(|(index, path)| FileEntry {
+                path: PathBuf::from(path),
+                size: index as u64,
+                modified_date: 10,
+                hash: format!("hash{}", index),
+                ..Default::default()
+            })
+            .collect();
+        let mut data = Vec::new();
+        write_duplicate_cache(&mut data, &file_entries.iter().map(|e| (e, None)).collect::<Vec<_>>())?;
+        let expected: Vec<(FileEntry, Option<FileIdentity>)> = file_entries.iter().map(|e| (e.clone(), None)).collect();
+        assert_eq!(read_duplicate_cache(data.as_slice())?, expected);
+
+        // Caches saved before identities of files were recorded are still loaded
+        let entries_v1: Vec<DuplicateCacheEntryV1> = file_entries
+            .iter()
+            .map(|e| DuplicateCacheEntryV1 {
+                path: path_to_bytes(&e.path),
+                size: e.size,
+                modified_date: e.modified_date,
+                hash: e.hash.clone(),
+            })
+            .collect();
+        let mut data_v1 = Vec::new();
+        write_versioned_cache(&mut data_v1, 1, &entries_v1)?;
+        assert_eq!(read_duplicate_cache(data_v1.as_slice())?, expected);
+
+        // Newer versions cannot be read
+        data[crate::common_cache::CACHE_MAGIC.len()] = 99;
+        assert!(read_duplicate_cache(data.as_slice()).is_err());
+        assert!(read_duplicate_cache(&b"/a//10//5//hash"[..]).is_err());
+
+        let mut messages = Messages::new();
+        let legacy = load_legacy_cache(&b"/a//10//5//hash\ninvalid line\n"[..], Path::new("cache.txt"), &mut messages).unwrap();
+        assert_eq!(legacy.len(), 1);
+        assert_eq!((legacy[0].path.as_path(), legacy[0].size, legacy[0].modified_date), (Path::new("/a"), 10, 5));
+        assert_eq!(messages.get_diagnostics_of_kind(DiagnosticKind::InvalidCache).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_reference_files() {
+        let files: Vec<FileEntry> = ["/ref/long/a", "/ref/b", "/other/c"]
+            .iter()
+            .map(|path| FileEntry {
+                path: PathBuf::from(path),
+                ..Default::default()
+            })
+            .collect();
+        let reference_directories = [PathBuf::from("/ref")];
+
+        let (reference, normal_files) = split_reference_files(files.clone(), &reference_directories, None).unwrap();
+        assert_eq!((reference.path.as_path(), normal_files.len()), (Path::new("/ref/b"), 1));
+        let keep_policy = KeepPolicy::new(vec![KeepRule::PreferPath(PathBuf::from("/ref/long"))]);
+        let (reference, _) = split_reference_files(files.clone(), &reference_directories, Some(&keep_policy)).unwrap();
+        assert_eq!(reference.path, Path::new("/ref/long/a"));
+        let keep_policy = KeepPolicy::new(vec![KeepRule::InPath(PathBuf::from("/other"))]);
+        assert!(split_reference_files(files, &reference_directories, Some(&keep_policy)).is_none());
+    }
+
     #[test]
     fn test_filter_hard_links_empty() {
         let expected: Vec<FileEntry> = Default::default();
@@ -1570,6 +2899,61 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hash_calculation_checksum_tools() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let mut buf = [0u8; 2];
+        let src = dir.path().join("a");
+        fs::write(&src, b"abc")?;
+        let e = FileEntry { path: src, ..Default::default() };
+        let mut hash = |hash_type| hash_calculation(&mut buf, &e, &hash_type, u64::MAX).unwrap();
+        assert_eq!(hash(HashType::Sha256), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hash(HashType::Sha1), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hash(HashType::Md5), "900150983cd24fb0d6963f7d28e17f72");
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_hash_stages() -> io::Result<()> {
+        let sampled = PartialHashStage::Sampled { chunks: 3, chunk_size: 2 };
+        assert_eq!(PartialHashStage::Head(4).ranges(10), vec![(0, 4)]);
+        assert_eq!(PartialHashStage::Tail(4).ranges(10), vec![(6, 4)]);
+        assert_eq!(PartialHashStage::Tail(40).ranges(10), vec![(0, 10)]);
+        assert_eq!(sampled.ranges(16), vec![(3, 2), (7, 2), (11, 2)]);
+        assert!(!PartialHashStage::Head(4).is_worth_checking(7));
+        assert!(!PartialHashStage::Sampled { chunks: 0, chunk_size: 2 }.is_worth_checking(16));
+        assert!(!PartialHashStage::Sampled { chunks: 3, chunk_size: 0 }.is_worth_checking(16));
+        assert!(!PartialHashStage::Head(0).is_worth_checking(16));
+
+        // Files differ only in middle, so only sampled stage can distinguish them
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::write(&a, b"aaaaaaaXaaaaaaaa")?;
+        fs::write(&b, b"aaaaaaaYaaaaaaaa")?;
+        let (e1, e2) = (
+            FileEntry {
+                path: a,
+                size: 16,
+                ..Default::default()
+            },
+            FileEntry {
+                path: b,
+                size: 16,
+                ..Default::default()
+            },
+        );
+        let mut buf = [0u8; 3];
+        let hash = |e: &FileEntry, stage: &PartialHashStage| partial_hash_calculation(&mut [0u8; 3], e, &HashType::Blake3, stage).unwrap();
+        assert_eq!(hash(&e1, &PartialHashStage::Head(4)), hash(&e2, &PartialHashStage::Head(4)));
+        assert_eq!(hash(&e1, &PartialHashStage::Tail(4)), hash(&e2, &PartialHashStage::Tail(4)));
+        assert_ne!(hash(&e1, &sampled), hash(&e2, &sampled));
+        assert_eq!(
+            partial_hash_calculation(&mut buf, &e1, &HashType::Blake3, &PartialHashStage::Head(16)),
+            hash_calculation(&mut buf, &e1, &HashType::Blake3, u64::MAX)
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_hash_calculation_invalid_file() -> io::Result<()> {
         let dir = tempfile::Builder::new().tempdir()?;
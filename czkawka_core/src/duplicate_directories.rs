@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::common_dir_traversal::FileEntry;
+
+/// Folder found in duplicated directories, size and number of files are counted recursively for identical folders
+/// and only from files directly inside folder for similar ones
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub number_of_files: usize,
+    /// Newest modification date of files inside
+    pub modified_date: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicatedDirectories {
+    /// None when whole folder trees are identical, otherwise percentage of files directly inside folders which are duplicated between them
+    pub similarity: Option<u8>,
+    pub directories: Vec<DirectoryEntry>,
+}
+
+#[derive(Default)]
+struct DirectoryNode {
+    /// Index of duplicate group of every file directly inside, None when file has no duplicates
+    files: Vec<Option<usize>>,
+    size: u64,
+    modified_date: u64,
+    children: BTreeSet<PathBuf>,
+}
+
+struct DirectorySummary {
+    /// None when folder contains any file without duplicates, so it cannot be identical to any other folder
+    fingerprint: Option<blake3::Hash>,
+    size: u64,
+    number_of_files: usize,
+    modified_date: u64,
+}
+
+/// Finds folders with identical content and, when minimal similarity is lower than 100, pairs of folders which share part of files
+/// `all_files` must contain every scanned file, not only duplicated ones, because otherwise folders with unique files would be reported
+/// Names of files and folders are ignored, only content and structure of folders is compared
+pub fn find_duplicated_directories(
+    all_files: &[FileEntry],
+    duplicate_groups: &[&Vec<FileEntry>],
+    root_directories: &[PathBuf],
+    minimal_similarity: u8,
+) -> Vec<DuplicatedDirectories> {
+    let mut group_of_file: HashMap<&Path, usize> = HashMap::new();
+    for (index, group) in duplicate_groups.iter().enumerate() {
+        for file_entry in group.iter() {
+            group_of_file.insert(&file_entry.path, index);
+        }
+    }
+
+    let nodes = build_directory_tree(all_files, &group_of_file, root_directories);
+    let summaries = summarize_directories(&nodes);
+
+    let mut identical_groups: HashMap<blake3::Hash, Vec<&Path>> = HashMap::new();
+    for (path, summary) in &summaries {
+        if let Some(fingerprint) = summary.fingerprint {
+            identical_groups.entry(fingerprint).or_default().push(path);
+        }
+    }
+    identical_groups.retain(|_fingerprint, paths| paths.len() > 1);
+
+    // Folders inside identical folders are identical too, so only topmost ones are shown
+    let identical_folders: HashSet<&Path> = identical_groups.values().flatten().copied().collect();
+    let mut results: Vec<DuplicatedDirectories> = identical_groups
+        .into_values()
+        .filter(|paths| !paths.iter().all(|path| matches!(path.parent(), Some(parent) if identical_folders.contains(parent))))
+        .map(|mut paths| {
+            paths.sort_unstable();
+            DuplicatedDirectories {
+                similarity: None,
+                directories: paths
+                    .into_iter()
+                    .map(|path| {
+                        let summary = &summaries[path];
+                        DirectoryEntry {
+                            path: path.to_path_buf(),
+                            size: summary.size,
+                            number_of_files: summary.number_of_files,
+                            modified_date: summary.modified_date,
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    results.sort_unstable_by(|a, b| {
+        b.directories[0]
+            .size
+            .cmp(&a.directories[0].size)
+            .then_with(|| a.directories[0].path.cmp(&b.directories[0].path))
+    });
+
+    if minimal_similarity < 100 {
+        results.extend(find_similar_directories(&nodes, duplicate_groups, &identical_folders, minimal_similarity));
+    }
+    results
+}
+
+fn build_directory_tree(all_files: &[FileEntry], group_of_file: &HashMap<&Path, usize>, root_directories: &[PathBuf]) -> BTreeMap<PathBuf, DirectoryNode> {
+    let mut nodes: BTreeMap<PathBuf, DirectoryNode> = BTreeMap::new();
+    for file_entry in all_files {
+        let parent = match file_entry.path.parent() {
+            Some(t) => t,
+            None => continue,
+        };
+        let node = nodes.entry(parent.to_path_buf()).or_default();
+        node.files.push(group_of_file.get(file_entry.path.as_path()).copied());
+        node.size += file_entry.size;
+        node.modified_date = node.modified_date.max(file_entry.modified_date);
+
+        // Folders above included directories are not checked, because they contain not scanned files
+        let mut child = parent;
+        while !root_directories.iter().any(|root| root == child) {
+            let upper = match child.parent() {
+                Some(upper) if root_directories.iter().any(|root| upper.starts_with(root)) => upper,
+                _ => break,
+            };
+            if !nodes.entry(upper.to_path_buf()).or_default().children.insert(child.to_path_buf()) {
+                break;
+            }
+            child = upper;
+        }
+    }
+    nodes
+}
+
+/// Fingerprints are computed from deepest folders, so summaries of children are always available
+fn summarize_directories(nodes: &BTreeMap<PathBuf, DirectoryNode>) -> HashMap<&Path, DirectorySummary> {
+    let mut paths: Vec<&PathBuf> = nodes.keys().collect();
+    paths.sort_by_key(|path| std::cmp::Reverse(path.components().count()));
+
+    let mut summaries: HashMap<&Path, DirectorySummary> = HashMap::with_capacity(paths.len());
+    for path in paths {
+        let node = &nodes[path];
+        let mut summary = DirectorySummary {
+            fingerprint: None,
+            size: node.size,
+            number_of_files: node.files.len(),
+            modified_date: node.modified_date,
+        };
+
+        let mut items: Option<Vec<String>> = Some(Vec::new());
+        for group in &node.files {
+            match (group, &mut items) {
+                (Some(group), Some(items)) => items.push(format!("f{}", group)),
+                _ => items = None,
+            }
+        }
+        for child in &node.children {
+            let child_summary = &summaries[child.as_path()];
+            summary.size += child_summary.size;
+            summary.number_of_files += child_summary.number_of_files;
+            summary.modified_date = summary.modified_date.max(child_summary.modified_date);
+            match (child_summary.fingerprint, &mut items) {
+                (Some(fingerprint), Some(items)) => items.push(format!("d{}", fingerprint.to_hex())),
+                _ => items = None,
+            }
+        }
+
+        if let Some(mut items) = items {
+            if summary.number_of_files > 0 {
+                items.sort_unstable();
+                summary.fingerprint = Some(blake3::hash(items.join("\n").as_bytes()));
+            }
+        }
+        summaries.insert(path, summary);
+    }
+    summaries
+}
+
+/// Compares only files directly inside folders, pairs already covered by identical folders are skipped
+fn find_similar_directories(
+    nodes: &BTreeMap<PathBuf, DirectoryNode>,
+    duplicate_groups: &[&Vec<FileEntry>],
+    identical_folders: &HashSet<&Path>,
+    minimal_similarity: u8,
+) -> Vec<DuplicatedDirectories> {
+    let mut common_files: BTreeMap<(&Path, &Path), usize> = BTreeMap::new();
+    for group in duplicate_groups {
+        let mut files_in_folder: BTreeMap<&Path, usize> = BTreeMap::new();
+        for file_entry in group.iter() {
+            if let Some(parent) = file_entry.path.parent() {
+                *files_in_folder.entry(parent).or_default() += 1;
+            }
+        }
+        let files_in_folder: Vec<(&Path, usize)> = files_in_folder.into_iter().collect();
+        for (index, (first_folder, first_number)) in files_in_folder.iter().enumerate() {
+            for (second_folder, second_number) in &files_in_folder[index + 1..] {
+                *common_files.entry((first_folder, second_folder)).or_default() += first_number.min(second_number);
+            }
+        }
+    }
+
+    let is_covered = |path: &Path| path.ancestors().any(|ancestor| identical_folders.contains(ancestor));
+    let to_entry = |path: &Path| {
+        let node = &nodes[path];
+        DirectoryEntry {
+            path: path.to_path_buf(),
+            size: node.size,
+            number_of_files: node.files.len(),
+            modified_date: node.modified_date,
+        }
+    };
+
+    let mut results: Vec<DuplicatedDirectories> = common_files
+        .into_iter()
+        .filter(|((first_folder, second_folder), _common)| !(is_covered(first_folder) && is_covered(second_folder)))
+        .filter_map(|((first_folder, second_folder), common)| {
+            let all_files = nodes[first_folder].files.len().max(nodes[second_folder].files.len());
+            let similarity = (common * 100 / all_files) as u8;
+            if similarity >= minimal_similarity {
+                Some(DuplicatedDirectories {
+                    similarity: Some(similarity),
+                    directories: vec![to_entry(first_folder), to_entry(second_folder)],
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+    results.sort_by_key(|e| std::cmp::Reverse(e.similarity));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::common_dir_traversal::FileEntry;
+    use crate::duplicate_directories::find_duplicated_directories;
+
+    fn file(path: &str, hash: &str) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            size: 10,
+            hash: hash.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_duplicated_directories() {
+        let all_files = vec![
+            file("/r/a/1", "x"),
+            file("/r/a/sub/2", "y"),
+            file("/r/b/1_renamed", "x"),
+            file("/r/b/sub/2", "y"),
+            file("/r/c/1", "x"),
+            file("/r/c/3", "z"),
+            file("/r/d/3", "z"),
+            file("/r/d/5", "unique2"),
+        ];
+        let group_x = vec![all_files[0].clone(), all_files[2].clone(), all_files[4].clone()];
+        let group_y = vec![all_files[1].clone(), all_files[3].clone()];
+        let group_z = vec![all_files[5].clone(), all_files[6].clone()];
+        let groups = [&group_x, &group_y, &group_z];
+
+        let results = find_duplicated_directories(&all_files, &groups, &[PathBuf::from("/r")], 100);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].similarity, None);
+        let paths: Vec<&Path> = results[0].directories.iter().map(|e| e.path.as_path()).collect();
+        // Identical subfolders are hidden, because their parents are reported
+        assert_eq!(paths, [Path::new("/r/a"), Path::new("/r/b")]);
+        assert_eq!(results[0].directories[0].number_of_files, 2);
+        assert_eq!(results[0].directories[0].size, 20);
+
+        let results = find_duplicated_directories(&all_files, &groups, &[PathBuf::from("/r")], 50);
+        let similar: Vec<(u8, &Path, &Path)> = results
+            .iter()
+            .filter_map(|e| {
+                e.similarity
+                    .map(|similarity| (similarity, e.directories[0].path.as_path(), e.directories[1].path.as_path()))
+            })
+            .collect();
+        // Pair of identical folders is not repeated as similar
+        assert_eq!(
+            similar,
+            [
+                (50, Path::new("/r/a"), Path::new("/r/c")),
+                (50, Path::new("/r/b"), Path::new("/r/c")),
+                (50, Path::new("/r/c"), Path::new("/r/d"))
+            ]
+        );
+    }
+}
@@ -1,18 +1,22 @@
-use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufWriter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use crossbeam_channel::Receiver;
 
 use crate::common::Common;
-use crate::common_dir_traversal::{DirTraversalBuilder, DirTraversalResult, FileEntry, ProgressData};
+use crate::common_dir_traversal::{DirTraversalBuilder, DirTraversalResult, FileEntry};
 use crate::common_directory::Directories;
+use crate::common_export::{ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_journal::Journal;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::ProgressData;
+use crate::common_remove::RemovalMethod;
+use crate::common_tool::{SearchSettings, Tool};
 use crate::common_traits::*;
 
 #[derive(Eq, PartialEq, Clone, Debug)]
@@ -43,6 +47,8 @@ pub struct EmptyFiles {
     excluded_items: ExcludedItems,
     recursive_search: bool,
     delete_method: DeleteMethod,
+    removal_method: RemovalMethod,
+    journal: Option<Journal>,
     stopped_search: bool,
 }
 
@@ -57,6 +63,8 @@ impl EmptyFiles {
             excluded_items: ExcludedItems::new(),
             empty_files: vec![],
             delete_method: DeleteMethod::None,
+            removal_method: RemovalMethod::Permanent,
+            journal: None,
             stopped_search: false,
         }
     }
@@ -92,6 +100,16 @@ impl EmptyFiles {
         self.delete_method = delete_method;
     }
 
+    /// Decides if removed files are deleted permanently, moved to trash or to quarantine
+    pub fn set_removal_method(&mut self, removal_method: RemovalMethod) {
+        self.removal_method = removal_method;
+    }
+
+    /// Removed files are saved to journal, so they can be restored later
+    pub fn set_journal(&mut self, journal: Option<Journal>) {
+        self.journal = journal;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
         self.recursive_search = recursive_search;
     }
@@ -141,7 +159,7 @@ impl EmptyFiles {
                     self.empty_files = empty_files.clone();
                 }
                 self.information.number_of_empty_files = self.empty_files.len();
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages.extend_diagnostics(warnings);
                 Common::print_time(start_time, SystemTime::now(), "check_files_name".to_string());
                 true
             }
@@ -159,8 +177,15 @@ impl EmptyFiles {
         match self.delete_method {
             DeleteMethod::Delete => {
                 for file_entry in &self.empty_files {
-                    if fs::remove_file(file_entry.path.clone()).is_err() {
-                        self.text_messages.warnings.push(file_entry.path.display().to_string());
+                    match self.removal_method.remove(&file_entry.path, self.journal.as_ref()) {
+                        Ok(journal_warning) => self.text_messages.extend_diagnostics(journal_warning),
+                        Err(e) => {
+                            self.text_messages.add_diagnostic(
+                                Diagnostic::warning(DiagnosticKind::CannotRemoveFile, file_entry.path.display().to_string())
+                                    .path(&file_entry.path)
+                                    .io_error(&e),
+                            );
+                        }
                     }
                 }
             }
@@ -179,6 +204,52 @@ impl Default for EmptyFiles {
     }
 }
 
+impl Tool for EmptyFiles {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_empty_files(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("empty_files", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        for entry in results.groups.into_iter().flatten() {
+            self.empty_files.push(FileEntry {
+                path: entry.path,
+                size: entry.size,
+                modified_date: entry.modified_date,
+                ..Default::default()
+            });
+        }
+        self.information.number_of_empty_files = self.empty_files.len();
+        self.delete_files();
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_empty_files
+    }
+}
+
 impl DebugPrint for EmptyFiles {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -220,7 +291,11 @@ impl SaveResults for EmptyFiles {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -231,7 +306,11 @@ impl SaveResults for EmptyFiles {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -248,6 +327,16 @@ impl SaveResults for EmptyFiles {
     }
 }
 
+impl ExportResults for EmptyFiles {
+    fn get_exported_results(&self) -> ExportedResults {
+        let mut results = ExportedResults::new("empty_files", false);
+        for file_entry in &self.empty_files {
+            results.add_entry(ExportedEntry::new(&file_entry.path, file_entry.size, file_entry.modified_date));
+        }
+        results
+    }
+}
+
 impl PrintResults for EmptyFiles {
     /// Print information's about duplicated entries
     /// Only needed for CLI
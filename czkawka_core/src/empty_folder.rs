@@ -1,23 +1,29 @@
 use std::collections::BTreeMap;
-use std::fs;
 use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use crossbeam_channel::Receiver;
 
 use crate::common::Common;
-use crate::common_dir_traversal::{Collect, DirTraversalBuilder, DirTraversalResult, FolderEmptiness, FolderEntry, ProgressData};
+use crate::common_dir_traversal::{Collect, DirTraversalBuilder, DirTraversalResult, FolderEmptiness, FolderEntry};
 use crate::common_directory::Directories;
+use crate::common_export::{ExportedEntry, ExportedResults};
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
-use crate::common_traits::{DebugPrint, PrintResults, SaveResults};
+use crate::common_journal::Journal;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::ProgressData;
+use crate::common_remove::RemovalMethod;
+use crate::common_tool::{SearchSettings, Tool};
+use crate::common_traits::{DebugPrint, ExportResults, PrintResults, SaveResults};
 
 /// Struct to store most basics info about all folder
 pub struct EmptyFolder {
     information: Info,
     delete_folders: bool,
+    removal_method: RemovalMethod,
+    journal: Option<Journal>,
     text_messages: Messages,
     excluded_items: ExcludedItems,
     empty_folder_list: BTreeMap<PathBuf, FolderEntry>, // Path, FolderEntry
@@ -44,6 +50,8 @@ impl EmptyFolder {
         Self {
             information: Default::default(),
             delete_folders: false,
+            removal_method: RemovalMethod::Permanent,
+            journal: None,
             text_messages: Messages::new(),
             excluded_items: Default::default(),
             empty_folder_list: Default::default(),
@@ -97,6 +105,16 @@ impl EmptyFolder {
         self.delete_folders = delete_folder;
     }
 
+    /// Decides if removed files are deleted permanently, moved to trash or to quarantine
+    pub fn set_removal_method(&mut self, removal_method: RemovalMethod) {
+        self.removal_method = removal_method;
+    }
+
+    /// Removed files are saved to journal, so they can be restored later
+    pub fn set_journal(&mut self, journal: Option<Journal>) {
+        self.journal = journal;
+    }
+
     /// Clean directory tree
     /// If directory contains only 2 empty folders, then this directory should be removed instead two empty folders inside because it will produce another empty folder.
     fn optimize_folders(&mut self) {
@@ -149,7 +167,7 @@ impl EmptyFolder {
                     }
                 }
 
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages.extend_diagnostics(warnings);
 
                 Common::print_time(start_time, SystemTime::now(), "check_for_empty_folder".to_string());
                 true
@@ -163,9 +181,13 @@ impl EmptyFolder {
         let start_time: SystemTime = SystemTime::now();
         // Folders may be deleted or require too big privileges
         for name in self.empty_folder_list.keys() {
-            match fs::remove_dir_all(name) {
-                Ok(_) => (),
-                Err(e) => self.text_messages.warnings.push(format!("Failed to remove folder {}, reason {}", name.display(), e)),
+            match self.removal_method.remove(name, self.journal.as_ref()) {
+                Ok(journal_warning) => self.text_messages.extend_diagnostics(journal_warning),
+                Err(e) => self.text_messages.add_diagnostic(
+                    Diagnostic::warning(DiagnosticKind::CannotRemoveFolder, format!("Failed to remove folder {}, reason {}", name.display(), e))
+                        .path(name)
+                        .io_error(&e),
+                ),
             };
         }
 
@@ -184,6 +206,54 @@ impl Default for EmptyFolder {
     }
 }
 
+impl Tool for EmptyFolder {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_empty_folders(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("empty_folders", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        for entry in results.groups.into_iter().flatten() {
+            self.empty_folder_list.insert(
+                entry.path,
+                FolderEntry {
+                    parent_path: None,
+                    is_empty: FolderEmptiness::Maybe,
+                    modified_date: entry.modified_date,
+                },
+            );
+        }
+        self.information.number_of_empty_folders = self.empty_folder_list.len();
+        if self.delete_folders {
+            self.delete_empty_folders();
+        }
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_empty_folders
+    }
+}
+
 impl DebugPrint for EmptyFolder {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -211,7 +281,11 @@ impl SaveResults for EmptyFolder {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -222,7 +296,11 @@ impl SaveResults for EmptyFolder {
             "Results of searching {:?} with excluded directories {:?}",
             self.directories.included_directories, self.directories.excluded_directories
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -244,6 +322,16 @@ impl SaveResults for EmptyFolder {
     }
 }
 
+impl ExportResults for EmptyFolder {
+    fn get_exported_results(&self) -> ExportedResults {
+        let mut results = ExportedResults::new("empty_folders", false);
+        for (path, folder_entry) in &self.empty_folder_list {
+            results.add_entry(ExportedEntry::new(path, 0, folder_entry.modified_date));
+        }
+        results
+    }
+}
+
 impl PrintResults for EmptyFolder {
     fn print_results(&self) {
         if !self.empty_folder_list.is_empty() {
@@ -8,9 +8,11 @@ extern crate bitflags;
 pub mod big_file;
 pub mod broken_files;
 pub mod duplicate;
+pub mod duplicate_directories;
 pub mod empty_files;
 pub mod empty_folder;
 pub mod invalid_symlinks;
+pub mod missing_in_backup;
 pub mod same_music;
 pub mod similar_images;
 pub mod similar_videos;
@@ -18,12 +20,25 @@ pub mod temporary;
 
 pub mod bad_extensions;
 pub mod common;
+pub mod common_apply;
+pub mod common_archive;
+pub mod common_cache;
+pub mod common_cache_manager;
 pub mod common_dir_traversal;
 pub mod common_directory;
+pub mod common_export;
 pub mod common_extensions;
+pub mod common_io_scheduler;
 pub mod common_items;
+pub mod common_journal;
+pub mod common_keep_policy;
 pub mod common_messages;
+pub mod common_progress;
+pub mod common_remove;
+pub mod common_tool;
 pub mod common_traits;
+pub mod common_volume;
+pub mod common_watch;
 pub mod localizer_core;
 
 pub const CZKAWKA_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,382 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crossbeam_channel::Receiver;
+use humansize::{file_size_opts as options, FileSize};
+
+use crate::common::Common;
+use crate::common_dir_traversal::{CheckingMethod, FileEntry};
+use crate::common_export::{ExportedEntry, ExportedResults};
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::ProgressData;
+use crate::common_tool::{SearchSettings, Tool};
+use crate::common_traits::*;
+use crate::duplicate::{DuplicateFinder, HashType};
+
+/// Info struck with helpful information's about results
+#[derive(Default)]
+pub struct Info {
+    pub number_of_missing_files: usize,
+    pub size_of_missing_files: u64,
+}
+
+impl Info {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// Finds files from included directories whose content doesn't exist anywhere inside backup directories
+/// Search is done by duplicate finder, which uses backup directories as reference folders
+pub struct MissingInBackup {
+    text_messages: Messages,
+    information: Info,
+    missing_files: Vec<FileEntry>,
+    search_settings: SearchSettings,
+    backup_directories: Vec<PathBuf>,
+    check_method: CheckingMethod,
+    hash_type: HashType,
+    minimal_file_size: u64,
+    maximal_file_size: u64,
+    use_cache: bool,
+    minimal_cache_file_size: u64,
+    stopped_search: bool,
+}
+
+impl MissingInBackup {
+    pub fn new() -> Self {
+        Self {
+            text_messages: Messages::new(),
+            information: Info::new(),
+            missing_files: vec![],
+            search_settings: SearchSettings::new(),
+            backup_directories: vec![],
+            check_method: CheckingMethod::Hash,
+            hash_type: HashType::Blake3,
+            minimal_file_size: 1,
+            maximal_file_size: u64::MAX,
+            use_cache: true,
+            minimal_cache_file_size: 1024 * 1024 / 4,
+            stopped_search: false,
+        }
+    }
+
+    pub fn find_missing_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        if self.backup_directories.is_empty() {
+            self.text_messages.add_diagnostic(Diagnostic::error(
+                DiagnosticKind::InvalidSettings,
+                "At least one backup directory must be provided".to_string(),
+            ));
+            return;
+        }
+        if !self.check_files(stop_receiver, progress_sender) {
+            self.stopped_search = true;
+            return;
+        }
+        self.debug_print();
+    }
+
+    pub fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    pub const fn get_missing_files(&self) -> &Vec<FileEntry> {
+        &self.missing_files
+    }
+
+    pub const fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    pub const fn get_information(&self) -> &Info {
+        &self.information
+    }
+
+    /// Files inside this directories are never reported, they may be placed outside included directories
+    pub fn set_backup_directory(&mut self, backup_directories: Vec<PathBuf>) {
+        self.backup_directories = backup_directories;
+    }
+
+    /// Only size and hash are supported, size is fast but files with same size are treated as backed up
+    pub fn set_check_method(&mut self, check_method: CheckingMethod) {
+        assert!(matches!(check_method, CheckingMethod::Size | CheckingMethod::Hash));
+        self.check_method = check_method;
+    }
+
+    pub fn set_hash_type(&mut self, hash_type: HashType) {
+        self.hash_type = hash_type;
+    }
+
+    pub fn set_minimal_file_size(&mut self, minimal_file_size: u64) {
+        self.minimal_file_size = minimal_file_size.max(1);
+    }
+
+    pub fn set_maximal_file_size(&mut self, maximal_file_size: u64) {
+        self.maximal_file_size = maximal_file_size.max(1);
+    }
+
+    pub fn set_use_cache(&mut self, use_cache: bool) {
+        self.use_cache = use_cache;
+    }
+
+    pub fn set_minimal_cache_file_size(&mut self, minimal_cache_file_size: u64) {
+        self.minimal_cache_file_size = minimal_cache_file_size;
+    }
+
+    /// Files from included directories which are not inside any group with file from backup directories are missing
+    fn check_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+        let start_time: SystemTime = SystemTime::now();
+
+        let mut search_settings = self.search_settings.clone();
+        search_settings.included_directories.extend(self.backup_directories.iter().cloned());
+        search_settings.reference_directories = self.backup_directories.clone();
+
+        let mut df = DuplicateFinder::new();
+        df.set_search_settings(search_settings);
+        df.set_check_method(self.check_method);
+        df.set_hash_type(self.hash_type);
+        df.set_minimal_file_size(self.minimal_file_size);
+        df.set_maximal_file_size(self.maximal_file_size);
+        df.set_use_cache(self.use_cache);
+        df.set_use_prehash_cache(self.use_cache);
+        df.set_minimal_cache_file_size(self.minimal_cache_file_size);
+        // Hard link inside backup directory is still a valid copy
+        df.set_ignore_hard_links(false);
+        df.set_keep_scanned_files(true);
+        df.find_duplicates(stop_receiver, progress_sender);
+
+        self.text_messages.extend_with_another_messages(df.get_text_messages().clone());
+        if df.get_stopped_search() {
+            return false;
+        }
+
+        let backed_up_files: HashSet<PathBuf> = match self.check_method {
+            CheckingMethod::Size => df
+                .get_files_with_identical_size_referenced()
+                .values()
+                .flat_map(|(_reference, vector)| vector)
+                .map(|e| e.path.clone())
+                .collect(),
+            _ => df
+                .get_files_with_identical_hashes_referenced()
+                .values()
+                .flatten()
+                .flat_map(|(_reference, vector)| vector)
+                .map(|e| e.path.clone())
+                .collect(),
+        };
+        let backup_directories = &self.backup_directories;
+        self.missing_files = df
+            .take_scanned_files()
+            .into_iter()
+            .filter(|e| !backup_directories.iter().any(|backup| e.path.starts_with(backup)) && !backed_up_files.contains(&e.path))
+            .collect();
+        self.missing_files.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+        self.information.number_of_missing_files = self.missing_files.len();
+        self.information.size_of_missing_files = self.missing_files.iter().map(|e| e.size).sum();
+
+        Common::print_time(start_time, SystemTime::now(), "check_files".to_string());
+        true
+    }
+}
+
+impl Default for MissingInBackup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for MissingInBackup {
+    /// Reference directories are ignored, backup directories must be set separately
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.search_settings = SearchSettings {
+            reference_directories: vec![],
+            ..search_settings
+        };
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_missing_files(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("missing_in_backup", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        for entry in results.groups.into_iter().flatten() {
+            self.missing_files.push(FileEntry {
+                path: entry.path,
+                size: entry.size,
+                modified_date: entry.modified_date,
+                ..Default::default()
+            });
+        }
+        self.information.number_of_missing_files = self.missing_files.len();
+        self.information.size_of_missing_files = self.missing_files.iter().map(|e| e.size).sum();
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_missing_files
+    }
+}
+
+impl DebugPrint for MissingInBackup {
+    #[allow(dead_code)]
+    #[allow(unreachable_code)]
+    /// Debugging printing - only available on debug build
+    fn debug_print(&self) {
+        #[cfg(not(debug_assertions))]
+        {
+            return;
+        }
+        println!("---------------DEBUG PRINT---------------");
+        println!("### Information's");
+
+        println!("Errors size - {}", self.text_messages.errors.len());
+        println!("Warnings size - {}", self.text_messages.warnings.len());
+        println!("Messages size - {}", self.text_messages.messages.len());
+
+        println!("### Other");
+
+        println!("Missing files list size - {}", self.missing_files.len());
+        println!("Included directories - {:?}", self.search_settings.included_directories);
+        println!("Backup directories - {:?}", self.backup_directories);
+        println!("Excluded directories - {:?}", self.search_settings.excluded_directories);
+        println!("Recursive search - {}", self.search_settings.recursive_search);
+        println!("Checking Method - {:?}", self.check_method);
+        println!("-----------------------------------------");
+    }
+}
+
+impl SaveResults for MissingInBackup {
+    fn save_results_to_file(&mut self, file_name: &str) -> bool {
+        let start_time: SystemTime = SystemTime::now();
+        let file_name: String = match file_name {
+            "" => "results.txt".to_string(),
+            k => k.to_string(),
+        };
+
+        let file_handler = match File::create(&file_name) {
+            Ok(t) => t,
+            Err(e) => {
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
+                return false;
+            }
+        };
+        let mut writer = BufWriter::new(file_handler);
+
+        if let Err(e) = writeln!(
+            writer,
+            "Results of searching {:?} with backup directories {:?}, excluded directories {:?} and excluded items {:?}",
+            self.search_settings.included_directories, self.backup_directories, self.search_settings.excluded_directories, self.search_settings.excluded_items
+        ) {
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
+            return false;
+        }
+
+        if !self.missing_files.is_empty() {
+            writeln!(
+                writer,
+                "Found {} files missing in backup which take {}.",
+                self.information.number_of_missing_files,
+                self.information.size_of_missing_files.file_size(options::BINARY).unwrap()
+            )
+            .unwrap();
+            for file_entry in self.missing_files.iter() {
+                writeln!(writer, "{}", file_entry.path.display()).unwrap();
+            }
+        } else {
+            write!(writer, "All files are present in backup.").unwrap();
+        }
+        Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+        true
+    }
+}
+
+impl ExportResults for MissingInBackup {
+    fn get_exported_results(&self) -> ExportedResults {
+        let mut results = ExportedResults::new("missing_in_backup", false).checking_method(&format!("{:?}", self.check_method).to_lowercase());
+        for file_entry in &self.missing_files {
+            results.add_entry(ExportedEntry::new(&file_entry.path, file_entry.size, file_entry.modified_date));
+        }
+        results
+    }
+}
+
+impl PrintResults for MissingInBackup {
+    /// Print information's about files missing in backup
+    /// Only needed for CLI
+    fn print_results(&self) {
+        let start_time: SystemTime = SystemTime::now();
+        println!(
+            "Found {} files missing in backup which take {}.\n",
+            self.information.number_of_missing_files,
+            self.information.size_of_missing_files.file_size(options::BINARY).unwrap()
+        );
+        for file_entry in self.missing_files.iter() {
+            println!("{}", file_entry.path.display());
+        }
+
+        Common::print_time(start_time, SystemTime::now(), "print_entries".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    use crate::common_tool::{SearchSettings, Tool};
+    use crate::missing_in_backup::MissingInBackup;
+
+    #[test]
+    fn test_find_missing_files() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (source, backup) = (dir.path().join("source"), dir.path().join("backup"));
+        fs::create_dir_all(source.join("sub"))?;
+        fs::create_dir_all(&backup)?;
+        fs::write(source.join("backed_up"), "first content")?;
+        fs::write(backup.join("renamed_copy"), "first content")?;
+        fs::write(source.join("sub/changed"), "second content")?;
+        fs::write(backup.join("changed"), "second CONTENT")?;
+        fs::write(source.join("sub/unique_size"), "third")?;
+
+        let mut finder = MissingInBackup::new();
+        finder.set_search_settings(SearchSettings {
+            included_directories: vec![source.clone()],
+            ..Default::default()
+        });
+        finder.set_backup_directory(vec![backup]);
+        finder.set_use_cache(false);
+        finder.run(None, None);
+
+        let missing: Vec<PathBuf> = finder.get_missing_files().iter().map(|e| e.path.clone()).collect();
+        assert_eq!(missing, [source.join("sub/changed"), source.join("sub/unique_size")]);
+        assert_eq!(finder.get_information().size_of_missing_files, 19);
+        Ok(())
+    }
+}
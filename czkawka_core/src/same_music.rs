@@ -1,26 +1,29 @@
 use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::{BufReader, BufWriter};
+use std::io::BufWriter;
+use std::mem;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime};
-use std::{mem, thread};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 
 use crossbeam_channel::Receiver;
 use lofty::{read_from_path, AudioFile, ItemKey};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::common::{open_cache_folder, Common, LOOP_DURATION};
-use crate::common_dir_traversal::{CheckingMethod, DirTraversalBuilder, DirTraversalResult, FileEntry, ProgressData};
+use crate::common::Common;
+use crate::common_cache::{load_cache_from_file_with_identities, save_cache_to_file_with_identities, CacheEntry, MovedFiles};
+use crate::common_dir_traversal::{DirTraversalBuilder, DirTraversalResult, FileEntry};
 use crate::common_directory::Directories;
+use crate::common_export::{ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
+use crate::common_tool::{SearchSettings, Tool};
 use crate::common_traits::*;
+use crate::common_volume::Volumes;
 use crate::similar_images::AUDIO_FILES_EXTENSIONS;
 
 #[derive(Eq, PartialEq, Clone, Debug)]
@@ -57,6 +60,21 @@ pub struct MusicEntry {
     pub bitrate: u32,
 }
 
+impl CacheEntry for MusicEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+}
+
 impl FileEntry {
     fn to_music_entry(&self) -> MusicEntry {
         MusicEntry {
@@ -277,7 +295,7 @@ impl SameMusic {
                         self.music_to_check.insert(fe.path.to_string_lossy().to_string(), fe.to_music_entry());
                     }
                 }
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages.extend_diagnostics(warnings);
                 Common::print_time(start_time, SystemTime::now(), "check_files".to_string());
                 true
             }
@@ -291,22 +309,31 @@ impl SameMusic {
     fn check_records_multithreaded(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
 
-        let loaded_hash_map;
+        let loaded_hash_map: HashMap<String, MusicEntry>;
+        let moved_files: MovedFiles<MusicEntry>;
+        let volumes = if self.use_cache {
+            Volumes::mounted_for(&self.directories.included_directories)
+        } else {
+            Volumes::default()
+        };
 
         let mut records_already_cached: HashMap<String, MusicEntry> = Default::default();
         let mut non_cached_files_to_check: HashMap<String, MusicEntry> = Default::default();
 
         if self.use_cache {
-            loaded_hash_map = match load_cache_from_file(&mut self.text_messages, self.delete_outdated_cache) {
-                Some(t) => t,
-                None => Default::default(),
-            };
+            let (cached_hash_map, cached_moved_files) =
+                load_cache_from_file_with_identities(&get_cache_file(), &volumes, self.delete_outdated_cache, &mut self.text_messages).unwrap_or_default();
+            loaded_hash_map = cached_hash_map;
+            moved_files = cached_moved_files;
 
             for (name, file_entry) in &self.music_to_check {
                 #[allow(clippy::if_same_then_else)]
                 if !loaded_hash_map.contains_key(name) {
-                    // If loaded data doesn't contains current image info
-                    non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                    // If loaded data doesn't contains current image info, but file may be moved or renamed since previous scan
+                    match moved_files.find(&file_entry.path) {
+                        Some(moved_entry) => records_already_cached.insert(name.clone(), moved_entry),
+                        None => non_cached_files_to_check.insert(name.clone(), file_entry.clone()),
+                    };
                 } else if file_entry.size != loaded_hash_map.get(name).unwrap().size || file_entry.modified_date != loaded_hash_map.get(name).unwrap().modified_date {
                     // When size or modification date of image changed, then it is clear that is different image
                     non_cached_files_to_check.insert(name.clone(), file_entry.clone());
@@ -317,50 +344,30 @@ impl SameMusic {
             }
         } else {
             loaded_hash_map = Default::default();
+            moved_files = Default::default();
             mem::swap(&mut self.music_to_check, &mut non_cached_files_to_check);
         }
 
         let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let music_to_check = non_cached_files_to_check.len();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: CheckingMethod::None,
-                        current_stage: 1,
-                        max_stage: 2,
-                        entries_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        entries_to_check: music_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::ReadingMusicTags, 1, 2)
+                .entries_to_check(non_cached_files_to_check.len())
+                .bytes_to_check(non_cached_files_to_check.values().map(|e| e.size).sum()),
+        );
 
         // Clean for duplicate files
         let mut vec_file_entry = non_cached_files_to_check
             .into_par_iter()
             .map(|(path, mut music_entry)| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
                     check_was_breaked.store(true, Ordering::Relaxed);
                     return None;
                 }
+                progress_reporter.set_current_path(&music_entry.path);
+                progress_reporter.add_bytes(music_entry.size);
 
                 let tagged_file = match read_from_path(&path, true) {
                     Ok(t) => t,
@@ -452,9 +459,7 @@ impl SameMusic {
             .map(|music_entry| music_entry.unwrap())
             .collect::<Vec<_>>();
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         // Check if user aborted search(only from GUI)
         if check_was_breaked.load(Ordering::Relaxed) {
@@ -475,7 +480,14 @@ impl SameMusic {
             for file_entry in vec_file_entry {
                 all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
             }
-            save_cache_to_file(&all_results, &mut self.text_messages, self.save_also_as_json);
+            save_cache_to_file_with_identities(
+                &get_cache_file(),
+                &all_results,
+                Some(&moved_files),
+                &volumes,
+                self.save_also_as_json,
+                &mut self.text_messages,
+            );
         }
 
         Common::print_time(start_time, SystemTime::now(), "check_records_multithreaded".to_string());
@@ -488,46 +500,19 @@ impl SameMusic {
         }
         let start_time: SystemTime = SystemTime::now();
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let music_to_check = self.music_to_check.len();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: CheckingMethod::None,
-                        current_stage: 2,
-                        max_stage: 2,
-                        entries_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        entries_to_check: music_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::ComparingMusicTags, 2, 2).entries_to_check(self.music_to_check.len()),
+        );
 
         let mut old_duplicates: Vec<Vec<MusicEntry>> = vec![self.music_entries.clone()];
         let mut new_duplicates: Vec<Vec<MusicEntry>> = Vec::new();
 
         if (self.music_similarity & MusicSimilarity::TRACK_TITLE) == MusicSimilarity::TRACK_TITLE {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<MusicEntry>> = Default::default();
@@ -552,11 +537,9 @@ impl SameMusic {
         }
         if (self.music_similarity & MusicSimilarity::TRACK_ARTIST) == MusicSimilarity::TRACK_ARTIST {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<MusicEntry>> = Default::default();
@@ -581,11 +564,9 @@ impl SameMusic {
         }
         if (self.music_similarity & MusicSimilarity::YEAR) == MusicSimilarity::YEAR {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<MusicEntry>> = Default::default();
@@ -607,11 +588,9 @@ impl SameMusic {
         }
         if (self.music_similarity & MusicSimilarity::LENGTH) == MusicSimilarity::LENGTH {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<MusicEntry>> = Default::default();
@@ -633,11 +612,9 @@ impl SameMusic {
         }
         if (self.music_similarity & MusicSimilarity::GENRE) == MusicSimilarity::GENRE {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<MusicEntry>> = Default::default();
@@ -659,11 +636,9 @@ impl SameMusic {
         }
         if (self.music_similarity & MusicSimilarity::BITRATE) == MusicSimilarity::BITRATE {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<MusicEntry>> = Default::default();
@@ -686,9 +661,7 @@ impl SameMusic {
             // new_duplicates = Vec::new();
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         self.duplicated_music_entries = old_duplicates;
 
@@ -752,8 +725,12 @@ impl SameMusic {
         // match self.delete_method {
         //     DeleteMethod::Delete => {
         //         for file_entry in &self.music_entries {
-        //             if fs::remove_file(file_entry.path.clone()).is_err() {
-        //                 self.text_messages.warnings.push(file_entry.path.display().to_string());
+        //             if let Err(e) = fs::remove_file(&file_entry.path) {
+        //                 self.text_messages.add_diagnostic(
+        //                     Diagnostic::warning(DiagnosticKind::CannotRemoveFile, file_entry.path.display().to_string())
+        //                         .path(&file_entry.path)
+        //                         .io_error(&e),
+        //                 );
         //             }
         //         }
         //     }
@@ -766,79 +743,79 @@ impl SameMusic {
     }
 }
 
-fn save_cache_to_file(hashmap: &HashMap<String, MusicEntry>, text_messages: &mut Messages, save_also_as_json: bool) {
-    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) = open_cache_folder(&get_cache_file(), true, save_also_as_json, &mut text_messages.warnings) {
-        {
-            let writer = BufWriter::new(file_handler.unwrap()); // Unwrap because cannot fail here
-            if let Err(e) = bincode::serialize_into(writer, hashmap) {
-                text_messages
-                    .warnings
-                    .push(format!("Cannot write data to cache file {}, reason {}", cache_file.display(), e));
-                return;
-            }
-        }
-        if save_also_as_json {
-            if let Some(file_handler_json) = file_handler_json {
-                let writer = BufWriter::new(file_handler_json);
-                if let Err(e) = serde_json::to_writer(writer, hashmap) {
-                    text_messages
-                        .warnings
-                        .push(format!("Cannot write data to cache file {}, reason {}", cache_file_json.display(), e));
-                    return;
-                }
-            }
-        }
+fn get_cache_file() -> String {
+    "cache_same_music.bin".to_string()
+}
 
-        text_messages.messages.push(format!("Properly saved to file {} cache entries.", hashmap.len()));
+impl Default for SameMusic {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-fn load_cache_from_file(text_messages: &mut Messages, delete_outdated_cache: bool) -> Option<HashMap<String, MusicEntry>> {
-    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) = open_cache_folder(&get_cache_file(), false, true, &mut text_messages.warnings) {
-        let mut hashmap_loaded_entries: HashMap<String, MusicEntry>;
-        if let Some(file_handler) = file_handler {
-            let reader = BufReader::new(file_handler);
-            hashmap_loaded_entries = match bincode::deserialize_from(reader) {
-                Ok(t) => t,
-                Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load data from cache file {}, reason {}", cache_file.display(), e));
-                    return None;
-                }
-            };
-        } else {
-            let reader = BufReader::new(file_handler_json.unwrap()); // Unwrap cannot fail, because at least one file must be valid
-            hashmap_loaded_entries = match serde_json::from_reader(reader) {
-                Ok(t) => t,
-                Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load data from cache file {}, reason {}", cache_file_json.display(), e));
-                    return None;
-                }
-            };
-        }
+impl Tool for SameMusic {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_reference_directory(search_settings.reference_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
 
-        // Don't load cache data if destination file not exists
-        if delete_outdated_cache {
-            hashmap_loaded_entries.retain(|src_path, _file_entry| Path::new(src_path).exists());
-        }
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_same_music(stop_receiver, progress_sender);
+    }
 
-        text_messages.messages.push(format!("Properly loaded {} cache entries.", hashmap_loaded_entries.len()));
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("same_music", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        let to_music_entry = |entry: ExportedEntry| MusicEntry {
+            size: entry.size,
+            modified_date: entry.modified_date,
+            track_title: entry.get_property("track_title"),
+            track_artist: entry.get_property("track_artist"),
+            year: entry.get_property("year"),
+            length: entry.get_property("length"),
+            genre: entry.get_property("genre"),
+            bitrate: entry.get_property("bitrate").parse().unwrap_or(0),
+            path: entry.path,
+        };
+        self.use_reference_folders = results.has_reference_groups();
+        for (reference, group) in results.into_split_groups() {
+            let group: Vec<MusicEntry> = group.into_iter().map(to_music_entry).collect();
+            self.music_entries.extend(group.iter().cloned());
+            match reference {
+                Some(reference) if self.use_reference_folders => {
+                    self.information.number_of_duplicates += group.len();
+                    self.duplicated_music_entries_referenced.push((to_music_entry(reference), group));
+                }
+                None if !self.use_reference_folders => {
+                    self.information.number_of_duplicates += group.len() - 1;
+                    self.duplicated_music_entries.push(group);
+                }
+                _ => continue,
+            }
+            self.information.number_of_groups += 1;
+        }
+        self.delete_files();
+        self.debug_print();
+    }
 
-        return Some(hashmap_loaded_entries);
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
     }
-    None
-}
 
-fn get_cache_file() -> String {
-    "cache_same_music.bin".to_string()
-}
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
 
-impl Default for SameMusic {
-    fn default() -> Self {
-        Self::new()
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_duplicates
     }
 }
 
@@ -885,7 +862,11 @@ impl SaveResults for SameMusic {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -896,7 +877,11 @@ impl SaveResults for SameMusic {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -913,6 +898,35 @@ impl SaveResults for SameMusic {
     }
 }
 
+impl ExportResults for SameMusic {
+    fn get_exported_results(&self) -> ExportedResults {
+        let to_exported = |music_entry: &MusicEntry| {
+            ExportedEntry::new(&music_entry.path, music_entry.size, music_entry.modified_date)
+                .info(format!(
+                    "{} - {} - {} - {} - {} - {}",
+                    music_entry.track_title, music_entry.track_artist, music_entry.year, music_entry.length, music_entry.genre, music_entry.bitrate
+                ))
+                .property("track_title", music_entry.track_title.clone())
+                .property("track_artist", music_entry.track_artist.clone())
+                .property("year", music_entry.year.clone())
+                .property("length", music_entry.length.clone())
+                .property("genre", music_entry.genre.clone())
+                .property("bitrate", music_entry.bitrate.to_string())
+        };
+        let mut results = ExportedResults::new("same_music", true);
+        if self.use_reference_folders {
+            for (reference, vector) in &self.duplicated_music_entries_referenced {
+                results.add_referenced_group(to_exported(reference), vector.iter().map(to_exported).collect());
+            }
+        } else {
+            for vector in &self.duplicated_music_entries {
+                results.add_group(vector.iter().map(to_exported).collect());
+            }
+        }
+        results
+    }
+}
+
 impl PrintResults for SameMusic {
     /// Print information's about duplicated entries
     /// Only needed for CLI
@@ -4,11 +4,8 @@ use std::io::Write;
 use std::io::*;
 use std::panic;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, mem, thread};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, mem};
 
 use bk_tree::BKTree;
 use crossbeam_channel::Receiver;
@@ -18,12 +15,17 @@ use image_hasher::{FilterType, HashAlg, HasherConfig};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::common::{get_dynamic_image_from_raw_image, open_cache_folder, Common, LOOP_DURATION};
+use crate::common::{get_dynamic_image_from_raw_image, Common};
+use crate::common_cache::{load_cache_from_file_with_identities, save_cache_to_file_with_identities, CacheEntry, MovedFiles};
 use crate::common_directory::Directories;
+use crate::common_export::{bytes_to_hex, hex_to_bytes, ExportedEntry, ExportedResults};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
-use crate::common_traits::{DebugPrint, PrintResults, SaveResults};
+use crate::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use crate::common_progress::{ProgressData, ProgressReporter, ProgressStage};
+use crate::common_tool::{SearchSettings, Tool};
+use crate::common_traits::{DebugPrint, ExportResults, PrintResults, SaveResults};
+use crate::common_volume::Volumes;
 use crate::flc;
 use crate::localizer_core::generate_translation_hashmap;
 
@@ -54,14 +56,6 @@ pub const SIMILAR_VALUES: [[u32; 6]; 4] = [
     [6, 20, 40, 40, 40, 40], // 64
 ];
 
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub images_checked: usize,
-    pub images_to_check: usize,
-}
-
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Serialize, Deserialize)]
 pub enum Similarity {
     Similar(u32),
@@ -77,6 +71,21 @@ pub struct FileEntry {
     pub similarity: Similarity,
 }
 
+impl CacheEntry for FileEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+}
+
 /// Used by CLI tool when we cannot use directly values
 #[derive(Clone, Debug)]
 pub enum SimilarityPreset {
@@ -306,45 +315,18 @@ impl SimilarImages {
             folders_to_check.push(id.clone());
         }
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 2,
-                        images_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        images_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(progress_sender, ProgressData::new(ProgressStage::CollectingFiles, 0, 2));
 
         while !folders_to_check.is_empty() {
             if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
+                progress_reporter.end();
                 return false;
             }
 
             let segments: Vec<_> = folders_to_check
                 .par_iter()
                 .map(|current_folder| {
+                    progress_reporter.set_current_path(current_folder);
                     let mut dir_result = vec![];
                     let mut warnings = vec![];
                     let mut fe_result = vec![];
@@ -352,10 +334,17 @@ impl SimilarImages {
                     let read_dir = match fs::read_dir(&current_folder) {
                         Ok(t) => t,
                         Err(e) => {
-                            warnings.push(flc!(
-                                "core_cannot_open_dir",
-                                generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                            ));
+                            warnings.push(
+                                Diagnostic::warning(
+                                    DiagnosticKind::CannotOpenDir,
+                                    flc!(
+                                        "core_cannot_open_dir",
+                                        generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                    ),
+                                )
+                                .path(current_folder)
+                                .io_error(&e),
+                            );
                             return (dir_result, warnings, fe_result);
                         }
                     };
@@ -365,20 +354,34 @@ impl SimilarImages {
                         let entry_data = match entry {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_entry_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadEntry,
+                                        flc!(
+                                            "core_cannot_read_entry_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
                         let metadata: Metadata = match entry_data.metadata() {
                             Ok(t) => t,
                             Err(e) => {
-                                warnings.push(flc!(
-                                    "core_cannot_read_metadata_dir",
-                                    generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
-                                ));
+                                warnings.push(
+                                    Diagnostic::warning(
+                                        DiagnosticKind::CannotReadMetadata,
+                                        flc!(
+                                            "core_cannot_read_metadata_dir",
+                                            generate_translation_hashmap(vec![("dir", current_folder.display().to_string()), ("reason", e.to_string())])
+                                        ),
+                                    )
+                                    .path(current_folder)
+                                    .io_error(&e),
+                                );
                                 continue 'dir;
                             }
                         };
@@ -400,22 +403,29 @@ impl SimilarImages {
                             if self.directories.exclude_other_filesystems() {
                                 match self.directories.is_on_other_filesystems(&next_folder) {
                                     Ok(true) => continue 'dir,
-                                    Err(e) => warnings.push(e.to_string()),
+                                    Err(e) => warnings.push(e),
                                     _ => (),
                                 }
                             }
 
                             dir_result.push(next_folder);
                         } else if metadata.is_file() {
-                            atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                            progress_reporter.add_entries(1);
+                            progress_reporter.add_bytes(metadata.len());
 
                             let file_name_lowercase: String = match entry_data.file_name().into_string() {
                                 Ok(t) => t,
                                 Err(_inspected) => {
-                                    warnings.push(flc!(
-                                        "core_file_not_utf8_name",
-                                        generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
-                                    ));
+                                    warnings.push(
+                                        Diagnostic::warning(
+                                            DiagnosticKind::NonUtf8Name,
+                                            flc!(
+                                                "core_file_not_utf8_name",
+                                                generate_translation_hashmap(vec![("name", entry_data.path().display().to_string())])
+                                            ),
+                                        )
+                                        .path(&entry_data.path()),
+                                    );
                                     continue 'dir;
                                 }
                             }
@@ -440,18 +450,31 @@ impl SimilarImages {
                                         Ok(t) => match t.duration_since(UNIX_EPOCH) {
                                             Ok(d) => d.as_secs(),
                                             Err(_inspected) => {
-                                                warnings.push(flc!(
-                                                    "core_file_modified_before_epoch",
-                                                    generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
-                                                ));
+                                                warnings.push(
+                                                    Diagnostic::warning(
+                                                        DiagnosticKind::ModifiedBeforeEpoch,
+                                                        flc!(
+                                                            "core_file_modified_before_epoch",
+                                                            generate_translation_hashmap(vec![("name", current_file_name.display().to_string())])
+                                                        ),
+                                                    )
+                                                    .path(&current_file_name),
+                                                );
                                                 0
                                             }
                                         },
                                         Err(e) => {
-                                            warnings.push(flc!(
-                                                "core_file_no_modification_date",
-                                                generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
-                                            ));
+                                            warnings.push(
+                                                Diagnostic::warning(
+                                                    DiagnosticKind::NoModificationDate,
+                                                    flc!(
+                                                        "core_file_no_modification_date",
+                                                        generate_translation_hashmap(vec![("name", current_file_name.display().to_string()), ("reason", e.to_string())])
+                                                    ),
+                                                )
+                                                .path(&current_file_name)
+                                                .io_error(&e),
+                                            );
                                             0
                                         }
                                     },
@@ -474,16 +497,15 @@ impl SimilarImages {
             // Process collected data
             for (segment, warnings, fe_result) in segments {
                 folders_to_check.extend(segment);
-                self.text_messages.warnings.extend(warnings);
+                self.text_messages
+                    .extend_diagnostics(warnings.into_iter().map(|warning| warning.stage(ProgressStage::CollectingFiles)));
                 for (name, fe) in fe_result {
                     self.images_to_check.insert(name, fe);
                 }
             }
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
         Common::print_time(start_time, SystemTime::now(), "check_for_similar_images".to_string());
         true
     }
@@ -498,22 +520,36 @@ impl SimilarImages {
     fn hash_images(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let hash_map_modification = SystemTime::now();
 
-        let loaded_hash_map;
+        let loaded_hash_map: HashMap<String, FileEntry>;
+        let moved_files: MovedFiles<FileEntry>;
+        let volumes = if self.use_cache {
+            Volumes::mounted_for(&self.directories.included_directories)
+        } else {
+            Volumes::default()
+        };
 
         let mut records_already_cached: HashMap<String, FileEntry> = Default::default();
         let mut non_cached_files_to_check: HashMap<String, FileEntry> = Default::default();
 
         if self.use_cache {
-            loaded_hash_map = match load_hashes_from_file(&mut self.text_messages, self.delete_outdated_cache, self.hash_size, self.hash_alg, self.image_filter) {
-                Some(t) => t,
-                None => Default::default(),
-            };
+            let (cached_hash_map, cached_moved_files) = load_cache_from_file_with_identities(
+                &get_cache_file(&self.hash_size, &self.hash_alg, &self.image_filter),
+                &volumes,
+                self.delete_outdated_cache,
+                &mut self.text_messages,
+            )
+            .unwrap_or_default();
+            loaded_hash_map = cached_hash_map;
+            moved_files = cached_moved_files;
 
             for (name, file_entry) in &self.images_to_check {
                 #[allow(clippy::if_same_then_else)]
                 if !loaded_hash_map.contains_key(name) {
-                    // If loaded data doesn't contains current image info
-                    non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                    // If loaded data doesn't contains current image info, but file may be moved or renamed since previous scan
+                    match moved_files.find(&file_entry.path) {
+                        Some(moved_entry) => records_already_cached.insert(name.clone(), moved_entry),
+                        None => non_cached_files_to_check.insert(name.clone(), file_entry.clone()),
+                    };
                 } else if file_entry.size != loaded_hash_map.get(name).unwrap().size || file_entry.modified_date != loaded_hash_map.get(name).unwrap().modified_date {
                     // When size or modification date of image changed, then it is clear that is different image
                     non_cached_files_to_check.insert(name.clone(), file_entry.clone());
@@ -524,6 +560,7 @@ impl SimilarImages {
             }
         } else {
             loaded_hash_map = Default::default();
+            moved_files = Default::default();
             mem::swap(&mut self.images_to_check, &mut non_cached_files_to_check);
         }
 
@@ -534,42 +571,22 @@ impl SimilarImages {
         );
         let hash_map_modification = SystemTime::now();
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let images_to_check = non_cached_files_to_check.len();
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 1,
-                        max_stage: 2,
-                        images_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        images_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
-        };
+        let progress_reporter = ProgressReporter::new(
+            progress_sender,
+            ProgressData::new(ProgressStage::HashingImages, 1, 2)
+                .entries_to_check(non_cached_files_to_check.len())
+                .bytes_to_check(non_cached_files_to_check.values().map(|e| e.size).sum()),
+        );
 
-        //// PROGRESS THREAD END
         let mut vec_file_entry: Vec<(FileEntry, Vec<u8>)> = non_cached_files_to_check
             .into_par_iter()
             .map(|(_s, mut file_entry)| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
+                progress_reporter.add_entries(1);
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
                     return None;
                 }
+                progress_reporter.set_current_path(&file_entry.path);
+                progress_reporter.add_bytes(file_entry.size);
                 let file_name_lowercase = file_entry.path.to_string_lossy().to_lowercase();
 
                 let image;
@@ -632,9 +649,7 @@ impl SimilarImages {
             .map(|file_entry| file_entry.unwrap())
             .collect::<Vec<(FileEntry, Vec<u8>)>>();
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         Common::print_time(hash_map_modification, SystemTime::now(), "sort_images - reading data from files in parallel".to_string());
         let hash_map_modification = SystemTime::now();
@@ -659,13 +674,13 @@ impl SimilarImages {
             for (file_entry, _hash) in vec_file_entry {
                 all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
             }
-            save_hashes_to_file(
+            save_cache_to_file_with_identities(
+                &get_cache_file(&self.hash_size, &self.hash_alg, &self.image_filter),
                 &all_results,
-                &mut self.text_messages,
+                Some(&moved_files),
+                &volumes,
                 self.save_also_as_json,
-                self.hash_size,
-                self.hash_alg,
-                self.image_filter,
+                &mut self.text_messages,
             );
         }
 
@@ -699,50 +714,23 @@ impl SimilarImages {
             }
         }
 
-        //// PROGRESS THREAD START
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_mode_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle = if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_mode_counter = atomic_mode_counter.clone();
-            let all_images = match self.fast_comparing {
-                false => similarity as usize * available_hashes.len(),
-                true => available_hashes.len(),
-            };
-            thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 2,
-                        max_stage: 2,
-                        images_checked: atomic_mode_counter.load(Ordering::Relaxed) as usize,
-                        images_to_check: all_images,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            })
-        } else {
-            thread::spawn(|| {})
+        let all_images = match self.fast_comparing {
+            false => similarity as usize * available_hashes.len(),
+            true => available_hashes.len(),
         };
-        //// PROGRESS THREAD END
+        let progress_reporter = ProgressReporter::new(progress_sender, ProgressData::new(ProgressStage::ComparingImageHashes, 2, 2).entries_to_check(all_images));
+
         if similarity >= 1 {
             if self.fast_comparing {
                 this_time_check_hashes = all_hashes_to_check.clone();
 
                 if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_reporter.end();
                     return false;
                 }
 
                 for (hash, mut vec_file_entry) in this_time_check_hashes.into_iter() {
-                    atomic_mode_counter.fetch_add(1, Ordering::Relaxed);
+                    progress_reporter.add_entries(1);
 
                     // It is not available, because in same iteration, was already taken out
                     if !all_hashes_to_check.contains_key(&hash) {
@@ -771,9 +759,7 @@ impl SimilarImages {
 
                         // This shouldn't be executed too much times, so it should be quite fast to check this
                         if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                            // End thread which send info to gui
-                            progress_thread_run.store(false, Ordering::Relaxed);
-                            progress_thread_handle.join().unwrap();
+                            progress_reporter.end();
                             return false;
                         }
                     }
@@ -793,14 +779,12 @@ impl SimilarImages {
                     this_time_check_hashes = all_hashes_to_check.clone();
 
                     if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                        // End thread which send info to gui
-                        progress_thread_run.store(false, Ordering::Relaxed);
-                        progress_thread_handle.join().unwrap();
+                        progress_reporter.end();
                         return false;
                     }
 
                     for (hash, mut vec_file_entry) in this_time_check_hashes.into_iter() {
-                        atomic_mode_counter.fetch_add(1, Ordering::Relaxed);
+                        progress_reporter.add_entries(1);
 
                         // It is not available, because in same iteration, was already taken out
                         if !all_hashes_to_check.contains_key(&hash) {
@@ -829,9 +813,7 @@ impl SimilarImages {
 
                             // This shouldn't be executed too much times, so it should be quite fast to check this
                             if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                                // End thread which send info to gui
-                                progress_thread_run.store(false, Ordering::Relaxed);
-                                progress_thread_handle.join().unwrap();
+                                progress_reporter.end();
                                 return false;
                             }
                         }
@@ -850,8 +832,7 @@ impl SimilarImages {
             }
         }
 
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_reporter.end();
 
         // Validating if group contains duplicated results
         #[cfg(debug_assertions)]
@@ -965,6 +946,67 @@ impl Default for SimilarImages {
     }
 }
 
+impl Tool for SimilarImages {
+    fn set_search_settings(&mut self, search_settings: SearchSettings) {
+        self.set_included_directory(search_settings.included_directories);
+        self.set_excluded_directory(search_settings.excluded_directories);
+        self.set_reference_directory(search_settings.reference_directories);
+        self.set_excluded_items(search_settings.excluded_items);
+        self.set_allowed_extensions(search_settings.allowed_extensions);
+        self.set_recursive_search(search_settings.recursive_search);
+        #[cfg(target_family = "unix")]
+        self.set_exclude_other_filesystems(search_settings.exclude_other_filesystems);
+    }
+
+    fn run(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_similar_images(stop_receiver, progress_sender);
+    }
+
+    fn load_results(&mut self, results: ExportedResults) {
+        let results = match results.validate_for_tool("similar_images", &mut self.text_messages) {
+            Some(t) => t,
+            None => return,
+        };
+        let to_file_entry = |entry: ExportedEntry| FileEntry {
+            size: entry.size,
+            dimensions: entry.info.unwrap_or_default(),
+            modified_date: entry.modified_date,
+            hash: entry.hash.as_deref().and_then(hex_to_bytes).unwrap_or_default(),
+            similarity: Similarity::Similar(entry.similarity.and_then(|e| e.parse().ok()).unwrap_or(0)),
+            path: entry.path,
+        };
+        self.use_reference_folders = results.has_reference_groups();
+        for (reference, group) in results.into_split_groups() {
+            let group: Vec<FileEntry> = group.into_iter().map(to_file_entry).collect();
+            match reference {
+                Some(reference) if self.use_reference_folders => {
+                    self.information.number_of_duplicates += group.len();
+                    self.similar_referenced_vectors.push((to_file_entry(reference), group));
+                }
+                None if !self.use_reference_folders => {
+                    self.information.number_of_duplicates += group.len() - 1;
+                    self.similar_vectors.push(group);
+                }
+                _ => continue,
+            }
+            self.information.number_of_groups += 1;
+        }
+        self.debug_print();
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_number_of_found_entries(&self) -> usize {
+        self.information.number_of_duplicates
+    }
+}
+
 impl DebugPrint for SimilarImages {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -991,7 +1033,11 @@ impl SaveResults for SimilarImages {
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
             Err(e) => {
-                self.text_messages.errors.push(format!("Failed to create file {}, reason {}", file_name, e));
+                self.text_messages.add_diagnostic(
+                    Diagnostic::error(DiagnosticKind::CannotCreateFile, format!("Failed to create file {}, reason {}", file_name, e))
+                        .path(Path::new(&file_name))
+                        .io_error(&e),
+                );
                 return false;
             }
         };
@@ -1002,7 +1048,11 @@ impl SaveResults for SimilarImages {
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
         ) {
-            self.text_messages.errors.push(format!("Failed to save results to file {}, reason {}", file_name, e));
+            self.text_messages.add_diagnostic(
+                Diagnostic::error(DiagnosticKind::CannotWriteFile, format!("Failed to save results to file {}, reason {}", file_name, e))
+                    .path(Path::new(&file_name))
+                    .io_error(&e),
+            );
             return false;
         }
 
@@ -1033,6 +1083,29 @@ impl SaveResults for SimilarImages {
     }
 }
 
+impl ExportResults for SimilarImages {
+    fn get_exported_results(&self) -> ExportedResults {
+        let to_exported = |file_entry: &FileEntry| {
+            let Similarity::Similar(similarity) = file_entry.similarity;
+            ExportedEntry::new(&file_entry.path, file_entry.size, file_entry.modified_date)
+                .hash(bytes_to_hex(&file_entry.hash))
+                .similarity(similarity.to_string())
+                .info(file_entry.dimensions.clone())
+        };
+        let mut results = ExportedResults::new("similar_images", true);
+        if self.use_reference_folders {
+            for (reference, vector) in &self.similar_referenced_vectors {
+                results.add_referenced_group(to_exported(reference), vector.iter().map(to_exported).collect());
+            }
+        } else {
+            for vector in &self.similar_vectors {
+                results.add_group(vector.iter().map(to_exported).collect());
+            }
+        }
+        results
+    }
+}
+
 impl PrintResults for SimilarImages {
     fn print_results(&self) {
         if !self.similar_vectors.is_empty() {
@@ -1056,85 +1129,33 @@ impl PrintResults for SimilarImages {
 
 pub fn save_hashes_to_file(
     hashmap: &HashMap<String, FileEntry>,
+    volumes: &Volumes,
     text_messages: &mut Messages,
     save_also_as_json: bool,
     hash_size: u8,
     hash_alg: HashAlg,
     image_filter: FilterType,
 ) {
-    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) =
-        open_cache_folder(&get_cache_file(&hash_size, &hash_alg, &image_filter), true, save_also_as_json, &mut text_messages.warnings)
-    {
-        {
-            let writer = BufWriter::new(file_handler.unwrap()); // Unwrap because cannot fail here
-            if let Err(e) = bincode::serialize_into(writer, hashmap) {
-                text_messages
-                    .warnings
-                    .push(format!("Cannot write data to cache file {}, reason {}", cache_file.display(), e));
-                return;
-            }
-        }
-        if save_also_as_json {
-            if let Some(file_handler_json) = file_handler_json {
-                let writer = BufWriter::new(file_handler_json);
-                if let Err(e) = serde_json::to_writer(writer, hashmap) {
-                    text_messages
-                        .warnings
-                        .push(format!("Cannot write data to cache file {}, reason {}", cache_file_json.display(), e));
-                    return;
-                }
-            }
-        }
-
-        text_messages.messages.push(format!("Properly saved to file {} cache entries.", hashmap.len()));
-    }
+    save_cache_to_file_with_identities(
+        &get_cache_file(&hash_size, &hash_alg, &image_filter),
+        hashmap,
+        None,
+        volumes,
+        save_also_as_json,
+        text_messages,
+    );
 }
 
 pub fn load_hashes_from_file(
     text_messages: &mut Messages,
+    volumes: &Volumes,
     delete_outdated_cache: bool,
     hash_size: u8,
     hash_alg: HashAlg,
     image_filter: FilterType,
 ) -> Option<HashMap<String, FileEntry>> {
-    if let Some(((file_handler, cache_file), (file_handler_json, cache_file_json))) =
-        open_cache_folder(&get_cache_file(&hash_size, &hash_alg, &image_filter), false, true, &mut text_messages.warnings)
-    {
-        let mut hashmap_loaded_entries: HashMap<String, FileEntry>;
-        if let Some(file_handler) = file_handler {
-            let reader = BufReader::new(file_handler);
-            hashmap_loaded_entries = match bincode::deserialize_from(reader) {
-                Ok(t) => t,
-                Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load data from cache file {}, reason {}", cache_file.display(), e));
-                    return None;
-                }
-            };
-        } else {
-            let reader = BufReader::new(file_handler_json.unwrap()); // Unwrap cannot fail, because at least one file must be valid
-            hashmap_loaded_entries = match serde_json::from_reader(reader) {
-                Ok(t) => t,
-                Err(e) => {
-                    text_messages
-                        .warnings
-                        .push(format!("Failed to load data from cache file {}, reason {}", cache_file_json.display(), e));
-                    return None;
-                }
-            };
-        }
-
-        // Don't load cache data if destination file not exists
-        if delete_outdated_cache {
-            hashmap_loaded_entries.retain(|src_path, _file_entry| Path::new(src_path).exists());
-        }
-
-        text_messages.messages.push(format!("Properly loaded {} cache entries.", hashmap_loaded_entries.len()));
-
-        return Some(hashmap_loaded_entries);
-    }
-    None
+    load_cache_from_file_with_identities(&get_cache_file(&hash_size, &hash_alg, &image_filter), volumes, delete_outdated_cache, text_messages)
+        .map(|(hashmap, _moved_files)| hashmap)
 }
 
 fn get_cache_file(hash_size: &u8, hash_alg: &HashAlg, image_filter: &FilterType) -> String {
@@ -0,0 +1,135 @@
+#[cfg(target_family = "unix")]
+mod rpc;
+#[cfg(target_family = "unix")]
+mod scans;
+#[cfg(target_family = "unix")]
+mod server;
+
+#[cfg(target_family = "unix")]
+fn main() {
+    use std::fs;
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::Arc;
+
+    use structopt::StructOpt;
+
+    #[derive(Debug, StructOpt)]
+    #[structopt(name = "czkawka_daemon", help_message = HELP_MESSAGE)]
+    struct Args {
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "Path of Unix socket, by default $XDG_RUNTIME_DIR/czkawka.sock or czkawka-<uid>/czkawka.sock in temporary folder is used"
+        )]
+        socket: Option<PathBuf>,
+    }
+
+    let args = Args::from_args();
+    let socket = match args.socket {
+        Some(socket) => socket,
+        None => match default_socket_folder() {
+            Ok(folder) => folder.join("czkawka.sock"),
+            Err(e) => {
+                eprintln!("Failed to prepare folder for socket, reason {}", e);
+                process::exit(1);
+            }
+        },
+    };
+
+    if socket.exists() {
+        if UnixStream::connect(&socket).is_ok() {
+            eprintln!("Other daemon already listens on socket {}", socket.display());
+            process::exit(1);
+        }
+        // Socket left by daemon which was killed
+        if let Err(e) = fs::remove_file(&socket) {
+            eprintln!("Failed to remove old socket {}, reason {}", socket.display(), e);
+            process::exit(1);
+        }
+    }
+
+    let listener = match bind_private_socket(&socket) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Failed to listen on socket {}, reason {}", socket.display(), e);
+            process::exit(1);
+        }
+    };
+
+    println!("Listening on socket {}", socket.display());
+    Arc::new(server::Daemon::new()).serve(&listener);
+}
+
+/// Clients may remove files, so only owner of daemon can connect to it
+/// Socket is created with restrictive umask, because changing its permissions later leaves moment when anyone could connect
+#[cfg(target_family = "unix")]
+fn bind_private_socket(socket: &std::path::Path) -> std::io::Result<std::os::unix::net::UnixListener> {
+    // Daemon has only one thread yet, so umask doesn't change permissions of files created at the same time
+    let old_umask = unsafe { libc::umask(0o177) };
+    let result = std::os::unix::net::UnixListener::bind(socket);
+    unsafe { libc::umask(old_umask) };
+    result
+}
+
+/// $XDG_RUNTIME_DIR is private to user, shared temporary folder is used only through private subfolder with name based on user id
+#[cfg(target_family = "unix")]
+fn default_socket_folder() -> std::io::Result<std::path::PathBuf> {
+    use std::fs;
+    use std::io::{Error, ErrorKind};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::path::PathBuf;
+
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(runtime_dir));
+    }
+
+    let uid = unsafe { libc::getuid() };
+    let folder = std::env::temp_dir().join(format!("czkawka-{}", uid));
+    if let Err(e) = fs::DirBuilder::new().mode(0o700).create(&folder) {
+        if e.kind() != ErrorKind::AlreadyExists {
+            return Err(e);
+        }
+    }
+    // Folder with the same name could be created earlier by other user
+    let metadata = fs::symlink_metadata(&folder)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is not private folder of current user", folder.display()),
+        ));
+    }
+    Ok(folder)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn main() {
+    eprintln!("Daemon uses Unix domain sockets, so it is only available on Unix systems");
+    std::process::exit(1);
+}
+
+#[cfg(target_family = "unix")]
+const HELP_MESSAGE: &str = "Prints help information
+
+Daemon accepts JSON-RPC 2.0 requests over Unix socket, every request and response is written in separate line.
+
+Methods:
+    tools.list                              - names of tools which may be started
+    scan.start { tool, included_directories, excluded_directories, reference_directories, excluded_items, allowed_extensions, recursive_search, exclude_other_filesystems }
+                                            - starts scan, returns its scan_id
+    scan.stop { scan_id }                   - stops running scan
+    scan.status { scan_id }                 - state, progress and messages of scan, all scans are returned without scan_id
+    scan.subscribe { scan_id }              - sends scan.progress and scan.finished notifications of scan to this client
+    scan.remove { scan_id }                 - forgets finished scan and its results
+    results.get { scan_id, offset, limit }  - groups of found files
+    results.delete { scan_id, keep, dry_run, journal, method }        - method is permanent, trash, quarantine or quarantine:<folder>
+    results.link { scan_id, keep, dry_run, journal, link_type }       - link_type is hard, symlink, symlink_relative or reflink
+    results.move { scan_id, keep, dry_run, journal, destination }
+
+Client which started scan receives its scan.progress and scan.finished notifications.
+Actions keep one file from every group, chosen by required keep rules e.g. \"prefer-path:/srv/master,oldest\".
+
+Example:
+    echo '{\"jsonrpc\": \"2.0\", \"method\": \"scan.start\", \"params\": {\"tool\": \"duplicate\", \"included_directories\": [\"/home/rafal\"]}, \"id\": 1}' | nc -U -q 5 $XDG_RUNTIME_DIR/czkawka.sock";
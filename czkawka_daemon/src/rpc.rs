@@ -0,0 +1,70 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+// Errors specific to daemon
+pub const SCAN_NOT_FOUND: i64 = -32001;
+pub const SCAN_RUNNING: i64 = -32002;
+pub const NO_RESULTS: i64 = -32003;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+/// Single JSON-RPC 2.0 request, request without id is notification which doesn't get response
+#[derive(Clone, Debug, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    pub id: Option<Value>,
+}
+
+pub fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(error) => json!({ "jsonrpc": "2.0", "error": error, "id": id }),
+    }
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// Missing params are treated like empty object, so methods with only optional params may be called without them
+pub fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params, reason {}", e)))
+}
+
+pub fn to_value<T: Serialize>(value: T) -> Value {
+    // Serializing of daemon types cannot fail, they don't contain maps with non string keys
+    serde_json::to_value(value).expect("Failed to serialize response")
+}
+
+/// Connection to client, shared between handler of requests and scans which send notifications
+pub type Output = Arc<Mutex<dyn Write + Send>>;
+
+/// Messages are separated by new lines, returns false when client is disconnected
+pub fn send_message(output: &Output, message: &Value) -> bool {
+    let mut line = message.to_string();
+    line.push('\n');
+    let mut output = output.lock().unwrap();
+    output.write_all(line.as_bytes()).and_then(|_| output.flush()).is_ok()
+}
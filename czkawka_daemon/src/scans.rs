@@ -0,0 +1,412 @@
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use czkawka_core::common_apply::{apply_to_results, ApplyAction};
+use czkawka_core::common_dir_traversal::CheckingMethod;
+use czkawka_core::common_export::ExportedResults;
+use czkawka_core::common_journal::Journal;
+use czkawka_core::common_keep_policy::KeepPolicy;
+use czkawka_core::common_messages::{Diagnostic, DiagnosticKind, Messages};
+use czkawka_core::common_progress::ProgressData;
+use czkawka_core::common_tool::{SearchSettings, Tool};
+use czkawka_core::{
+    bad_extensions::BadExtensions, big_file::BigFile, broken_files::BrokenFiles, duplicate::DuplicateFinder, empty_files::EmptyFiles, empty_folder::EmptyFolder,
+    invalid_symlinks::InvalidSymlinks, same_music::SameMusic, similar_images::SimilarImages, similar_videos::SimilarVideos, temporary::Temporary,
+};
+
+use crate::rpc::{notification, send_message, to_value, Output, RpcError, INVALID_PARAMS, NO_RESULTS, SCAN_NOT_FOUND, SCAN_RUNNING};
+
+/// Names of tools which may be started, same as names used in exported results
+pub const TOOLS: [&str; 11] = [
+    "duplicate",
+    "empty_files",
+    "empty_folders",
+    "big_files",
+    "temporary",
+    "invalid_symlinks",
+    "broken_files",
+    "bad_extensions",
+    "same_music",
+    "similar_images",
+    "similar_videos",
+];
+
+/// Tools use their default settings, only search settings are configurable
+fn create_tool(name: &str) -> Option<Box<dyn Tool + Send>> {
+    Some(match name {
+        "duplicate" => {
+            // Finder doesn't have default checking method
+            let mut duplicate_finder = DuplicateFinder::new();
+            duplicate_finder.set_check_method(CheckingMethod::Hash);
+            Box::new(duplicate_finder)
+        }
+        "empty_files" => Box::new(EmptyFiles::new()),
+        "empty_folders" => Box::new(EmptyFolder::new()),
+        "big_files" => Box::new(BigFile::new()),
+        "temporary" => Box::new(Temporary::new()),
+        "invalid_symlinks" => Box::new(InvalidSymlinks::new()),
+        "broken_files" => Box::new(BrokenFiles::new()),
+        "bad_extensions" => Box::new(BadExtensions::new()),
+        "same_music" => Box::new(SameMusic::new()),
+        "similar_images" => Box::new(SimilarImages::new()),
+        "similar_videos" => Box::new(SimilarVideos::new()),
+        _ => return None,
+    })
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StartParams {
+    pub tool: String,
+    pub included_directories: Vec<PathBuf>,
+    #[serde(default)]
+    pub excluded_directories: Vec<PathBuf>,
+    #[serde(default)]
+    pub reference_directories: Vec<PathBuf>,
+    #[serde(default)]
+    pub excluded_items: Vec<String>,
+    #[serde(default)]
+    pub allowed_extensions: Vec<String>,
+    #[serde(default = "default_true")]
+    pub recursive_search: bool,
+    #[cfg(target_family = "unix")]
+    #[serde(default)]
+    pub exclude_other_filesystems: bool,
+}
+
+impl StartParams {
+    fn search_settings(self) -> SearchSettings {
+        SearchSettings {
+            included_directories: self.included_directories,
+            excluded_directories: self.excluded_directories,
+            reference_directories: self.reference_directories,
+            excluded_items: self.excluded_items,
+            allowed_extensions: self.allowed_extensions.join(","),
+            recursive_search: self.recursive_search,
+            #[cfg(target_family = "unix")]
+            exclude_other_filesystems: self.exclude_other_filesystems,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanState {
+    Running,
+    /// Stop was requested, but tool didn't notice it yet
+    Stopping,
+    Finished,
+    Stopped,
+    /// Tool crashed, e.g. when processing invalid file
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanProgress {
+    pub checking_method: String,
+    pub stage: &'static str,
+    pub current_stage: u8,
+    pub max_stage: u8,
+    pub entries_checked: usize,
+    pub entries_to_check: usize,
+    pub bytes_checked: u64,
+    pub bytes_to_check: u64,
+    pub current_path: Option<PathBuf>,
+}
+
+impl From<&ProgressData> for ScanProgress {
+    fn from(progress_data: &ProgressData) -> Self {
+        Self {
+            checking_method: format!("{:?}", progress_data.checking_method).to_lowercase(),
+            stage: progress_data.stage.name(),
+            current_stage: progress_data.current_stage,
+            max_stage: progress_data.max_stage,
+            entries_checked: progress_data.entries_checked,
+            entries_to_check: progress_data.entries_to_check,
+            bytes_checked: progress_data.bytes_checked,
+            bytes_to_check: progress_data.bytes_to_check,
+            current_path: progress_data.current_path.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanStatus {
+    pub scan_id: u64,
+    pub tool: String,
+    pub state: ScanState,
+    pub progress: Option<ScanProgress>,
+    /// Number of found entries reported by tool when scan finished
+    pub found_entries: usize,
+    pub messages: Messages,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ActionResult {
+    pub processed_files: usize,
+    pub failed_files: usize,
+    pub skipped_groups: usize,
+    pub processed_size: u64,
+    pub messages: Messages,
+}
+
+struct Scan {
+    tool: String,
+    state: ScanState,
+    progress: Option<ScanProgress>,
+    stop_sender: Sender<()>,
+    results: Option<ExportedResults>,
+    found_entries: usize,
+    messages: Messages,
+    /// Clients which receive progress and end of scan
+    subscribers: Vec<Output>,
+    /// Held during whole action, so next action uses results which are already updated by previous one
+    apply_lock: Arc<Mutex<()>>,
+}
+
+impl Scan {
+    fn status(&self, scan_id: u64) -> ScanStatus {
+        ScanStatus {
+            scan_id,
+            tool: self.tool.clone(),
+            state: self.state,
+            progress: self.progress.clone(),
+            found_entries: self.found_entries,
+            messages: self.messages.clone(),
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self.state, ScanState::Running | ScanState::Stopping)
+    }
+
+    fn finished_results(&self, scan_id: u64) -> Result<&ExportedResults, RpcError> {
+        if self.is_running() {
+            return Err(RpcError::new(SCAN_RUNNING, format!("Scan {} is still running", scan_id)));
+        }
+        self.results
+            .as_ref()
+            .ok_or_else(|| RpcError::new(NO_RESULTS, format!("Scan {} didn't finish, so it has no results", scan_id)))
+    }
+}
+
+/// Keeps all scans started by clients, finished scans are kept until they are removed
+#[derive(Default)]
+pub struct ScanManager {
+    scans: Mutex<BTreeMap<u64, Scan>>,
+    next_id: AtomicU64,
+}
+
+impl ScanManager {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts tool in separate thread, given client is informed about progress and end of scan
+    pub fn start(self: &Arc<Self>, params: StartParams, subscriber: Option<Output>) -> Result<u64, RpcError> {
+        let mut tool = create_tool(&params.tool).ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown tool {}, allowed tools: {}", params.tool, TOOLS.join(", "))))?;
+
+        let scan_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (stop_sender, stop_receiver) = crossbeam_channel::unbounded::<()>();
+        let (progress_sender, progress_receiver) = futures::channel::mpsc::unbounded::<ProgressData>();
+        self.scans.lock().unwrap().insert(
+            scan_id,
+            Scan {
+                tool: params.tool.clone(),
+                state: ScanState::Running,
+                progress: None,
+                stop_sender,
+                results: None,
+                found_entries: 0,
+                messages: Messages::new(),
+                subscribers: subscriber.into_iter().collect(),
+                apply_lock: Arc::new(Mutex::new(())),
+            },
+        );
+
+        let progress_manager = self.clone();
+        thread::spawn(move || {
+            for progress_data in futures::executor::block_on_stream(progress_receiver) {
+                progress_manager.update_progress(scan_id, &progress_data);
+            }
+        });
+
+        let manager = self.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                tool.set_search_settings(params.search_settings());
+                tool.run(Some(&stop_receiver), Some(&progress_sender));
+            }));
+            manager.finish(scan_id, if result.is_ok() { Some(tool.as_ref()) } else { None });
+        });
+
+        Ok(scan_id)
+    }
+
+    pub fn stop(&self, scan_id: u64) -> Result<ScanStatus, RpcError> {
+        self.with_scan(scan_id, |scan| {
+            if scan.state == ScanState::Running {
+                scan.state = ScanState::Stopping;
+                // Tool may have already ended and dropped receiver
+                let _ = scan.stop_sender.send(());
+            }
+            Ok(scan.status(scan_id))
+        })
+    }
+
+    pub fn status(&self, scan_id: u64) -> Result<ScanStatus, RpcError> {
+        self.with_scan(scan_id, |scan| Ok(scan.status(scan_id)))
+    }
+
+    pub fn statuses(&self) -> Vec<ScanStatus> {
+        self.scans.lock().unwrap().iter().map(|(scan_id, scan)| scan.status(*scan_id)).collect()
+    }
+
+    pub fn subscribe(&self, scan_id: u64, subscriber: Output) -> Result<ScanStatus, RpcError> {
+        self.with_scan(scan_id, |scan| {
+            if scan.is_running() && !scan.subscribers.iter().any(|e| Arc::ptr_eq(e, &subscriber)) {
+                scan.subscribers.push(subscriber);
+            }
+            Ok(scan.status(scan_id))
+        })
+    }
+
+    /// Forgets finished scan with its results
+    pub fn remove(&self, scan_id: u64) -> Result<(), RpcError> {
+        let mut scans = self.scans.lock().unwrap();
+        match scans.get(&scan_id) {
+            Some(scan) if scan.is_running() => Err(RpcError::new(SCAN_RUNNING, format!("Scan {} is still running", scan_id))),
+            Some(scan) if scan.apply_lock.try_lock().is_err() => Err(RpcError::new(SCAN_RUNNING, format!("Action on results of scan {} is still performed", scan_id))),
+            Some(_) => {
+                scans.remove(&scan_id);
+                Ok(())
+            }
+            None => Err(scan_not_found(scan_id)),
+        }
+    }
+
+    /// Returns part of result groups, whole results are returned when limit is not given
+    pub fn results(&self, scan_id: u64, offset: usize, limit: Option<usize>) -> Result<Value, RpcError> {
+        self.with_scan(scan_id, |scan| {
+            let results = scan.finished_results(scan_id)?;
+            let groups: Vec<_> = results.groups.iter().skip(offset).take(limit.unwrap_or(usize::MAX)).collect();
+            Ok(json!({
+                "tool": results.tool,
+                "checking_method": results.checking_method,
+                "grouped": results.grouped,
+                "total_groups": results.groups.len(),
+                "groups": groups,
+            }))
+        })
+    }
+
+    /// Applies action to results of finished scan, entries changed by action are removed from results
+    /// Actions on the same scan are performed one after another, other requests are not blocked by them
+    pub fn apply(&self, scan_id: u64, keep: &KeepPolicy, action: &ApplyAction, dryrun: bool, journal: Option<&Journal>) -> Result<ActionResult, RpcError> {
+        let apply_lock = self.with_scan(scan_id, |scan| Ok(scan.apply_lock.clone()))?;
+        let _apply_guard = apply_lock.lock().unwrap();
+        let results = self.with_scan(scan_id, |scan| Ok(scan.finished_results(scan_id)?.clone()))?;
+
+        let mut messages = Messages::new();
+        let info = apply_to_results(results.clone(), keep, action, dryrun, journal, &mut messages);
+
+        if !dryrun {
+            // Checking of files may be slow, so it is done without holding lock of all scans
+            // Results cannot be changed in meantime, because other actions wait for apply lock
+            let mut results = results;
+            results.revalidate();
+            let _ = self.with_scan(scan_id, |scan| {
+                scan.results = Some(results);
+                Ok(())
+            });
+        }
+
+        Ok(ActionResult {
+            processed_files: info.processed_files,
+            failed_files: info.failed_files,
+            skipped_groups: info.skipped_groups,
+            processed_size: info.processed_size,
+            messages,
+        })
+    }
+
+    fn with_scan<T>(&self, scan_id: u64, function: impl FnOnce(&mut Scan) -> Result<T, RpcError>) -> Result<T, RpcError> {
+        match self.scans.lock().unwrap().get_mut(&scan_id) {
+            Some(scan) => function(scan),
+            None => Err(scan_not_found(scan_id)),
+        }
+    }
+
+    fn update_progress(&self, scan_id: u64, progress_data: &ProgressData) {
+        let progress = ScanProgress::from(progress_data);
+        let subscribers = self.with_scan(scan_id, |scan| {
+            // Progress sent after end of scan would overwrite final state
+            if !scan.is_running() {
+                return Ok(Vec::new());
+            }
+            scan.progress = Some(progress.clone());
+            Ok(scan.subscribers.clone())
+        });
+        self.notify(
+            scan_id,
+            subscribers.unwrap_or_default(),
+            "scan.progress",
+            json!({ "scan_id": scan_id, "progress": progress }),
+        );
+    }
+
+    /// Tool is not given when it crashed
+    fn finish(&self, scan_id: u64, tool: Option<&dyn Tool>) {
+        let finished = self.with_scan(scan_id, |scan| {
+            match tool {
+                Some(tool) => {
+                    scan.messages = tool.get_text_messages().clone();
+                    if tool.get_stopped_search() {
+                        scan.state = ScanState::Stopped;
+                    } else {
+                        scan.state = ScanState::Finished;
+                        scan.results = Some(tool.get_exported_results());
+                        scan.found_entries = tool.get_number_of_found_entries();
+                    }
+                }
+                None => {
+                    scan.state = ScanState::Failed;
+                    scan.messages
+                        .add_diagnostic(Diagnostic::error(DiagnosticKind::Other, format!("{} tool crashed", scan.tool)));
+                }
+            }
+            Ok((scan.status(scan_id), std::mem::take(&mut scan.subscribers)))
+        });
+        if let Ok((status, subscribers)) = finished {
+            self.notify(scan_id, subscribers, "scan.finished", to_value(status));
+        }
+    }
+
+    /// Sends notification outside of lock, so slow clients don't block other requests
+    /// Clients which are disconnected stop being subscribers
+    fn notify(&self, scan_id: u64, subscribers: Vec<Output>, method: &str, params: Value) {
+        let message = notification(method, params);
+        let disconnected: Vec<Output> = subscribers.into_iter().filter(|subscriber| !send_message(subscriber, &message)).collect();
+        if !disconnected.is_empty() {
+            let _ = self.with_scan(scan_id, |scan| {
+                scan.subscribers.retain(|subscriber| !disconnected.iter().any(|e| Arc::ptr_eq(e, subscriber)));
+                Ok(())
+            });
+        }
+    }
+}
+
+fn scan_not_found(scan_id: u64) -> RpcError {
+    RpcError::new(SCAN_NOT_FOUND, format!("Scan {} doesn't exist", scan_id))
+}
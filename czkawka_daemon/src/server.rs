@@ -0,0 +1,323 @@
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use czkawka_core::common_apply::ApplyAction;
+use czkawka_core::common_journal::{default_quarantine_folder, Journal};
+use czkawka_core::common_keep_policy::KeepPolicy;
+use czkawka_core::common_remove::RemovalMethod;
+use czkawka_core::duplicate::SymlinkType;
+
+use crate::rpc::{parse_params, response, send_message, to_value, Output, Request, RpcError, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR};
+use crate::scans::{ScanManager, StartParams, TOOLS};
+
+#[derive(Deserialize)]
+struct ScanIdParams {
+    scan_id: u64,
+}
+
+#[derive(Deserialize)]
+struct StatusParams {
+    scan_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ResultsParams {
+    scan_id: u64,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+/// Params shared by all actions, `keep` uses same rules as `apply` command of CLI
+/// `keep` is required, because files not chosen by it are deleted, linked or moved
+#[derive(Deserialize)]
+struct ActionParams {
+    scan_id: u64,
+    keep: String,
+    #[serde(default)]
+    dry_run: bool,
+    journal: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct DeleteParams {
+    #[serde(flatten)]
+    action: ActionParams,
+    #[serde(default = "default_removal_method")]
+    method: String,
+}
+
+#[derive(Deserialize)]
+struct LinkParams {
+    #[serde(flatten)]
+    action: ActionParams,
+    #[serde(default = "default_link_type")]
+    link_type: String,
+}
+
+#[derive(Deserialize)]
+struct MoveParams {
+    #[serde(flatten)]
+    action: ActionParams,
+    destination: PathBuf,
+}
+
+fn default_removal_method() -> String {
+    "permanent".to_string()
+}
+
+fn default_link_type() -> String {
+    "hard".to_string()
+}
+
+fn parse_removal_method(method: &str) -> Result<RemovalMethod, RpcError> {
+    if let Some(folder) = method.strip_prefix("quarantine:").filter(|e| !e.is_empty()) {
+        return Ok(RemovalMethod::Quarantine(PathBuf::from(folder)));
+    }
+    match method {
+        "permanent" => Ok(RemovalMethod::Permanent),
+        "trash" => Ok(RemovalMethod::Trash),
+        "quarantine" => default_quarantine_folder()
+            .map(RemovalMethod::Quarantine)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Couldn't find app data folder for quarantine".to_string())),
+        _ => Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("Unknown removal method {} (allowed: permanent, trash, quarantine, quarantine:<folder>)", method),
+        )),
+    }
+}
+
+fn parse_link_type(link_type: &str) -> Result<ApplyAction, RpcError> {
+    match link_type {
+        "hard" => Ok(ApplyAction::HardLink),
+        "symlink" => Ok(ApplyAction::Symlink(SymlinkType::Absolute)),
+        "symlink_relative" => Ok(ApplyAction::Symlink(SymlinkType::Relative)),
+        "reflink" => Ok(ApplyAction::Reflink),
+        _ => Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("Unknown link type {} (allowed: hard, symlink, symlink_relative, reflink)", link_type),
+        )),
+    }
+}
+
+/// Handles JSON-RPC 2.0 requests of all connected clients
+pub struct Daemon {
+    scans: Arc<ScanManager>,
+}
+
+impl Daemon {
+    pub fn new() -> Self {
+        Self {
+            scans: Arc::new(ScanManager::new()),
+        }
+    }
+
+    /// Accepts clients until listener fails, every client is handled in separate thread
+    pub fn serve(self: &Arc<Self>, listener: &UnixListener) {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let daemon = self.clone();
+                    thread::spawn(move || daemon.handle_connection(stream));
+                }
+                Err(e) => eprintln!("Failed to accept connection, reason {}", e),
+            }
+        }
+    }
+
+    fn handle_connection(&self, stream: UnixStream) {
+        let output: Output = match stream.try_clone() {
+            Ok(t) => Arc::new(Mutex::new(t)),
+            Err(e) => {
+                eprintln!("Failed to handle connection, reason {}", e);
+                return;
+            }
+        };
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(t) => t,
+                Err(_) => break,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line, &output) {
+                if !send_message(&output, &response) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns response to single request or batch of requests, notifications don't get response
+    pub fn handle_message(&self, message: &str, output: &Output) -> Option<Value> {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(t) => t,
+            Err(e) => return Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, format!("Failed to parse message, reason {}", e))))),
+        };
+        match message {
+            Value::Array(batch) if batch.is_empty() => Some(response(Value::Null, Err(RpcError::new(INVALID_REQUEST, "Batch is empty".to_string())))),
+            Value::Array(batch) => {
+                let responses: Vec<Value> = batch.into_iter().filter_map(|request| self.handle_request(request, output)).collect();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(responses))
+                }
+            }
+            request => self.handle_request(request, output),
+        }
+    }
+
+    fn handle_request(&self, request: Value, output: &Output) -> Option<Value> {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let request = match serde_json::from_value::<Request>(request) {
+            Ok(t) if t.jsonrpc == "2.0" => t,
+            Ok(_) => return Some(response(id, Err(RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported".to_string())))),
+            Err(e) => return Some(response(id, Err(RpcError::new(INVALID_REQUEST, format!("Invalid request, reason {}", e))))),
+        };
+        let result = self.call(&request.method, request.params, output);
+        request.id.map(|id| response(id, result))
+    }
+
+    fn call(&self, method: &str, params: Value, output: &Output) -> Result<Value, RpcError> {
+        match method {
+            "tools.list" => Ok(json!(TOOLS)),
+            "scan.start" => {
+                let params: StartParams = parse_params(params)?;
+                let scan_id = self.scans.start(params, Some(output.clone()))?;
+                Ok(json!({ "scan_id": scan_id }))
+            }
+            "scan.stop" => {
+                let params: ScanIdParams = parse_params(params)?;
+                self.scans.stop(params.scan_id).map(to_value)
+            }
+            "scan.status" => {
+                let params: StatusParams = parse_params(params)?;
+                match params.scan_id {
+                    Some(scan_id) => self.scans.status(scan_id).map(to_value),
+                    None => Ok(to_value(self.scans.statuses())),
+                }
+            }
+            "scan.subscribe" => {
+                let params: ScanIdParams = parse_params(params)?;
+                self.scans.subscribe(params.scan_id, output.clone()).map(to_value)
+            }
+            "scan.remove" => {
+                let params: ScanIdParams = parse_params(params)?;
+                self.scans.remove(params.scan_id).map(|_| Value::Null)
+            }
+            "results.get" => {
+                let params: ResultsParams = parse_params(params)?;
+                self.scans.results(params.scan_id, params.offset, params.limit)
+            }
+            "results.delete" => {
+                let params: DeleteParams = parse_params(params)?;
+                let action = ApplyAction::Remove(parse_removal_method(&params.method)?);
+                self.apply(params.action, &action)
+            }
+            "results.link" => {
+                let params: LinkParams = parse_params(params)?;
+                let action = parse_link_type(&params.link_type)?;
+                self.apply(params.action, &action)
+            }
+            "results.move" => {
+                let params: MoveParams = parse_params(params)?;
+                self.apply(params.action, &ApplyAction::Move(params.destination))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+
+    fn apply(&self, params: ActionParams, action: &ApplyAction) -> Result<Value, RpcError> {
+        let keep = KeepPolicy::from_text(&params.keep).map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+        if keep.rules.is_empty() {
+            return Err(RpcError::new(INVALID_PARAMS, "At least one keep rule is required".to_string()));
+        }
+        let journal = params.journal.map(Journal::new);
+        self.scans.apply(params.scan_id, &keep, action, params.dry_run, journal.as_ref()).map(to_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use crate::rpc::{Output, INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR, SCAN_NOT_FOUND};
+    use crate::server::Daemon;
+
+    fn call(daemon: &Daemon, output: &Output, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+        daemon.handle_message(&request.to_string(), output).unwrap()
+    }
+
+    #[test]
+    fn test_scan_and_delete_empty_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("empty"), "").unwrap();
+        fs::write(dir.path().join("not_empty"), "abc").unwrap();
+
+        let daemon = Daemon::new();
+        let written = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let output: Output = written.clone();
+
+        let started = call(&daemon, &output, "scan.start", json!({ "tool": "empty_files", "included_directories": [dir.path()] }));
+        let scan_id = started["result"]["scan_id"].as_u64().unwrap();
+
+        let mut status = Value::Null;
+        for _ in 0..100 {
+            status = call(&daemon, &output, "scan.status", json!({ "scan_id": scan_id }));
+            if status["result"]["state"] != "running" {
+                break;
+            }
+            sleep(Duration::from_millis(50));
+        }
+        assert_eq!(status["result"]["state"], "finished");
+        assert_eq!(status["result"]["found_entries"], 1);
+
+        let results = call(&daemon, &output, "results.get", json!({ "scan_id": scan_id }));
+        assert_eq!(results["result"]["total_groups"], 1);
+        assert_eq!(results["result"]["groups"][0][0]["path"], json!(dir.path().join("empty")));
+
+        // Without keep rules it is unknown which files should be left
+        let deleted = call(&daemon, &output, "results.delete", json!({ "scan_id": scan_id }));
+        assert_eq!(deleted["error"]["code"], INVALID_PARAMS);
+        let deleted = call(&daemon, &output, "results.delete", json!({ "scan_id": scan_id, "keep": "" }));
+        assert_eq!(deleted["error"]["code"], INVALID_PARAMS);
+        assert!(dir.path().join("empty").exists());
+
+        let deleted = call(&daemon, &output, "results.delete", json!({ "scan_id": scan_id, "keep": "newest" }));
+        assert_eq!(deleted["result"]["processed_files"], 1);
+        assert!(!dir.path().join("empty").exists());
+        assert!(dir.path().join("not_empty").exists());
+
+        let results = call(&daemon, &output, "results.get", json!({ "scan_id": scan_id }));
+        assert_eq!(results["result"]["total_groups"], 0);
+
+        let written = String::from_utf8(written.lock().unwrap().clone()).unwrap();
+        assert!(written.lines().any(|line| line.contains("\"scan.finished\"")));
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let daemon = Daemon::new();
+        let output: Output = Arc::new(Mutex::new(Vec::<u8>::new()));
+
+        assert_eq!(daemon.handle_message("{", &output).unwrap()["error"]["code"], PARSE_ERROR);
+        assert_eq!(call(&daemon, &output, "scan.restart", Value::Null)["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(call(&daemon, &output, "scan.status", json!({ "scan_id": 5 }))["error"]["code"], SCAN_NOT_FOUND);
+        // Notifications don't get response
+        assert!(daemon.handle_message(r#"{"jsonrpc": "2.0", "method": "tools.list"}"#, &output).is_none());
+    }
+}
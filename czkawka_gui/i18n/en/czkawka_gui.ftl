@@ -28,6 +28,12 @@ duplicate_case_sensitive_name_tooltip =
 
         Disabling such option will group names without checking if each letter is same size e.g. żoŁD <-> Żołd
 
+duplicate_find_directories = Folders
+duplicate_find_directories_tooltip =
+        When enabled, folders with identical content are found too.
+
+        Groups of files which are inside identical folders are not shown separately.
+
 duplicate_mode_name_combo_box = Name
 duplicate_mode_size_combo_box = Size
 duplicate_mode_hash_combo_box = Hash
@@ -223,6 +229,7 @@ bottom_delete_button = Delete
 bottom_save_button = Save
 bottom_symlink_button = Symlink
 bottom_hardlink_button = Hardlink
+bottom_reflink_button = Reflink
 bottom_move_button = Move
 
 bottom_search_button_tooltip = Start search
@@ -237,6 +244,11 @@ bottom_hardlink_button_tooltip =
         Create hardlinks.
         Only works when at least two results in a group are selected.
         First is unchanged and second and later are hardlinked to first.
+bottom_reflink_button_tooltip =
+        Share content of files using copy-on-write(reflinks).
+        Files stay independent, but use disk space only once.
+        Requires filesystem with reflink support, like Btrfs or XFS.
+        First is unchanged and second and later share content with first.
 bottom_move_button_tooltip = 
         Moves files to chosen directory.
         It copies all files to the directory without preserving the directory tree.
@@ -262,6 +274,8 @@ about_translation_button = Translation
 # Header
 header_setting_button_tooltip = Opens settings dialog.
 header_about_button_tooltip = Opens dialog with info about app.
+header_load_results_button_tooltip = Loads results saved earlier in JSON format.
+header_undo_button_tooltip = Restores files moved to trash, quarantine or other folder and replaced by links.
 
 # Settings
 ## General
@@ -373,6 +387,8 @@ compute_stopped_by_user = Searching was stopped by user
 
 compute_found_duplicates_hash_size = Found { $number_files } duplicates in { $number_groups } groups which took { $size }
 compute_found_duplicates_name = Found { $number_files } duplicates in { $number_groups } groups
+compute_found_duplicates_directories = , { $number_directories } identical folders which took { $size }
+compute_identical_folders = Identical folders - { $number_files } files
 compute_found_empty_folders = Found { $number_files } empty folders
 compute_found_empty_files = Found { $number_files } empty files
 compute_found_big_files = Found { $number_files } big files
@@ -452,6 +468,7 @@ delete_items_label = { $items } files will be deleted.
 delete_items_groups_label = { $items } files from { $groups } groups will be deleted.
 
 hardlink_failed = Failed to hardlink
+reflink_failed = Failed to reflink
 hard_sym_invalid_selection_title_dialog = Invalid selection with some groups
 hard_sym_invalid_selection_label_1 = In some groups there is only one record selected and it will be ignored.
 hard_sym_invalid_selection_label_2 = To be able to hard/sym link these files, at least two results in the group need to be selected.
@@ -471,6 +488,18 @@ search_not_choosing_any_music = ERROR: You must select at least one checkbox wit
 
 include_folders_dialog_title = Folders to include
 exclude_folders_dialog_title = Folders to exclude
+load_results_dialog_title = Results file to load
+load_results_unknown_tool = File contains results of unknown tool
+
+undo_dialog_title = Restore files
+undo_dialog_label = Select operations which should be reverted
+undo_restore_button = Restore
+undo_column_time = Time
+undo_column_action = Action
+undo_column_path = Original path
+undo_column_target = Current location
+undo_no_journal = Couldn't find folder with journal of operations
+undo_restored_files = Restored {$number} files
 
 include_manually_directories_dialog_title = Add directory manually
 
@@ -56,6 +56,7 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
     let notebook_upper = gui_data.upper_notebook.notebook_upper.clone();
     let button_settings = gui_data.header.button_settings.clone();
     let button_app_info = gui_data.header.button_app_info.clone();
+    let button_load_results = gui_data.header.button_load_results.clone();
 
     let main_context = glib::MainContext::default();
     let _guard = main_context.acquire().unwrap();
@@ -67,6 +68,7 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
         notebook_upper.set_sensitive(true);
         button_settings.set_sensitive(true);
         button_app_info.set_sensitive(true);
+        button_load_results.set_sensitive(true);
 
         window_progress.hide();
 
@@ -110,17 +112,24 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
                             duplicates_number = information.number_of_duplicated_files_by_hash;
                             duplicates_size = information.lost_space_by_hash;
                             duplicates_group = information.number_of_groups_by_hash;
-                            entry_info.set_text(
-                                flg!(
-                                    "compute_found_duplicates_hash_size",
+                            let mut text = flg!(
+                                "compute_found_duplicates_hash_size",
+                                generate_translation_hashmap(vec![
+                                    ("number_files", duplicates_number.to_string()),
+                                    ("number_groups", duplicates_group.to_string()),
+                                    ("size", duplicates_size.file_size(options::BINARY).unwrap())
+                                ])
+                            );
+                            if information.number_of_duplicated_directories > 0 {
+                                text += &flg!(
+                                    "compute_found_duplicates_directories",
                                     generate_translation_hashmap(vec![
-                                        ("number_files", duplicates_number.to_string()),
-                                        ("number_groups", duplicates_group.to_string()),
-                                        ("size", duplicates_size.file_size(options::BINARY).unwrap())
+                                        ("number_directories", information.number_of_duplicated_directories.to_string()),
+                                        ("size", information.lost_space_by_directories.file_size(options::BINARY).unwrap())
                                     ])
-                                )
-                                .as_str(),
-                            );
+                                );
+                            }
+                            entry_info.set_text(text.as_str());
                         }
                         CheckingMethod::Size => {
                             duplicates_number = information.number_of_duplicated_files_by_size;
@@ -379,10 +388,51 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
                                     }
                                 }
                                 CheckingMethod::Hash => {
+                                    // Identical folders are shown before files, and groups of files inside them are hidden
+                                    for group in df.get_duplicated_directories().iter().filter(|e| e.similarity.is_none()) {
+                                        let header_text = flg!(
+                                            "compute_identical_folders",
+                                            generate_translation_hashmap(vec![("number_files", group.directories[0].number_of_files.to_string())])
+                                        );
+                                        let values: [(u32, &dyn ToValue); 9] = [
+                                            (ColumnsDuplicates::ActivatableSelectButton as u32, &false),
+                                            (ColumnsDuplicates::SelectionButton as u32, &false),
+                                            (ColumnsDuplicates::Size as u32, (&"".to_string())),
+                                            (ColumnsDuplicates::Name as u32, &header_text),
+                                            (ColumnsDuplicates::Path as u32, (&"".to_string())),
+                                            (ColumnsDuplicates::Modification as u32, &"".to_string()), // No text in 3 column
+                                            (ColumnsDuplicates::ModificationAsSecs as u32, &(0)),
+                                            (ColumnsDuplicates::Color as u32, &(HEADER_ROW_COLOR.to_string())),
+                                            (ColumnsDuplicates::TextColor as u32, &(TEXT_COLOR.to_string())),
+                                        ];
+
+                                        list_store.set(&list_store.append(), &values);
+                                        for entry in &group.directories {
+                                            let (directory, file) = split_path(&entry.path);
+
+                                            let values: [(u32, &dyn ToValue); 9] = [
+                                                (ColumnsDuplicates::ActivatableSelectButton as u32, &true),
+                                                (ColumnsDuplicates::SelectionButton as u32, &false),
+                                                (ColumnsDuplicates::Size as u32, (&entry.size.file_size(options::BINARY).unwrap())),
+                                                (ColumnsDuplicates::Name as u32, &file),
+                                                (ColumnsDuplicates::Path as u32, &directory),
+                                                (
+                                                    ColumnsDuplicates::Modification as u32,
+                                                    &(NaiveDateTime::from_timestamp(entry.modified_date as i64, 0).to_string()),
+                                                ),
+                                                (ColumnsDuplicates::ModificationAsSecs as u32, &(entry.modified_date)),
+                                                (ColumnsDuplicates::Color as u32, &(MAIN_ROW_COLOR.to_string())),
+                                                (ColumnsDuplicates::TextColor as u32, &(TEXT_COLOR.to_string())),
+                                            ];
+
+                                            list_store.set(&list_store.append(), &values);
+                                        }
+                                    }
+
                                     let btreemap = df.get_files_sorted_by_hash();
 
                                     for (_size, vectors_vector) in btreemap.iter().rev() {
-                                        for vector in vectors_vector {
+                                        for vector in vectors_vector.iter().filter(|vector| !df.is_group_in_duplicated_directories(vector)) {
                                             // Sort
                                             let vector = if vector.len() >= 2 {
                                                 let mut vector = vector.clone();
@@ -500,6 +550,7 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
                                 BottomButtonsEnum::Select,
                                 BottomButtonsEnum::Symlink,
                                 BottomButtonsEnum::Hardlink,
+                                BottomButtonsEnum::Reflink,
                                 BottomButtonsEnum::Move,
                             ],
                             duplicates_number > 0,
@@ -1,7 +1,10 @@
 use std::collections::BTreeMap;
 use std::fs;
 use std::fs::Metadata;
+use std::path::Path;
 
+use czkawka_core::common_journal::Journal;
+use czkawka_core::common_remove::RemovalMethod;
 use gtk::prelude::*;
 use gtk::{Align, CheckButton, Dialog, ResponseType, TextView};
 
@@ -268,7 +271,12 @@ pub fn empty_folder_remover(
     check_button_settings_use_trash: &CheckButton,
     text_view_errors: &TextView,
 ) {
-    let use_trash = check_button_settings_use_trash.is_active();
+    let removal_method = if check_button_settings_use_trash.is_active() {
+        RemovalMethod::Trash
+    } else {
+        RemovalMethod::Permanent
+    };
+    let journal = Journal::default_journal();
 
     let model = get_list_store(tree_view);
 
@@ -348,20 +356,15 @@ pub fn empty_folder_remover(
         }
 
         if !error_happened {
-            if !use_trash {
-                match fs::remove_dir_all(get_full_name_from_path_name(&path, &name)) {
-                    Ok(_) => {
-                        model.remove(&iter);
-                    }
-                    Err(_inspected) => error_happened = true,
-                }
-            } else {
-                match trash::delete(get_full_name_from_path_name(&path, &name)) {
-                    Ok(_) => {
-                        model.remove(&iter);
+            match removal_method.remove(Path::new(&get_full_name_from_path_name(&path, &name)), journal.as_ref()) {
+                Ok(journal_warning) => {
+                    if let Some(journal_warning) = journal_warning {
+                        messages += &journal_warning.text;
+                        messages += "\n";
                     }
-                    Err(_inspected) => error_happened = true,
+                    model.remove(&iter);
                 }
+                Err(_inspected) => error_happened = true,
             }
         }
         if error_happened {
@@ -384,7 +387,12 @@ pub fn basic_remove(
     check_button_settings_use_trash: &CheckButton,
     text_view_errors: &TextView,
 ) {
-    let use_trash = check_button_settings_use_trash.is_active();
+    let removal_method = if check_button_settings_use_trash.is_active() {
+        RemovalMethod::Trash
+    } else {
+        RemovalMethod::Permanent
+    };
+    let journal = Journal::default_journal();
 
     let model = get_list_store(tree_view);
 
@@ -416,34 +424,21 @@ pub fn basic_remove(
         let name = model.value(&iter, column_file_name).get::<String>().unwrap();
         let path = model.value(&iter, column_path).get::<String>().unwrap();
 
-        if !use_trash {
-            match fs::remove_file(get_full_name_from_path_name(&path, &name)) {
-                Ok(_) => {
-                    model.remove(&iter);
-                }
-
-                Err(e) => {
-                    messages += flg!(
-                        "delete_file_failed",
-                        generate_translation_hashmap(vec![("name", get_full_name_from_path_name(&path, &name)), ("reason", e.to_string())])
-                    )
-                    .as_str();
+        match removal_method.remove(Path::new(&get_full_name_from_path_name(&path, &name)), journal.as_ref()) {
+            Ok(journal_warning) => {
+                if let Some(journal_warning) = journal_warning {
+                    messages += &journal_warning.text;
                     messages += "\n";
                 }
+                model.remove(&iter);
             }
-        } else {
-            match trash::delete(get_full_name_from_path_name(&path, &name)) {
-                Ok(_) => {
-                    model.remove(&iter);
-                }
-                Err(e) => {
-                    messages += flg!(
-                        "delete_file_failed",
-                        generate_translation_hashmap(vec![("name", get_full_name_from_path_name(&path, &name)), ("reason", e.to_string())])
-                    )
-                    .as_str();
-                    messages += "\n";
-                }
+            Err(e) => {
+                messages += flg!(
+                    "delete_file_failed",
+                    generate_translation_hashmap(vec![("name", get_full_name_from_path_name(&path, &name)), ("reason", e.to_string())])
+                )
+                .as_str();
+                messages += "\n";
             }
         }
     }
@@ -461,7 +456,12 @@ pub fn tree_remove(
     check_button_settings_use_trash: &CheckButton,
     text_view_errors: &TextView,
 ) {
-    let use_trash = check_button_settings_use_trash.is_active();
+    let removal_method = if check_button_settings_use_trash.is_active() {
+        RemovalMethod::Trash
+    } else {
+        RemovalMethod::Permanent
+    };
+    let journal = Journal::default_journal();
 
     let model = get_list_store(tree_view);
 
@@ -513,8 +513,13 @@ pub fn tree_remove(
         for file_name in vec_file_name {
             handle_gtk_pending_event_counter(counter);
             counter += 1;
-            if !use_trash {
-                if let Err(e) = fs::remove_file(get_full_name_from_path_name(&path, &file_name)) {
+            match removal_method.remove(Path::new(&get_full_name_from_path_name(&path, &file_name)), journal.as_ref()) {
+                Ok(Some(journal_warning)) => {
+                    messages += &journal_warning.text;
+                    messages += "\n";
+                }
+                Ok(None) => {}
+                Err(e) => {
                     messages += flg!(
                         "delete_file_failed",
                         generate_translation_hashmap(vec![("name", get_full_name_from_path_name(&path, &file_name)), ("reason", e.to_string())])
@@ -522,13 +527,6 @@ pub fn tree_remove(
                     .as_str();
                     messages += "\n";
                 }
-            } else if let Err(e) = trash::delete(get_full_name_from_path_name(&path, &file_name)) {
-                messages += flg!(
-                    "delete_file_failed",
-                    generate_translation_hashmap(vec![("name", get_full_name_from_path_name(&path, &file_name)), ("reason", e.to_string())])
-                )
-                .as_str();
-                messages += "\n";
             }
 
             vec_path_to_delete.push((path.clone(), file_name.clone()));
@@ -1,11 +1,12 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use gtk::prelude::*;
 use gtk::{Align, CheckButton, Dialog, ResponseType, TextView, TreeIter, TreePath};
 
 use crate::flg;
-use czkawka_core::duplicate::make_hard_link;
+use czkawka_core::common_journal::{record_operation, Journal, JournalAction, JournalEntry};
+use czkawka_core::duplicate::{make_hard_link, make_reflink, make_symlink, SymlinkType};
 
 use crate::gui_structs::gui_data::GuiData;
 use crate::help_functions::*;
@@ -16,6 +17,7 @@ use crate::notebook_enums::*;
 enum TypeOfTool {
     Hardlinking,
     Symlinking,
+    Reflinking,
 }
 
 pub fn connect_button_hardlink_symlink(gui_data: &GuiData) {
@@ -40,6 +42,17 @@ pub fn connect_button_hardlink_symlink(gui_data: &GuiData) {
             glib::MainContext::default().spawn_local(sym_hard_link_things(gui_data.clone(), TypeOfTool::Symlinking));
         });
     }
+
+    // Reflinking
+    {
+        let buttons_reflink = gui_data.bottom_buttons.buttons_reflink.clone();
+
+        let gui_data = gui_data.clone();
+
+        buttons_reflink.connect_clicked(move |_| {
+            glib::MainContext::default().spawn_local(sym_hard_link_things(gui_data.clone(), TypeOfTool::Reflinking));
+        });
+    }
 }
 
 async fn sym_hard_link_things(gui_data: GuiData, hardlinking: TypeOfTool) {
@@ -198,6 +211,7 @@ fn hardlink_symlink(
             break;
         }
     }
+    let journal = Journal::default_journal();
     if hardlinking == TypeOfTool::Hardlinking {
         for symhardlink_data in vec_symhardlink_data {
             for (counter, file_to_hardlink) in symhardlink_data.files_to_symhardlink.into_iter().enumerate() {
@@ -206,13 +220,30 @@ fn hardlink_symlink(
                     add_text_to_text_view(text_view_errors, format!("{} {}, reason {}", flg!("hardlink_failed"), file_to_hardlink, e).as_str());
                     continue;
                 }
+                record_link(
+                    journal.as_ref(),
+                    JournalAction::HardLink,
+                    &file_to_hardlink,
+                    &symhardlink_data.original_data,
+                    text_view_errors,
+                );
+            }
+        }
+    } else if hardlinking == TypeOfTool::Reflinking {
+        // Reflinked files are still independent copies, so there is nothing to save to journal
+        for symhardlink_data in vec_symhardlink_data {
+            for (counter, file_to_reflink) in symhardlink_data.files_to_symhardlink.into_iter().enumerate() {
+                handle_gtk_pending_event_counter(counter);
+                if let Err(e) = make_reflink(Path::new(&symhardlink_data.original_data), Path::new(&file_to_reflink)) {
+                    add_text_to_text_view(text_view_errors, format!("{} {}, reason {}", flg!("reflink_failed"), file_to_reflink, e).as_str());
+                }
             }
         }
     } else {
         for symhardlink_data in vec_symhardlink_data {
             for (counter, file_to_symlink) in symhardlink_data.files_to_symhardlink.into_iter().enumerate() {
                 handle_gtk_pending_event_counter(counter);
-                if let Err(e) = fs::remove_file(&file_to_symlink) {
+                if let Err(e) = make_symlink(Path::new(&symhardlink_data.original_data), Path::new(&file_to_symlink), SymlinkType::Absolute) {
                     add_text_to_text_view(
                         text_view_errors,
                         flg!(
@@ -223,35 +254,13 @@ fn hardlink_symlink(
                     );
                     continue;
                 };
-
-                #[cfg(target_family = "unix")]
-                {
-                    if let Err(e) = std::os::unix::fs::symlink(&symhardlink_data.original_data, &file_to_symlink) {
-                        add_text_to_text_view(
-                            text_view_errors,
-                            flg!(
-                                "delete_file_failed",
-                                generate_translation_hashmap(vec![("name", file_to_symlink.to_string()), ("reason", e.to_string())])
-                            )
-                            .as_str(),
-                        );
-                        continue;
-                    };
-                }
-                #[cfg(target_family = "windows")]
-                {
-                    if let Err(e) = std::os::windows::fs::symlink_file(&symhardlink_data.original_data, &file_to_symlink) {
-                        add_text_to_text_view(
-                            text_view_errors,
-                            flg!(
-                                "delete_file_failed",
-                                generate_translation_hashmap(vec![("name", file_to_symlink.to_string()), ("reason", e.to_string())])
-                            )
-                            .as_str(),
-                        );
-                        continue;
-                    };
-                }
+                record_link(
+                    journal.as_ref(),
+                    JournalAction::Symlink,
+                    &file_to_symlink,
+                    &symhardlink_data.original_data,
+                    text_view_errors,
+                );
             }
         }
     }
@@ -262,6 +271,13 @@ fn hardlink_symlink(
     clean_invalid_headers(&model, column_color, column_path);
 }
 
+fn record_link(journal: Option<&Journal>, action: JournalAction, linked_file: &str, original_file: &str, text_view_errors: &TextView) {
+    let size = fs::metadata(original_file).map(|e| e.len()).unwrap_or(0);
+    if let Some(journal_warning) = record_operation(journal, &JournalEntry::new(action, Path::new(linked_file), size).target(Path::new(original_file))) {
+        add_text_to_text_view(text_view_errors, &journal_warning.text);
+    }
+}
+
 fn create_dialog_non_group(window_main: &gtk::Window) -> Dialog {
     let dialog = gtk::Dialog::builder()
         .title(&flg!("hard_sym_invalid_selection_title_dialog"))
@@ -1,5 +1,6 @@
 use std::path::{Path, PathBuf};
 
+use czkawka_core::common_journal::{record_operation, Journal, JournalAction, JournalEntry};
 use gtk::prelude::*;
 use gtk::{ResponseType, TreePath};
 
@@ -222,6 +223,7 @@ fn move_files_common(
     let mut messages: String = "".to_string();
 
     let mut moved_files: u32 = 0;
+    let journal = Journal::default_journal();
 
     // Save to variable paths of files, and remove it when not removing all occurrences.
     'next_result: for (counter, tree_path) in selected_rows.iter().rev().enumerate() {
@@ -233,6 +235,10 @@ fn move_files_common(
 
         let thing = get_full_name_from_path_name(&path, &file_name);
         let destination_file = destination_folder.join(file_name);
+        let size = match std::fs::symlink_metadata(&thing) {
+            Ok(metadata) if !metadata.is_dir() => metadata.len(),
+            _ => 0,
+        };
         if Path::new(&thing).is_dir() {
             if let Err(e) = fs_extra::dir::move_dir(&thing, &destination_file, &fs_extra::dir::CopyOptions::new()) {
                 messages += flg!("move_folder_failed", generate_translation_hashmap(vec![("name", thing), ("reason", e.to_string())])).as_str();
@@ -247,6 +253,10 @@ fn move_files_common(
                 continue 'next_result;
             }
         }
+        if let Some(journal_warning) = record_operation(journal.as_ref(), &JournalEntry::new(JournalAction::Move, Path::new(&thing), size).target(&destination_file)) {
+            messages += &journal_warning.text;
+            messages += "\n";
+        }
         model.remove(&iter);
         moved_files += 1;
     }
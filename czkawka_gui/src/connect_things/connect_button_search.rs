@@ -8,7 +8,7 @@ use gtk::prelude::*;
 use czkawka_core::bad_extensions::BadExtensions;
 use czkawka_core::big_file::BigFile;
 use czkawka_core::broken_files::BrokenFiles;
-use czkawka_core::common_dir_traversal;
+use czkawka_core::common_progress::ProgressData;
 use czkawka_core::duplicate::DuplicateFinder;
 use czkawka_core::empty_files::EmptyFiles;
 use czkawka_core::empty_folder::EmptyFolder;
@@ -28,22 +28,7 @@ use crate::notebook_enums::*;
 use crate::taskbar_progress::tbp_flags::TBPF_NOPROGRESS;
 use crate::{flg, DEFAULT_MAXIMAL_FILE_SIZE, DEFAULT_MINIMAL_CACHE_SIZE, DEFAULT_MINIMAL_FILE_SIZE};
 
-#[allow(clippy::too_many_arguments)]
-pub fn connect_button_search(
-    gui_data: &GuiData,
-    glib_stop_sender: Sender<Message>,
-    futures_sender_duplicate_files: futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-    futures_sender_empty_files: futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-    futures_sender_empty_folder: futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-    futures_sender_big_file: futures::channel::mpsc::UnboundedSender<big_file::ProgressData>,
-    futures_sender_same_music: futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-    futures_sender_similar_images: futures::channel::mpsc::UnboundedSender<similar_images::ProgressData>,
-    futures_sender_similar_videos: futures::channel::mpsc::UnboundedSender<similar_videos::ProgressData>,
-    futures_sender_temporary: futures::channel::mpsc::UnboundedSender<temporary::ProgressData>,
-    futures_sender_invalid_symlinks: futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-    futures_sender_broken_files: futures::channel::mpsc::UnboundedSender<broken_files::ProgressData>,
-    futures_sender_bad_extensions: futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-) {
+pub fn connect_button_search(gui_data: &GuiData, glib_stop_sender: Sender<Message>, futures_sender: futures::channel::mpsc::UnboundedSender<ProgressData>) {
     let combo_box_image_hash_size = gui_data.main_notebook.combo_box_image_hash_size.clone();
     let combo_box_image_hash_algorithm = gui_data.main_notebook.combo_box_image_hash_algorithm.clone();
     let combo_box_image_resize_algorithm = gui_data.main_notebook.combo_box_image_resize_algorithm.clone();
@@ -56,6 +41,7 @@ pub fn connect_button_search(
     let buttons_search_clone = gui_data.bottom_buttons.buttons_search.clone();
     let check_button_duplicates_use_prehash_cache = gui_data.settings.check_button_duplicates_use_prehash_cache.clone();
     let check_button_duplicate_case_sensitive_name: gtk::CheckButton = gui_data.main_notebook.check_button_duplicate_case_sensitive_name.clone();
+    let check_button_duplicate_find_directories: gtk::CheckButton = gui_data.main_notebook.check_button_duplicate_find_directories.clone();
     let check_button_music_artist: gtk::CheckButton = gui_data.main_notebook.check_button_music_artist.clone();
     let check_button_music_title: gtk::CheckButton = gui_data.main_notebook.check_button_music_title.clone();
     let check_button_music_year: gtk::CheckButton = gui_data.main_notebook.check_button_music_year.clone();
@@ -106,6 +92,7 @@ pub fn connect_button_search(
     let entry_info = gui_data.entry_info.clone();
     let button_settings = gui_data.header.button_settings.clone();
     let button_app_info = gui_data.header.button_app_info.clone();
+    let button_load_results = gui_data.header.button_load_results.clone();
     let check_button_music_approximate_comparison = gui_data.main_notebook.check_button_music_approximate_comparison.clone();
     let check_button_image_fast_compare = gui_data.main_notebook.check_button_image_fast_compare.clone();
     let check_button_settings_save_also_json = gui_data.settings.check_button_settings_save_also_json.clone();
@@ -149,6 +136,7 @@ pub fn connect_button_search(
         notebook_upper.set_sensitive(false);
         button_settings.set_sensitive(false);
         button_app_info.set_sensitive(false);
+        button_load_results.set_sensitive(false);
 
         entry_info.set_text(&flg!("searching_for_data"));
 
@@ -183,10 +171,11 @@ pub fn connect_button_search(
                 let minimal_prehash_cache_file_size = entry_settings_prehash_cache_file_minimal_size.text().as_str().parse::<u64>().unwrap_or(0);
 
                 let case_sensitive_name_comparison = check_button_duplicate_case_sensitive_name.is_active();
+                let find_duplicated_directories = check_button_duplicate_find_directories.is_active();
 
                 let delete_outdated_cache = check_button_settings_duplicates_delete_outdated_cache.is_active();
 
-                let futures_sender_duplicate_files = futures_sender_duplicate_files.clone();
+                let futures_sender = futures_sender.clone();
                 // Find duplicates
                 thread::spawn(move || {
                     let mut df = DuplicateFinder::new();
@@ -207,7 +196,8 @@ pub fn connect_button_search(
                     df.set_use_prehash_cache(use_prehash_cache);
                     df.set_delete_outdated_cache(delete_outdated_cache);
                     df.set_case_sensitive_name_comparison(case_sensitive_name_comparison);
-                    df.find_duplicates(Some(&stop_receiver), Some(&futures_sender_duplicate_files));
+                    df.set_find_duplicated_directories(find_duplicated_directories);
+                    df.find_duplicates(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::Duplicates(df));
                 });
             }
@@ -218,7 +208,7 @@ pub fn connect_button_search(
 
                 get_list_store(&tree_view_empty_files_finder).clear();
 
-                let futures_sender_empty_files = futures_sender_empty_files.clone();
+                let futures_sender = futures_sender.clone();
                 // Find empty files
                 thread::spawn(move || {
                     let mut vf = EmptyFiles::new();
@@ -228,7 +218,7 @@ pub fn connect_button_search(
                     vf.set_recursive_search(recursive_search);
                     vf.set_excluded_items(excluded_items);
                     vf.set_allowed_extensions(allowed_extensions);
-                    vf.find_empty_files(Some(&stop_receiver), Some(&futures_sender_empty_files));
+                    vf.find_empty_files(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::EmptyFiles(vf));
                 });
             }
@@ -239,14 +229,14 @@ pub fn connect_button_search(
 
                 get_list_store(&tree_view_empty_folder_finder).clear();
 
-                let futures_sender_empty_folder = futures_sender_empty_folder.clone();
+                let futures_sender = futures_sender.clone();
                 // Find empty folders
                 thread::spawn(move || {
                     let mut ef = EmptyFolder::new();
                     ef.set_included_directory(included_directories);
                     ef.set_excluded_directory(excluded_directories);
                     ef.set_excluded_items(excluded_items);
-                    ef.find_empty_folders(Some(&stop_receiver), Some(&futures_sender_empty_folder));
+                    ef.find_empty_folders(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::EmptyFolders(ef));
                 });
             }
@@ -259,7 +249,7 @@ pub fn connect_button_search(
 
                 let numbers_of_files_to_check = entry_big_files_number.text().as_str().parse::<usize>().unwrap_or(50);
 
-                let futures_sender_big_file = futures_sender_big_file.clone();
+                let futures_sender = futures_sender.clone();
                 // Find big files
                 thread::spawn(move || {
                     let mut bf = BigFile::new();
@@ -270,7 +260,7 @@ pub fn connect_button_search(
                     bf.set_excluded_items(excluded_items);
                     bf.set_allowed_extensions(allowed_extensions);
                     bf.set_number_of_files_to_check(numbers_of_files_to_check);
-                    bf.find_big_files(Some(&stop_receiver), Some(&futures_sender_big_file));
+                    bf.find_big_files(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::BigFiles(bf));
                 });
             }
@@ -281,7 +271,7 @@ pub fn connect_button_search(
 
                 get_list_store(&tree_view_temporary_files_finder).clear();
 
-                let futures_sender_temporary = futures_sender_temporary.clone();
+                let futures_sender = futures_sender.clone();
                 // Find temporary files
                 thread::spawn(move || {
                     let mut tf = Temporary::new();
@@ -290,7 +280,7 @@ pub fn connect_button_search(
                     tf.set_excluded_directory(excluded_directories);
                     tf.set_recursive_search(recursive_search);
                     tf.set_excluded_items(excluded_items);
-                    tf.find_temporary_files(Some(&stop_receiver), Some(&futures_sender_temporary));
+                    tf.find_temporary_files(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::Temporary(tf));
                 });
             }
@@ -320,7 +310,7 @@ pub fn connect_button_search(
 
                 let fast_compare = check_button_image_fast_compare.is_active();
 
-                let futures_sender_similar_images = futures_sender_similar_images.clone();
+                let futures_sender = futures_sender.clone();
                 // Find similar images
                 thread::spawn(move || {
                     let mut sf = SimilarImages::new();
@@ -342,7 +332,7 @@ pub fn connect_button_search(
                     sf.set_exclude_images_with_same_size(ignore_same_size);
                     sf.set_fast_comparing(fast_compare);
                     sf.set_save_also_as_json(save_also_as_json);
-                    sf.find_similar_images(Some(&stop_receiver), Some(&futures_sender_similar_images));
+                    sf.find_similar_images(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::SimilarImages(sf));
                 });
             }
@@ -359,7 +349,7 @@ pub fn connect_button_search(
 
                 let ignore_same_size = check_button_video_ignore_same_size.is_active();
 
-                let futures_sender_similar_videos = futures_sender_similar_videos.clone();
+                let futures_sender = futures_sender.clone();
                 // Find similar videos
                 thread::spawn(move || {
                     let mut sf = SimilarVideos::new();
@@ -377,7 +367,7 @@ pub fn connect_button_search(
                     sf.set_delete_outdated_cache(delete_outdated_cache);
                     sf.set_exclude_videos_with_same_size(ignore_same_size);
                     sf.set_save_also_as_json(save_also_as_json);
-                    sf.find_similar_videos(Some(&stop_receiver), Some(&futures_sender_similar_videos));
+                    sf.find_similar_videos(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::SimilarVideos(sf));
                 });
             }
@@ -412,7 +402,7 @@ pub fn connect_button_search(
                 }
 
                 if music_similarity != MusicSimilarity::NONE {
-                    let futures_sender_same_music = futures_sender_same_music.clone();
+                    let futures_sender = futures_sender.clone();
                     // Find Similar music
                     thread::spawn(move || {
                         let mut mf = SameMusic::new();
@@ -428,7 +418,7 @@ pub fn connect_button_search(
                         mf.set_music_similarity(music_similarity);
                         mf.set_approximate_comparison(approximate_comparison);
                         mf.set_save_also_as_json(save_also_as_json);
-                        mf.find_same_music(Some(&stop_receiver), Some(&futures_sender_same_music));
+                        mf.find_same_music(Some(&stop_receiver), Some(&futures_sender));
                         let _ = glib_stop_sender.send(Message::SameMusic(mf));
                     });
                 } else {
@@ -448,7 +438,7 @@ pub fn connect_button_search(
 
                 get_list_store(&tree_view_invalid_symlinks).clear();
 
-                let futures_sender_invalid_symlinks = futures_sender_invalid_symlinks.clone();
+                let futures_sender = futures_sender.clone();
 
                 thread::spawn(move || {
                     let mut isf = InvalidSymlinks::new();
@@ -458,7 +448,7 @@ pub fn connect_button_search(
                     isf.set_recursive_search(recursive_search);
                     isf.set_excluded_items(excluded_items);
                     isf.set_allowed_extensions(allowed_extensions);
-                    isf.find_invalid_links(Some(&stop_receiver), Some(&futures_sender_invalid_symlinks));
+                    isf.find_invalid_links(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::InvalidSymlinks(isf));
                 });
             }
@@ -469,7 +459,7 @@ pub fn connect_button_search(
 
                 get_list_store(&tree_view_broken_files).clear();
 
-                let futures_sender_broken_files = futures_sender_broken_files.clone();
+                let futures_sender = futures_sender.clone();
 
                 thread::spawn(move || {
                     let mut br = BrokenFiles::new();
@@ -481,7 +471,7 @@ pub fn connect_button_search(
                     br.set_use_cache(use_cache);
                     br.set_allowed_extensions(allowed_extensions);
                     br.set_save_also_as_json(save_also_as_json);
-                    br.find_broken_files(Some(&stop_receiver), Some(&futures_sender_broken_files));
+                    br.find_broken_files(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::BrokenFiles(br));
                 });
             }
@@ -492,7 +482,7 @@ pub fn connect_button_search(
 
                 get_list_store(&tree_view_bad_extensions).clear();
 
-                let futures_sender_bad_extensions = futures_sender_bad_extensions.clone();
+                let futures_sender = futures_sender.clone();
                 // Find Similar music
                 thread::spawn(move || {
                     let mut be = BadExtensions::new();
@@ -504,7 +494,7 @@ pub fn connect_button_search(
                     be.set_maximal_file_size(maximal_file_size);
                     be.set_allowed_extensions(allowed_extensions);
                     be.set_recursive_search(recursive_search);
-                    be.find_bad_extensions_files(Some(&stop_receiver), Some(&futures_sender_bad_extensions));
+                    be.find_bad_extensions_files(Some(&stop_receiver), Some(&futures_sender));
                     let _ = glib_stop_sender.send(Message::BadExtensions(be));
                 });
             }
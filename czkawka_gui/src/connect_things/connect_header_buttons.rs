@@ -1,6 +1,27 @@
+use glib::Sender;
 use gtk::prelude::*;
+use gtk::ResponseType;
 
+use czkawka_core::bad_extensions::BadExtensions;
+use czkawka_core::big_file::BigFile;
+use czkawka_core::broken_files::BrokenFiles;
+use czkawka_core::common_export::ExportedResults;
+use czkawka_core::common_journal::Journal;
+use czkawka_core::common_tool::Tool;
+use czkawka_core::duplicate::DuplicateFinder;
+use czkawka_core::empty_files::EmptyFiles;
+use czkawka_core::empty_folder::EmptyFolder;
+use czkawka_core::invalid_symlinks::InvalidSymlinks;
+use czkawka_core::same_music::SameMusic;
+use czkawka_core::similar_images::SimilarImages;
+use czkawka_core::similar_videos::SimilarVideos;
+use czkawka_core::temporary::Temporary;
+
+use crate::flg;
 use crate::gui_structs::gui_data::GuiData;
+use crate::help_functions::{add_text_to_text_view, get_dialog_box_child, get_list_store, Message};
+use crate::localizer_core::generate_translation_hashmap;
+use crate::notebook_enums::NotebookMainEnum;
 
 pub fn connect_button_about(gui_data: &GuiData) {
     let about_dialog = gui_data.about.about_dialog.clone();
@@ -15,3 +36,166 @@ pub fn connect_button_about(gui_data: &GuiData) {
         });
     });
 }
+
+pub fn connect_button_load_results(gui_data: &GuiData, glib_stop_sender: Sender<Message>) {
+    let button_load_results = gui_data.header.button_load_results.clone();
+    let window_main = gui_data.window_main.clone();
+    let entry_info = gui_data.entry_info.clone();
+    let notebook_main = gui_data.main_notebook.notebook_main.clone();
+    let main_tree_views = gui_data.main_notebook.get_main_tree_views();
+    button_load_results.connect_clicked(move |_| {
+        let file_chooser = gtk::FileChooserDialog::builder()
+            .title(&flg!("load_results_dialog_title"))
+            .action(gtk::FileChooserAction::Open)
+            .transient_for(&window_main)
+            .modal(true)
+            .build();
+        file_chooser.add_button(&flg!("general_ok_button"), ResponseType::Ok);
+        file_chooser.add_button(&flg!("general_close_button"), ResponseType::Cancel);
+        file_chooser.show_all();
+
+        let entry_info = entry_info.clone();
+        let notebook_main = notebook_main.clone();
+        let main_tree_views = main_tree_views.clone();
+        let glib_stop_sender = glib_stop_sender.clone();
+        file_chooser.connect_response(move |file_chooser, response_type| {
+            if response_type == ResponseType::Ok {
+                if let Some(file_name) = file_chooser.filename() {
+                    match ExportedResults::load_from_file(&file_name.to_string_lossy()) {
+                        Ok(results) => {
+                            if let Some((notebook_enum, message)) = load_results_into_tool(results) {
+                                let page = notebook_enum as usize;
+                                get_list_store(&main_tree_views[page]).clear();
+                                notebook_main.set_current_page(Some(page as u32));
+                                let _ = glib_stop_sender.send(message);
+                            } else {
+                                entry_info.set_text(&flg!("load_results_unknown_tool"));
+                            }
+                        }
+                        Err(diagnostic) => entry_info.set_text(&diagnostic.text),
+                    }
+                }
+            }
+            file_chooser.close();
+        });
+    });
+}
+
+pub fn connect_button_undo(gui_data: &GuiData) {
+    let button_undo = gui_data.header.button_undo.clone();
+    let window_main = gui_data.window_main.clone();
+    let entry_info = gui_data.entry_info.clone();
+    let text_view_errors = gui_data.text_view_errors.clone();
+    button_undo.connect_clicked(move |_| {
+        let journal = match Journal::default_journal() {
+            Some(t) => t,
+            None => {
+                entry_info.set_text(&flg!("undo_no_journal"));
+                return;
+            }
+        };
+        let entries = match journal.load() {
+            Ok(t) => t,
+            Err(diagnostic) => {
+                entry_info.set_text(&diagnostic.text);
+                return;
+            }
+        };
+
+        let dialog = gtk::Dialog::builder()
+            .title(&flg!("undo_dialog_title"))
+            .transient_for(&window_main)
+            .modal(true)
+            .default_width(800)
+            .default_height(400)
+            .build();
+        dialog.add_button(&flg!("undo_restore_button"), ResponseType::Ok);
+        dialog.add_button(&flg!("general_close_button"), ResponseType::Cancel);
+
+        // Index of entry in journal, time, action, original path, target
+        let list_store = gtk::ListStore::new(&[
+            u32::static_type(),
+            String::static_type(),
+            String::static_type(),
+            String::static_type(),
+            String::static_type(),
+        ]);
+        for (index, entry) in entries.iter().enumerate().rev().filter(|(_, e)| e.can_be_undone()) {
+            let target = entry.target.as_ref().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+            let values: [(u32, &dyn ToValue); 5] = [
+                (0, &(index as u32)),
+                (1, &entry.formatted_time()),
+                (2, &format!("{:?}", entry.action)),
+                (3, &entry.original_path.to_string_lossy().to_string()),
+                (4, &target),
+            ];
+            list_store.set(&list_store.append(), &values);
+        }
+
+        let tree_view = gtk::TreeView::with_model(&list_store);
+        tree_view.selection().set_mode(gtk::SelectionMode::Multiple);
+        for (column_index, title) in [
+            (1, flg!("undo_column_time")),
+            (2, flg!("undo_column_action")),
+            (3, flg!("undo_column_path")),
+            (4, flg!("undo_column_target")),
+        ] {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.set_title(&title);
+            column.set_resizable(true);
+            column.pack_start(&renderer, true);
+            column.add_attribute(&renderer, "text", column_index);
+            tree_view.append_column(&column);
+        }
+
+        let scrolled_window = gtk::ScrolledWindow::builder().hexpand(true).vexpand(true).build();
+        scrolled_window.add(&tree_view);
+        let internal_box = get_dialog_box_child(&dialog);
+        internal_box.add(&gtk::Label::new(Some(&flg!("undo_dialog_label"))));
+        internal_box.add(&scrolled_window);
+        dialog.show_all();
+
+        let entry_info = entry_info.clone();
+        let text_view_errors = text_view_errors.clone();
+        dialog.connect_response(move |dialog, response_type| {
+            if response_type == ResponseType::Ok {
+                let (selected_rows, model) = tree_view.selection().selected_rows();
+                let selected_indexes: Vec<usize> = selected_rows
+                    .iter()
+                    .map(|tree_path| model.value(&model.iter(tree_path).unwrap(), 0).get::<u32>().unwrap() as usize)
+                    .collect();
+
+                let (restored, diagnostics) = journal.undo(|index, _| selected_indexes.contains(&index), false);
+                for diagnostic in diagnostics {
+                    add_text_to_text_view(&text_view_errors, &diagnostic.text);
+                }
+                entry_info.set_text(&flg!("undo_restored_files", generate_translation_hashmap(vec![("number", restored.len().to_string())])));
+            }
+            dialog.close();
+        });
+    });
+}
+
+/// Recreates tool which created results, so they can be shown like results of normal search
+fn load_results_into_tool(results: ExportedResults) -> Option<(NotebookMainEnum, Message)> {
+    fn load<T: Tool>(mut tool: T, results: ExportedResults) -> T {
+        tool.load_results(results);
+        tool
+    }
+
+    Some(match results.tool.as_str() {
+        "duplicate" => (NotebookMainEnum::Duplicate, Message::Duplicates(load(DuplicateFinder::new(), results))),
+        "empty_folders" => (NotebookMainEnum::EmptyDirectories, Message::EmptyFolders(load(EmptyFolder::new(), results))),
+        "big_files" => (NotebookMainEnum::BigFiles, Message::BigFiles(load(BigFile::new(), results))),
+        "empty_files" => (NotebookMainEnum::EmptyFiles, Message::EmptyFiles(load(EmptyFiles::new(), results))),
+        "temporary" => (NotebookMainEnum::Temporary, Message::Temporary(load(Temporary::new(), results))),
+        "similar_images" => (NotebookMainEnum::SimilarImages, Message::SimilarImages(load(SimilarImages::new(), results))),
+        "similar_videos" => (NotebookMainEnum::SimilarVideos, Message::SimilarVideos(load(SimilarVideos::new(), results))),
+        "same_music" => (NotebookMainEnum::SameMusic, Message::SameMusic(load(SameMusic::new(), results))),
+        "invalid_symlinks" => (NotebookMainEnum::Symlinks, Message::InvalidSymlinks(load(InvalidSymlinks::new(), results))),
+        "broken_files" => (NotebookMainEnum::BrokenFiles, Message::BrokenFiles(load(BrokenFiles::new(), results))),
+        "bad_extensions" => (NotebookMainEnum::BadExtensions, Message::BadExtensions(load(BadExtensions::new(), results))),
+        _ => return None,
+    })
+}
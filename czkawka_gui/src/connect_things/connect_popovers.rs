@@ -1,8 +1,11 @@
+use std::path::PathBuf;
+
 use gtk::prelude::*;
 use gtk::{ResponseType, TreeIter, Window};
 use regex::Regex;
 
 use czkawka_core::common::Common;
+use czkawka_core::common_keep_policy::{KeepCandidate, KeepPolicy, KeepRule};
 
 use crate::flg;
 use crate::gui_structs::gui_data::GuiData;
@@ -80,14 +83,15 @@ fn popover_reverse(popover: &gtk::Popover, tree_view: &gtk::TreeView, column_but
     popover.popdown();
 }
 
-fn popover_all_except_oldest_newest(
+/// Selects all files in every group except the one kept by rules, or only this file when `select_only_kept` is set
+fn popover_select_by_keep_policy(
     popover: &gtk::Popover,
     tree_view: &gtk::TreeView,
     column_color: i32,
-    column_modification_as_secs: i32,
-    column_file_name: i32,
     column_button_selection: u32,
-    except_oldest: bool,
+    keep_policy: &KeepPolicy,
+    candidate: impl Fn(&gtk::ListStore, &TreeIter) -> KeepCandidate,
+    select_only_kept: bool,
 ) {
     let model = get_list_store(tree_view);
 
@@ -95,14 +99,7 @@ fn popover_all_except_oldest_newest(
         let mut end: bool = false;
         loop {
             let mut tree_iter_array: Vec<TreeIter> = Vec::new();
-            let mut used_index: Option<usize> = None;
-            let mut current_index: usize = 0;
-            let mut modification_time_min_max: u64 = match except_oldest {
-                true => u64::MAX,
-                false => 0,
-            };
-
-            let mut file_length: usize = 0;
+            let mut candidates: Vec<KeepCandidate> = Vec::new();
 
             loop {
                 let color = model.value(&iter, column_color).get::<String>().unwrap();
@@ -112,37 +109,18 @@ fn popover_all_except_oldest_newest(
                     }
                     break;
                 }
+                candidates.push(candidate(&model, &iter));
                 tree_iter_array.push(iter);
-                let modification = model.value(&iter, column_modification_as_secs).get::<u64>().unwrap();
-                let current_file_length = model.value(&iter, column_file_name).get::<String>().unwrap().len();
-                if except_oldest {
-                    if modification < modification_time_min_max || (modification == modification_time_min_max && current_file_length < file_length) {
-                        file_length = current_file_length;
-                        modification_time_min_max = modification;
-                        used_index = Some(current_index);
-                    }
-                } else {
-                    if modification > modification_time_min_max || (modification == modification_time_min_max && current_file_length < file_length) {
-                        file_length = current_file_length;
-                        modification_time_min_max = modification;
-                        used_index = Some(current_index);
-                    }
-                }
-                current_index += 1;
 
                 if !model.iter_next(&iter) {
                     end = true;
                     break;
                 }
             }
-            if used_index == None {
-                continue;
-            }
-            for (index, tree_iter) in tree_iter_array.iter().enumerate() {
-                if index != used_index.unwrap() {
-                    model.set_value(tree_iter, column_button_selection, &true.to_value());
-                } else {
-                    model.set_value(tree_iter, column_button_selection, &false.to_value());
+            if let Some(kept_index) = keep_policy.file_to_keep(&candidates) {
+                for (index, tree_iter) in tree_iter_array.iter().enumerate() {
+                    let selected = (index == kept_index) == select_only_kept;
+                    model.set_value(tree_iter, column_button_selection, &selected.to_value());
                 }
             }
 
@@ -155,6 +133,25 @@ fn popover_all_except_oldest_newest(
     popover.popdown();
 }
 
+fn popover_all_except_oldest_newest(
+    popover: &gtk::Popover,
+    tree_view: &gtk::TreeView,
+    column_color: i32,
+    column_modification_as_secs: i32,
+    column_file_name: i32,
+    column_button_selection: u32,
+    except_oldest: bool,
+) {
+    let rule = if except_oldest { KeepRule::Oldest } else { KeepRule::Newest };
+    let keep_policy = KeepPolicy::new(vec![rule, KeepRule::ShortestName]);
+    let candidate = |model: &gtk::ListStore, iter: &TreeIter| KeepCandidate {
+        path: PathBuf::from(model.value(iter, column_file_name).get::<String>().unwrap()),
+        modified_date: model.value(iter, column_modification_as_secs).get::<u64>().unwrap(),
+        ..Default::default()
+    };
+    popover_select_by_keep_policy(popover, tree_view, column_color, column_button_selection, &keep_policy, candidate, false);
+}
+
 fn popover_one_oldest_newest(
     popover: &gtk::Popover,
     tree_view: &gtk::TreeView,
@@ -164,71 +161,14 @@ fn popover_one_oldest_newest(
     column_button_selection: u32,
     check_oldest: bool,
 ) {
-    let model = get_list_store(tree_view);
-
-    if let Some(iter) = model.iter_first() {
-        let mut end: bool = false;
-        loop {
-            let mut tree_iter_array: Vec<TreeIter> = Vec::new();
-            let mut used_index: Option<usize> = None;
-            let mut current_index: usize = 0;
-            let mut modification_time_min_max: u64 = match check_oldest {
-                true => u64::MAX,
-                false => 0,
-            };
-
-            let mut file_length: usize = 0;
-
-            loop {
-                let color = model.value(&iter, column_color).get::<String>().unwrap();
-                if color == HEADER_ROW_COLOR {
-                    if !model.iter_next(&iter) {
-                        end = true;
-                    }
-                    break;
-                }
-                tree_iter_array.push(iter);
-                let modification = model.value(&iter, column_modification_as_secs).get::<u64>().unwrap();
-                let current_file_length = model.value(&iter, column_file_name).get::<String>().unwrap().len();
-                if check_oldest {
-                    if modification < modification_time_min_max || (modification == modification_time_min_max && current_file_length > file_length) {
-                        file_length = current_file_length;
-                        modification_time_min_max = modification;
-                        used_index = Some(current_index);
-                    }
-                } else {
-                    if modification > modification_time_min_max || (modification == modification_time_min_max && current_file_length > file_length) {
-                        file_length = current_file_length;
-                        modification_time_min_max = modification;
-                        used_index = Some(current_index);
-                    }
-                }
-
-                current_index += 1;
-
-                if !model.iter_next(&iter) {
-                    end = true;
-                    break;
-                }
-            }
-            if used_index == None {
-                continue;
-            }
-            for (index, tree_iter) in tree_iter_array.iter().enumerate() {
-                if index == used_index.unwrap() {
-                    model.set_value(tree_iter, column_button_selection, &true.to_value());
-                } else {
-                    model.set_value(tree_iter, column_button_selection, &false.to_value());
-                }
-            }
-
-            if end {
-                break;
-            }
-        }
-    }
-
-    popover.popdown();
+    let rule = if check_oldest { KeepRule::Oldest } else { KeepRule::Newest };
+    let keep_policy = KeepPolicy::new(vec![rule]);
+    let candidate = |model: &gtk::ListStore, iter: &TreeIter| KeepCandidate {
+        path: PathBuf::from(model.value(iter, column_file_name).get::<String>().unwrap()),
+        modified_date: model.value(iter, column_modification_as_secs).get::<u64>().unwrap(),
+        ..Default::default()
+    };
+    popover_select_by_keep_policy(popover, tree_view, column_color, column_button_selection, &keep_policy, candidate, true);
 }
 
 fn popover_custom_select_unselect(
@@ -518,93 +458,17 @@ fn popover_all_except_biggest_smallest(
     column_button_selection: u32,
     except_biggest: bool,
 ) {
-    let model = get_list_store(tree_view);
-
-    if let Some(iter) = model.iter_first() {
-        let mut end: bool = false;
-        loop {
-            let mut tree_iter_array: Vec<TreeIter> = Vec::new();
-            let mut used_index: Option<usize> = None;
-            let mut current_index: usize = 0;
-            let mut size_as_bytes_min_max: u64 = match except_biggest {
-                true => 0,
-                false => u64::MAX,
-            };
-            let mut number_of_pixels_min_max: u64 = match except_biggest {
-                true => 0,
-                false => u64::MAX,
-            };
-
-            loop {
-                let color = model.value(&iter, column_color).get::<String>().unwrap();
-                if color == HEADER_ROW_COLOR {
-                    if !model.iter_next(&iter) {
-                        end = true;
-                    }
-                    break;
-                }
-                tree_iter_array.push(iter);
-                let size_as_bytes = model.value(&iter, column_size_as_bytes).get::<u64>().unwrap();
-
-                // If dimension exists, then needs to be checked images
-                if let Some(column_dimensions) = column_dimensions {
-                    let dimensions_string = model.value(&iter, column_dimensions).get::<String>().unwrap();
-
-                    let dimensions = change_dimension_to_krotka(dimensions_string);
-                    let number_of_pixels = dimensions.0 * dimensions.1;
-
-                    if except_biggest {
-                        if number_of_pixels > number_of_pixels_min_max || (number_of_pixels == number_of_pixels_min_max && size_as_bytes > size_as_bytes_min_max) {
-                            number_of_pixels_min_max = number_of_pixels;
-                            size_as_bytes_min_max = size_as_bytes;
-                            used_index = Some(current_index);
-                        }
-                    } else {
-                        if number_of_pixels < number_of_pixels_min_max || (number_of_pixels == number_of_pixels_min_max && size_as_bytes < size_as_bytes_min_max) {
-                            number_of_pixels_min_max = number_of_pixels;
-                            size_as_bytes_min_max = size_as_bytes;
-                            used_index = Some(current_index);
-                        }
-                    }
-                } else {
-                    if except_biggest {
-                        if size_as_bytes > size_as_bytes_min_max {
-                            size_as_bytes_min_max = size_as_bytes;
-                            used_index = Some(current_index);
-                        }
-                    } else {
-                        if size_as_bytes < size_as_bytes_min_max {
-                            size_as_bytes_min_max = size_as_bytes;
-                            used_index = Some(current_index);
-                        }
-                    }
-                }
-
-                current_index += 1;
-
-                if !model.iter_next(&iter) {
-                    end = true;
-                    break;
-                }
-            }
-            if used_index == None {
-                continue;
-            }
-            for (index, tree_iter) in tree_iter_array.iter().enumerate() {
-                if index != used_index.unwrap() {
-                    model.set_value(tree_iter, column_button_selection, &true.to_value());
-                } else {
-                    model.set_value(tree_iter, column_button_selection, &false.to_value());
-                }
-            }
-
-            if end {
-                break;
-            }
-        }
-    }
-
-    popover.popdown();
+    // Images are compared by resolution first, their dimensions are known only when column exists
+    let keep_policy = match except_biggest {
+        true => KeepPolicy::new(vec![KeepRule::HighestResolution, KeepRule::Biggest]),
+        false => KeepPolicy::new(vec![KeepRule::LowestResolution, KeepRule::Smallest]),
+    };
+    let candidate = |model: &gtk::ListStore, iter: &TreeIter| KeepCandidate {
+        size: model.value(iter, column_size_as_bytes).get::<u64>().unwrap(),
+        dimensions: column_dimensions.map(|column_dimensions| change_dimension_to_krotka(model.value(iter, column_dimensions).get::<String>().unwrap())),
+        ..Default::default()
+    };
+    popover_select_by_keep_policy(popover, tree_view, column_color, column_button_selection, &keep_policy, candidate, false);
 }
 
 pub fn connect_popovers(gui_data: &GuiData) {
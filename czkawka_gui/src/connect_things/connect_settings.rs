@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::default::Default;
+use std::path::PathBuf;
 
 use directories_next::ProjectDirs;
 use gtk::builders::LabelBuilder;
@@ -10,6 +11,7 @@ use image_hasher::HashAlg;
 
 use crate::flg;
 use czkawka_core::common_messages::Messages;
+use czkawka_core::common_volume::Volumes;
 use czkawka_core::duplicate::HashType;
 
 use crate::gui_structs::gui_data::GuiData;
@@ -105,17 +107,19 @@ pub fn connect_settings(gui_data: &GuiData) {
                 dialog.connect_response(move |dialog, response_type| {
                     if response_type == ResponseType::Ok {
                         let mut messages: Messages = Messages::new();
+                        let volumes = Volumes::mounted();
                         for use_prehash in [true, false] {
-                            for type_of_hash in [HashType::Xxh3, HashType::Blake3, HashType::Crc32].iter() {
-                                if let Some(cache_entries) = czkawka_core::duplicate::load_hashes_from_file(&mut messages, true, type_of_hash, use_prehash) {
-                                    let mut hashmap_to_save: BTreeMap<String, czkawka_core::common_dir_traversal::FileEntry> = Default::default();
+                            for type_of_hash in [HashType::Xxh3, HashType::Blake3, HashType::Crc32, HashType::Sha256, HashType::Sha1, HashType::Md5].iter() {
+                                if let Some(cache_entries) = czkawka_core::duplicate::load_hashes_from_file(&mut messages, &volumes, true, type_of_hash, use_prehash) {
+                                    let mut hashmap_to_save: BTreeMap<PathBuf, czkawka_core::common_dir_traversal::FileEntry> = Default::default();
                                     for (_, vec_file_entry) in cache_entries {
                                         for file_entry in vec_file_entry {
-                                            hashmap_to_save.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
+                                            hashmap_to_save.insert(file_entry.path.clone(), file_entry);
                                         }
                                     }
                                     czkawka_core::duplicate::save_hashes_to_file(
                                         &hashmap_to_save,
+                                        &volumes,
                                         &mut messages,
                                         type_of_hash,
                                         use_prehash,
@@ -146,6 +150,7 @@ pub fn connect_settings(gui_data: &GuiData) {
                 dialog.connect_response(move |dialog, response_type| {
                     if response_type == ResponseType::Ok {
                         let mut messages: Messages = Messages::new();
+                        let volumes = Volumes::mounted();
                         for hash_size in [8, 16, 32, 64].iter() {
                             for image_filter in [
                                 FilterType::Lanczos3,
@@ -157,8 +162,10 @@ pub fn connect_settings(gui_data: &GuiData) {
                             .iter()
                             {
                                 for hash_alg in [HashAlg::Blockhash, HashAlg::Gradient, HashAlg::DoubleGradient, HashAlg::VertGradient, HashAlg::Mean].iter() {
-                                    if let Some(cache_entries) = czkawka_core::similar_images::load_hashes_from_file(&mut messages, true, *hash_size, *hash_alg, *image_filter) {
-                                        czkawka_core::similar_images::save_hashes_to_file(&cache_entries, &mut messages, false, *hash_size, *hash_alg, *image_filter);
+                                    if let Some(cache_entries) =
+                                        czkawka_core::similar_images::load_hashes_from_file(&mut messages, &volumes, true, *hash_size, *hash_alg, *image_filter)
+                                    {
+                                        czkawka_core::similar_images::save_hashes_to_file(&cache_entries, &volumes, &mut messages, false, *hash_size, *hash_alg, *image_filter);
                                     }
                                 }
                             }
@@ -185,8 +192,9 @@ pub fn connect_settings(gui_data: &GuiData) {
                 dialog.connect_response(move |dialog, response_type| {
                     if response_type == ResponseType::Ok {
                         let mut messages: Messages = Messages::new();
-                        if let Some(cache_entries) = czkawka_core::similar_videos::load_hashes_from_file(&mut messages, true) {
-                            czkawka_core::similar_videos::save_hashes_to_file(&cache_entries, &mut messages, false);
+                        let volumes = Volumes::mounted();
+                        if let Some(cache_entries) = czkawka_core::similar_videos::load_hashes_from_file(&mut messages, &volumes, true) {
+                            czkawka_core::similar_videos::save_hashes_to_file(&cache_entries, &volumes, &mut messages, false);
                         }
 
                         messages.messages.push(flg!("cache_properly_cleared"));
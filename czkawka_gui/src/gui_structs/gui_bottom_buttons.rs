@@ -15,12 +15,13 @@ pub struct GuiBottomButtons {
     pub buttons_save: gtk::Button,
     pub buttons_symlink: gtk::Button,
     pub buttons_hardlink: gtk::Button,
+    pub buttons_reflink: gtk::Button,
     pub buttons_move: gtk::Button,
     pub buttons_compare: gtk::Button,
     pub buttons_show_errors: gtk::Button,
     pub buttons_show_upper_notebook: gtk::Button,
-    pub buttons_names: [BottomButtonsEnum; 8],
-    pub buttons_array: [Widget; 8],
+    pub buttons_names: [BottomButtonsEnum; 9],
+    pub buttons_array: [Widget; 9],
 }
 
 impl GuiBottomButtons {
@@ -31,6 +32,7 @@ impl GuiBottomButtons {
         let buttons_save: gtk::Button = builder.object("buttons_save").unwrap();
         let buttons_symlink: gtk::Button = builder.object("buttons_symlink").unwrap();
         let buttons_hardlink: gtk::Button = builder.object("buttons_hardlink").unwrap();
+        let buttons_reflink: gtk::Button = builder.object("buttons_reflink").unwrap();
         let buttons_move: gtk::Button = builder.object("buttons_move").unwrap();
         let buttons_compare: gtk::Button = builder.object("buttons_compare").unwrap();
 
@@ -43,6 +45,7 @@ impl GuiBottomButtons {
         set_icon_of_button(&buttons_save, CZK_ICON_SAVE);
         set_icon_of_button(&buttons_symlink, CZK_ICON_SYMLINK);
         set_icon_of_button(&buttons_hardlink, CZK_ICON_HARDLINK);
+        set_icon_of_button(&buttons_reflink, CZK_ICON_HARDLINK);
         set_icon_of_button(&buttons_move, CZK_ICON_MOVE);
         set_icon_of_button(&buttons_compare, CZK_ICON_COMPARE);
         set_icon_of_button(&buttons_show_errors, CZK_ICON_HIDE_DOWN);
@@ -55,6 +58,7 @@ impl GuiBottomButtons {
             BottomButtonsEnum::Save,
             BottomButtonsEnum::Symlink,
             BottomButtonsEnum::Hardlink,
+            BottomButtonsEnum::Reflink,
             BottomButtonsEnum::Move,
             BottomButtonsEnum::Compare,
         ];
@@ -65,6 +69,7 @@ impl GuiBottomButtons {
             buttons_save.clone().upcast::<Widget>(),
             buttons_symlink.clone().upcast::<Widget>(),
             buttons_hardlink.clone().upcast::<Widget>(),
+            buttons_reflink.clone().upcast::<Widget>(),
             buttons_move.clone().upcast::<Widget>(),
             buttons_compare.clone().upcast::<Widget>(),
         ];
@@ -78,6 +83,7 @@ impl GuiBottomButtons {
             buttons_save,
             buttons_symlink,
             buttons_hardlink,
+            buttons_reflink,
             buttons_move,
             buttons_compare,
             buttons_show_errors,
@@ -93,6 +99,7 @@ impl GuiBottomButtons {
         get_custom_label_from_button_with_image(&self.buttons_save.clone().upcast::<Bin>()).set_text(&flg!("bottom_save_button"));
         get_custom_label_from_button_with_image(&self.buttons_symlink.clone().upcast::<Bin>()).set_text(&flg!("bottom_symlink_button"));
         get_custom_label_from_button_with_image(&self.buttons_hardlink.clone().upcast::<Bin>()).set_text(&flg!("bottom_hardlink_button"));
+        get_custom_label_from_button_with_image(&self.buttons_reflink.clone().upcast::<Bin>()).set_text(&flg!("bottom_reflink_button"));
         get_custom_label_from_button_with_image(&self.buttons_move.clone().upcast::<Bin>()).set_text(&flg!("bottom_move_button"));
 
         // get_custom_label_from_button_with_image(&self.buttons_search.clone()).set_text(&flg!("bottom_search_button"));
@@ -109,6 +116,7 @@ impl GuiBottomButtons {
         self.buttons_save.set_tooltip_text(Some(&flg!("bottom_save_button_tooltip")));
         self.buttons_symlink.set_tooltip_text(Some(&flg!("bottom_symlink_button_tooltip")));
         self.buttons_hardlink.set_tooltip_text(Some(&flg!("bottom_hardlink_button_tooltip")));
+        self.buttons_reflink.set_tooltip_text(Some(&flg!("bottom_reflink_button_tooltip")));
         self.buttons_move.set_tooltip_text(Some(&flg!("bottom_move_button_tooltip")));
 
         self.buttons_show_errors.set_tooltip_text(Some(&flg!("bottom_show_errors_tooltip")));
@@ -1,27 +1,40 @@
 use gtk::prelude::*;
 
 use crate::help_functions::set_icon_of_button;
-use crate::{flg, CZK_ICON_INFO, CZK_ICON_SETTINGS};
+use crate::{flg, CZK_ICON_ADD, CZK_ICON_INFO, CZK_ICON_LEFT, CZK_ICON_SETTINGS};
 
 #[derive(Clone)]
 pub struct GuiHeader {
     pub button_settings: gtk::Button,
     pub button_app_info: gtk::Button,
+    pub button_load_results: gtk::Button,
+    pub button_undo: gtk::Button,
 }
 
 impl GuiHeader {
     pub fn create_from_builder(builder: &gtk::Builder) -> Self {
         let button_settings: gtk::Button = builder.object("button_settings").unwrap();
         let button_app_info: gtk::Button = builder.object("button_app_info").unwrap();
+        let button_load_results: gtk::Button = builder.object("button_load_results").unwrap();
+        let button_undo: gtk::Button = builder.object("button_undo").unwrap();
 
         set_icon_of_button(&button_settings, CZK_ICON_SETTINGS);
         set_icon_of_button(&button_app_info, CZK_ICON_INFO);
+        set_icon_of_button(&button_load_results, CZK_ICON_ADD);
+        set_icon_of_button(&button_undo, CZK_ICON_LEFT);
 
-        Self { button_settings, button_app_info }
+        Self {
+            button_settings,
+            button_app_info,
+            button_load_results,
+            button_undo,
+        }
     }
 
     pub fn update_language(&self) {
         self.button_settings.set_tooltip_text(Some(&flg!("header_setting_button_tooltip")));
         self.button_app_info.set_tooltip_text(Some(&flg!("header_about_button_tooltip")));
+        self.button_load_results.set_tooltip_text(Some(&flg!("header_load_results_button_tooltip")));
+        self.button_undo.set_tooltip_text(Some(&flg!("header_undo_button_tooltip")));
     }
 }
@@ -70,6 +70,7 @@ pub struct GuiMainNotebook {
     pub label_duplicate_check_method: gtk::Label,
     pub label_duplicate_hash_type: gtk::Label,
     pub check_button_duplicate_case_sensitive_name: gtk::CheckButton,
+    pub check_button_duplicate_find_directories: gtk::CheckButton,
 
     pub image_preview_duplicates: gtk::Image,
 
@@ -211,6 +212,7 @@ impl GuiMainNotebook {
 
         //// Check Buttons
         let check_button_duplicate_case_sensitive_name: gtk::CheckButton = builder.object("check_button_duplicate_case_sensitive_name").unwrap();
+        let check_button_duplicate_find_directories: gtk::CheckButton = builder.object("check_button_duplicate_find_directories").unwrap();
         let check_button_music_title: gtk::CheckButton = builder.object("check_button_music_title").unwrap();
         let check_button_music_artist: gtk::CheckButton = builder.object("check_button_music_artist").unwrap();
         let check_button_music_year: gtk::CheckButton = builder.object("check_button_music_year").unwrap();
@@ -317,6 +319,7 @@ impl GuiMainNotebook {
             check_button_video_ignore_same_size,
             check_button_image_fast_compare,
             check_button_duplicate_case_sensitive_name,
+            check_button_duplicate_find_directories,
             evk_tree_view_bad_extensions,
         }
     }
@@ -339,6 +342,7 @@ impl GuiMainNotebook {
 
     pub fn update_language(&self) {
         self.check_button_duplicate_case_sensitive_name.set_label(&flg!("duplicate_case_sensitive_name"));
+        self.check_button_duplicate_find_directories.set_label(&flg!("duplicate_find_directories"));
         self.check_button_music_title.set_label(&flg!("music_title_checkbox"));
         self.check_button_music_artist.set_label(&flg!("music_artist_checkbox"));
         self.check_button_music_year.set_label(&flg!("music_year_checkbox"));
@@ -368,6 +372,8 @@ impl GuiMainNotebook {
         self.combo_box_duplicate_hash_type.set_tooltip_text(Some(&flg!("duplicate_hash_type_tooltip")));
         self.check_button_duplicate_case_sensitive_name
             .set_tooltip_text(Some(&flg!("duplicate_case_sensitive_name_tooltip")));
+        self.check_button_duplicate_find_directories
+            .set_tooltip_text(Some(&flg!("duplicate_find_directories_tooltip")));
 
         self.combo_box_image_hash_size.set_tooltip_text(Some(&flg!("image_hash_size_tooltip")));
         self.label_image_hash_size.set_tooltip_text(Some(&flg!("image_hash_size_tooltip")));
@@ -7,7 +7,7 @@ pub struct HashTypeStruct {
     pub eng_name: &'static str,
     pub hash_type: HashType,
 }
-pub const DUPLICATES_HASH_TYPE_COMBO_BOX: [HashTypeStruct; 3] = [
+pub const DUPLICATES_HASH_TYPE_COMBO_BOX: [HashTypeStruct; 6] = [
     HashTypeStruct {
         eng_name: "Blake3",
         hash_type: HashType::Blake3,
@@ -20,6 +20,18 @@ pub const DUPLICATES_HASH_TYPE_COMBO_BOX: [HashTypeStruct; 3] = [
         eng_name: "XXH3",
         hash_type: HashType::Xxh3,
     },
+    HashTypeStruct {
+        eng_name: "SHA-256",
+        hash_type: HashType::Sha256,
+    },
+    HashTypeStruct {
+        eng_name: "SHA-1",
+        hash_type: HashType::Sha1,
+    },
+    HashTypeStruct {
+        eng_name: "MD5",
+        hash_type: HashType::Md5,
+    },
 ];
 pub struct CheckMethodStruct {
     pub eng_name: &'static str,
@@ -59,6 +59,7 @@ pub enum BottomButtonsEnum {
     Save,
     Symlink,
     Hardlink,
+    Reflink,
     Move,
     Compare,
 }
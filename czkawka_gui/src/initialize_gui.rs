@@ -33,6 +33,7 @@ pub fn initialize_gui(gui_data: &mut GuiData) {
         let buttons_select = gui_data.bottom_buttons.buttons_select.clone();
         let buttons_symlink = gui_data.bottom_buttons.buttons_symlink.clone();
         let buttons_hardlink = gui_data.bottom_buttons.buttons_hardlink.clone();
+        let buttons_reflink = gui_data.bottom_buttons.buttons_reflink.clone();
         let buttons_move = gui_data.bottom_buttons.buttons_move.clone();
         let buttons_compare = gui_data.bottom_buttons.buttons_compare.clone();
 
@@ -43,6 +44,7 @@ pub fn initialize_gui(gui_data: &mut GuiData) {
         buttons_select.hide();
         buttons_symlink.hide();
         buttons_hardlink.hide();
+        buttons_reflink.hide();
         buttons_move.hide();
         buttons_compare.hide();
     }
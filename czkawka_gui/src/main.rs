@@ -11,6 +11,7 @@ use gtk::Application;
 use std::env;
 use std::ffi::OsString;
 
+use czkawka_core::common_progress::ProgressData;
 use czkawka_core::*;
 
 use crate::compute_results::*;
@@ -73,49 +74,9 @@ fn build_ui(application: &Application, arguments: Vec<OsString>) {
     let (glib_stop_sender, glib_stop_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
 
     // Futures progress report
-    let (futures_sender_duplicate_files, futures_receiver_duplicate_files): (
-        futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<common_dir_traversal::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_empty_files, futures_receiver_empty_files): (
-        futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<common_dir_traversal::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_empty_folder, futures_receiver_empty_folder): (
-        futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<common_dir_traversal::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_big_file, futures_receiver_big_files): (
-        futures::channel::mpsc::UnboundedSender<big_file::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<big_file::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_same_music, futures_receiver_same_music): (
-        futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<common_dir_traversal::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_similar_images, futures_receiver_similar_images): (
-        futures::channel::mpsc::UnboundedSender<similar_images::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<similar_images::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_similar_videos, futures_receiver_similar_videos): (
-        futures::channel::mpsc::UnboundedSender<similar_videos::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<similar_videos::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_temporary, futures_receiver_temporary): (
-        futures::channel::mpsc::UnboundedSender<temporary::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<temporary::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_invalid_symlinks, futures_receiver_invalid_symlinks): (
-        futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<common_dir_traversal::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_broken_files, futures_receiver_broken_files): (
-        futures::channel::mpsc::UnboundedSender<broken_files::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<broken_files::ProgressData>,
-    ) = futures::channel::mpsc::unbounded();
-    let (futures_sender_bad_extensions, futures_receiver_bad_extensions): (
-        futures::channel::mpsc::UnboundedSender<common_dir_traversal::ProgressData>,
-        futures::channel::mpsc::UnboundedReceiver<common_dir_traversal::ProgressData>,
+    let (futures_sender, futures_receiver): (
+        futures::channel::mpsc::UnboundedSender<ProgressData>,
+        futures::channel::mpsc::UnboundedReceiver<ProgressData>,
     ) = futures::channel::mpsc::unbounded();
 
     initialize_gui(&mut gui_data);
@@ -137,21 +98,9 @@ fn build_ui(application: &Application, arguments: Vec<OsString>) {
 
     connect_button_delete(&gui_data);
     connect_button_save(&gui_data);
-    connect_button_search(
-        &gui_data,
-        glib_stop_sender,
-        futures_sender_duplicate_files,
-        futures_sender_empty_files,
-        futures_sender_empty_folder,
-        futures_sender_big_file,
-        futures_sender_same_music,
-        futures_sender_similar_images,
-        futures_sender_similar_videos,
-        futures_sender_temporary,
-        futures_sender_invalid_symlinks,
-        futures_sender_broken_files,
-        futures_sender_bad_extensions,
-    );
+    connect_button_load_results(&gui_data, glib_stop_sender.clone());
+    connect_button_undo(&gui_data);
+    connect_button_search(&gui_data, glib_stop_sender, futures_sender);
     connect_button_select(&gui_data);
     connect_button_stop(&gui_data);
     connect_button_hardlink_symlink(&gui_data);
@@ -163,20 +112,7 @@ fn build_ui(application: &Application, arguments: Vec<OsString>) {
     connect_selection_of_directories(&gui_data);
     connect_popovers(&gui_data);
     connect_compute_results(&gui_data, glib_stop_receiver);
-    connect_progress_window(
-        &gui_data,
-        futures_receiver_duplicate_files,
-        futures_receiver_empty_files,
-        futures_receiver_empty_folder,
-        futures_receiver_big_files,
-        futures_receiver_same_music,
-        futures_receiver_similar_images,
-        futures_receiver_similar_videos,
-        futures_receiver_temporary,
-        futures_receiver_invalid_symlinks,
-        futures_receiver_broken_files,
-        futures_receiver_bad_extensions,
-    );
+    connect_progress_window(&gui_data, futures_receiver);
     connect_show_hide_ui(&gui_data);
     connect_settings(&gui_data);
     connect_button_about(&gui_data);
@@ -41,6 +41,7 @@ const DEFAULT_VIDEO_REMOVE_AUTO_OUTDATED_CACHE: bool = false;
 const DEFAULT_IMAGE_REMOVE_AUTO_OUTDATED_CACHE: bool = true;
 const DEFAULT_DUPLICATE_REMOVE_AUTO_OUTDATED_CACHE: bool = true;
 const DEFAULT_DUPLICATE_CASE_SENSITIVE_NAME_CHECKING: bool = false;
+const DEFAULT_DUPLICATE_FIND_DIRECTORIES: bool = false;
 
 const DEFAULT_NUMBER_OF_BIGGEST_FILES: &str = "50";
 const DEFAULT_SIMILAR_IMAGES_SIMILARITY: i32 = 0;
@@ -427,6 +428,7 @@ enum LoadText {
     SimilarVideosIgnoreSameSize,
     MusicApproximateComparison,
     DuplicateNameCaseSensitive,
+    DuplicateFindDirectories,
 }
 
 fn create_hash_map() -> (HashMap<LoadText, String>, HashMap<String, LoadText>) {
@@ -468,6 +470,7 @@ fn create_hash_map() -> (HashMap<LoadText, String>, HashMap<String, LoadText>) {
         (LoadText::SimilarVideosIgnoreSameSize, "similar_videos_ignore_same_size"),
         (LoadText::MusicApproximateComparison, "music_approximate_comparison"),
         (LoadText::DuplicateNameCaseSensitive, "duplicate_name_case_sensitive"),
+        (LoadText::DuplicateFindDirectories, "duplicate_find_directories"),
     ];
     let mut hashmap_ls: HashMap<LoadText, String> = Default::default();
     let mut hashmap_sl: HashMap<String, LoadText> = Default::default();
@@ -623,6 +626,10 @@ pub fn save_configuration(manual_execution: bool, upper_notebook: &GuiUpperNoteb
         hashmap_ls.get(&LoadText::DuplicateNameCaseSensitive).unwrap().to_string(),
         main_notebook.check_button_duplicate_case_sensitive_name.is_active(),
     );
+    saving_struct.save_var(
+        hashmap_ls.get(&LoadText::DuplicateFindDirectories).unwrap().to_string(),
+        main_notebook.check_button_duplicate_find_directories.is_active(),
+    );
     saving_struct.save_var(
         hashmap_ls.get(&LoadText::NumberOfBiggestFiles).unwrap().to_string(),
         main_notebook.entry_big_files_number.text(),
@@ -749,6 +756,7 @@ pub fn load_configuration(
         hashmap_ls.get(&LoadText::DuplicateNameCaseSensitive).unwrap().clone(),
         DEFAULT_DUPLICATE_CASE_SENSITIVE_NAME_CHECKING,
     );
+    let check_button_find_directories = loaded_entries.get_bool(hashmap_ls.get(&LoadText::DuplicateFindDirectories).unwrap().clone(), DEFAULT_DUPLICATE_FIND_DIRECTORIES);
 
     // Setting data
     if manual_execution || loading_at_start {
@@ -867,6 +875,7 @@ pub fn load_configuration(
         save_proper_value_to_combo_box(&main_notebook.combo_box_image_resize_algorithm, combo_box_image_resize_algorithm);
 
         main_notebook.check_button_duplicate_case_sensitive_name.set_active(check_button_case_sensitive_name);
+        main_notebook.check_button_duplicate_find_directories.set_active(check_button_find_directories);
         main_notebook.entry_big_files_number.set_text(&number_of_biggest_files);
         main_notebook.check_button_image_ignore_same_size.set_active(similar_images_ignore_same_size);
         main_notebook.check_button_image_fast_compare.set_active(similar_images_fast_compare);
@@ -879,9 +888,11 @@ pub fn load_configuration(
             if DUPLICATES_CHECK_METHOD_COMBO_BOX[combo_chosen_index as usize].check_method == CheckingMethod::Hash {
                 main_notebook.combo_box_duplicate_hash_type.set_visible(true);
                 main_notebook.label_duplicate_hash_type.set_visible(true);
+                main_notebook.check_button_duplicate_find_directories.set_visible(true);
             } else {
                 main_notebook.combo_box_duplicate_hash_type.set_visible(false);
                 main_notebook.label_duplicate_hash_type.set_visible(false);
+                main_notebook.check_button_duplicate_find_directories.set_visible(false);
             }
 
             if DUPLICATES_CHECK_METHOD_COMBO_BOX[combo_chosen_index as usize].check_method == CheckingMethod::Name {